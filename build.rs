@@ -0,0 +1,208 @@
+// sw: terminal stopwatch (build script)
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+
+    let lock_path = manifest_dir.join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let git_dir = manifest_dir.join(".git");
+    let git_head = git_dir.join("HEAD");
+    if git_head.exists() {
+        println!("cargo:rerun-if-changed={}", git_head.display());
+        // HEAD only changes when switching branches, while committing moves
+        // the branch it points to, which is either a file of its own or
+        // packed with the others
+        let head = fs::read_to_string(&git_head).unwrap_or_default();
+        if let Some(branch) = head.trim().strip_prefix("ref: ") {
+            let branch = git_dir.join(branch);
+            if branch.exists() {
+                println!("cargo:rerun-if-changed={}", branch.display());
+            }
+        }
+        let packed = git_dir.join("packed-refs");
+        if packed.exists() {
+            println!("cargo:rerun-if-changed={}", packed.display());
+        }
+    }
+
+    // the lockfile may be missing, in which case no dependencies are listed
+    let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+
+    let mut out = String::new();
+
+    // licenses and authors are read from the sources which cargo unpacked,
+    // and are unknown if they're elsewhere, such as when vendored
+    let registry = registry_src();
+    out.push_str("pub const DEPENDENCIES: &[Dependency] = &[\n");
+    for (name, version) in direct_dependencies(&lock) {
+        let manifest = registry
+            .iter()
+            .map(|index| index.join(format!("{name}-{version}")).join("Cargo.toml"))
+            .find_map(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let (license, authors) = published_info(&manifest);
+        writeln!(out, "    Dependency {{").unwrap();
+        writeln!(out, "        name: {name:?},").unwrap();
+        writeln!(out, "        version: {version:?},").unwrap();
+        writeln!(out, "        license: {:?},", license.unwrap_or("?")).unwrap();
+        writeln!(out, "        authors: &{authors:?},").unwrap();
+        writeln!(out, "    }},").unwrap();
+    }
+    out.push_str("];\n");
+
+    // build information for `--version`, where the commit and compiler are
+    // unknown if git or rustc can't say
+    let commit = output("git", &["rev-parse", "--short", "HEAD"], &manifest_dir);
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc = output(&rustc, &["--version"], &manifest_dir);
+    // reproducible builds give the time to use
+    let built = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            now.map(|since| since.as_secs()).unwrap_or_default()
+        });
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            let feature = key.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+    writeln!(out, "pub const GIT_COMMIT: Option<&str> = {commit:?};").unwrap();
+    writeln!(out, "pub const RUSTC_VERSION: Option<&str> = {rustc:?};").unwrap();
+    writeln!(out, "/// Seconds since the Unix epoch.").unwrap();
+    writeln!(out, "#[allow(clippy::unreadable_literal)]").unwrap();
+    writeln!(out, "pub const BUILT_AT: u64 = {built};").unwrap();
+    writeln!(out, "pub const FEATURES: &[&str] = &{features:?};").unwrap();
+
+    fs::write(out_dir.join("meta.rs"), out).unwrap();
+}
+
+/// Runs `program` in `dir`, returning the first line it prints if it
+/// succeeds.
+fn output(program: &str, args: &[&str], dir: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let line = stdout.lines().next()?.trim();
+    Some(line.to_string()).filter(|line| !line.is_empty())
+}
+
+/// Returns the value of `key` on a line of TOML written as `key = value`,
+/// without quotes.
+fn value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+    Some(rest.trim().trim_matches('"'))
+}
+
+/// Returns the name and version of each package in a `Cargo.lock`.
+fn locked_packages(lock: &str) -> Vec<(&str, &str)> {
+    let mut packages = Vec::new();
+    let mut name = None;
+    for line in lock.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(found) = value(line, "name") {
+            name = Some(found);
+        } else if let Some(version) = value(line, "version") {
+            if let Some(name) = name.take() {
+                packages.push((name, version));
+            }
+        }
+    }
+    packages
+}
+
+/// Returns the name and version of each package which `sw` depends on
+/// directly, according to a `Cargo.lock`.
+fn direct_dependencies(lock: &str) -> Vec<(&str, &str)> {
+    let packages = locked_packages(lock);
+    let mut deps = Vec::new();
+    let mut in_sw = false;
+    let mut in_deps = false;
+    for line in lock.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            in_sw = false;
+        } else if value(line, "name").is_some() {
+            in_sw = value(line, "name") == Some(env!("CARGO_PKG_NAME"));
+        } else if in_sw && line == "dependencies = [" {
+            in_deps = true;
+        } else if in_deps && line == "]" {
+            in_deps = false;
+        } else if in_deps {
+            // written as "name", or "name version" if several are locked
+            let mut dep = line.trim_end_matches(',').trim_matches('"').split(' ');
+            let name = dep.next().unwrap_or_default();
+            let version = dep.next();
+            deps.extend(packages.iter().find(|(locked, locked_version)| {
+                *locked == name && version.map_or(true, |version| version == *locked_version)
+            }));
+        }
+    }
+    deps
+}
+
+/// Directories in which cargo unpacks the sources of registry packages, one
+/// per registry.
+fn registry_src() -> Vec<PathBuf> {
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")));
+    let Some(src) = cargo_home.map(|home| home.join("registry").join("src")) else {
+        return Vec::new();
+    };
+    fs::read_dir(src)
+        .map(|dir| dir.filter_map(|entry| Some(entry.ok()?.path())).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the license and authors given in a package's `Cargo.toml`, as
+/// normalized by cargo when it was published.
+fn published_info(manifest: &str) -> (Option<&str>, Vec<&str>) {
+    let mut license = None;
+    let mut authors = Vec::new();
+    let mut in_package = false;
+    let mut lines = manifest.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if !in_package {
+            // only the package's own fields are of interest
+        } else if let Some(found) = value(line, "license") {
+            license = Some(found);
+        } else if let Some(list) = value(line, "authors") {
+            // either all on this line, or one author per line after it
+            let mut items = vec![list];
+            if !list.ends_with(']') {
+                items.extend(lines.by_ref().take_while(|line| *line != "]"));
+            }
+            authors = items
+                .into_iter()
+                .flat_map(|item| item.split(','))
+                .map(|author| author.trim_matches(|c| matches!(c, '[' | ']' | '"' | ' ')))
+                .filter(|author| !author.is_empty())
+                .collect();
+        }
+    }
+    (license, authors)
+}
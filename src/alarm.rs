@@ -0,0 +1,155 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use core::time::Duration;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alarm {
+    /// Elapsed time at which the alarm goes off.
+    pub at: Duration,
+    /// Whether the elapsed time was past `at` when last checked.
+    passed: bool,
+    /// When the alarm went off, if it hasn't been acknowledged since.
+    pending_since: Option<Instant>,
+    /// How long the alarm went unacknowledged the last time it went off.
+    pub acked_after: Option<Duration>,
+}
+
+impl Alarm {
+    pub const fn is_passed(&self) -> bool {
+        self.passed
+    }
+
+    /// Whether the alarm went off and is waiting to be acknowledged.
+    pub const fn is_pending(&self) -> bool {
+        self.pending_since.is_some()
+    }
+}
+
+/// Record of an alarm being acknowledged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ack {
+    /// Elapsed time at which the alarm went off.
+    pub at: Duration,
+    /// How long the alarm went unacknowledged.
+    pub after: Duration,
+}
+
+/// Elapsed time thresholds, sorted from earliest to latest.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Alarms {
+    alarms: Vec<Alarm>,
+    acks: Vec<Ack>,
+}
+
+impl Alarms {
+    pub const fn new() -> Self {
+        Self {
+            alarms: Vec::new(),
+            acks: Vec::new(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    /// Every acknowledgement this session, in order.
+    pub fn acks(&self) -> &[Ack] {
+        &self.acks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alarms.is_empty()
+    }
+
+    /// Adds an alarm at `at`, returning `false` if there already was one.
+    ///
+    /// An alarm which is added after the elapsed time has already passed it
+    /// goes off at the next check.
+    pub fn add(&mut self, at: Duration) -> bool {
+        match self.alarms.binary_search_by_key(&at, |alarm| alarm.at) {
+            Ok(_) => false,
+            Err(idx) => {
+                let alarm = Alarm {
+                    at,
+                    passed: false,
+                    pending_since: None,
+                    acked_after: None,
+                };
+                self.alarms.insert(idx, alarm); // @alloc
+                true
+            }
+        }
+    }
+
+    /// Removes the alarm at `idx` in the list, counting from zero.
+    pub fn remove(&mut self, idx: usize) -> Option<Alarm> {
+        (idx < self.alarms.len()).then(|| self.alarms.remove(idx))
+    }
+
+    /// Returns the alarms which the elapsed time of `sw` has passed since
+    /// the last check. They go off at the instant the elapsed time reached
+    /// them, or at `now` if it jumped past them, and are pending until
+    /// acknowledged.
+    ///
+    /// Alarms are rearmed once the elapsed time drops below them, such as
+    /// after a reset.
+    pub fn check(&mut self, sw: &Sw, now: Instant) -> Vec<Alarm> {
+        let elapsed = sw.elapsed_at(now);
+        self.alarms
+            .iter_mut()
+            .filter_map(|alarm| {
+                let was_passed = alarm.passed;
+                alarm.passed = elapsed >= alarm.at;
+                if !alarm.passed {
+                    alarm.pending_since = None;
+                }
+                (alarm.passed && !was_passed).then(|| {
+                    alarm.pending_since = Some(reached_at(sw, alarm.at).unwrap_or(now));
+                    *alarm
+                })
+            })
+            .collect() // @alloc
+    }
+
+    /// Instants at which alarms went off without being acknowledged, and at
+    /// which the running stopwatch `sw` will reach the others.
+    pub fn due(&self, sw: &Sw) -> Vec<Instant> {
+        self.alarms
+            .iter()
+            .filter_map(|alarm| {
+                alarm
+                    .pending_since
+                    .or_else(|| (!alarm.passed).then(|| reached_at(sw, alarm.at)).flatten())
+            })
+            .collect() // @alloc
+    }
+
+    /// Acknowledges the alarms which have gone off, recording how long they
+    /// went unacknowledged as of `now`, and returns the records.
+    pub fn acknowledge(&mut self, now: Instant) -> &[Ack] {
+        let start = self.acks.len();
+        for alarm in &mut self.alarms {
+            if let Some(since) = alarm.pending_since.take() {
+                let after = now.saturating_duration_since(since);
+                alarm.acked_after = Some(after);
+                self.acks.push(Ack {
+                    at: alarm.at,
+                    after,
+                }); // @alloc
+            }
+        }
+        &self.acks[start..]
+    }
+}
+
+/// Instant at which the running stopwatch `sw` reaches an elapsed time of
+/// `at`, unless it had already reached it when last started.
+fn reached_at(sw: &Sw, at: Duration) -> Option<Instant> {
+    sw.start?.checked_add(at.checked_sub(sw.elapsed)?)
+}
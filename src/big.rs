@@ -0,0 +1,78 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+/// Height of a glyph in pixels, before scaling.
+const HEIGHT: usize = 5;
+
+/// Largest scale at which text is drawn, however big the terminal is.
+const MAX_SCALE: usize = 4;
+
+/// Pixels of `chr`, row by row, where `#` is set. Every row of a glyph has
+/// the same width.
+const fn glyph(chr: char) -> Option<[&'static str; HEIGHT]> {
+    Some(match chr {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        ':' => [" ", "#", " ", "#", " "],
+        '.' => [" ", " ", " ", " ", "#"],
+        _ => return None,
+    })
+}
+
+/// Width of `text` in columns when drawn at `scale`, or `None` if it has a
+/// character with no glyph. Since terminal cells are about twice as tall as
+/// they're wide, each pixel is two columns wide and one row tall at a scale
+/// of one.
+fn width(text: &str, scale: usize) -> Option<usize> {
+    let mut width = 0;
+    for (idx, chr) in text.chars().enumerate() {
+        if idx != 0 {
+            width += scale;
+        }
+        width += glyph(chr)?[0].len() * 2 * scale;
+    }
+    Some(width)
+}
+
+/// Largest scale at which `text` fits in a terminal of `cols` columns and
+/// `rows` rows, leaving two rows for the state and the prompt. Returns `None`
+/// if it doesn't fit at all.
+pub fn fit(text: &str, cols: u16, rows: u16) -> Option<usize> {
+    (1..=MAX_SCALE).rev().find(|scale| {
+        width(text, *scale).is_some_and(|width| width <= usize::from(cols))
+            && HEIGHT * scale + 2 <= usize::from(rows)
+    })
+}
+
+/// Draws `text` with blocks at `scale`, returning the rows. Characters with
+/// no glyph are skipped.
+pub fn render(text: &str, scale: usize) -> Vec<String> {
+    let glyphs = text.chars().filter_map(glyph).collect::<Vec<_>>(); // @alloc
+    let mut rows = Vec::with_capacity(HEIGHT * scale); // @alloc
+    for pixel_row in 0..HEIGHT {
+        let mut row = String::new(); // @alloc
+        for (idx, glyph) in glyphs.iter().enumerate() {
+            if idx != 0 {
+                row.extend(core::iter::repeat(' ').take(scale));
+            }
+            for pixel in glyph[pixel_row].chars() {
+                let fill = if pixel == '#' { '█' } else { ' ' };
+                row.extend(core::iter::repeat(fill).take(2 * scale));
+            }
+        }
+        let row = row.trim_end().to_string(); // @alloc
+        for _ in 0..scale {
+            rows.push(row.clone()); // @alloc
+        }
+    }
+    rows
+}
@@ -0,0 +1,129 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::cmp::Ordering;
+use std::fs;
+use std::io;
+
+use crate::persist;
+
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const UNRELEASED: &str = "Unreleased";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Release<'a> {
+    pub version: &'a str,
+    pub date: Option<&'a str>,
+    pub sections: Vec<Section<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    pub heading: &'a str,
+    /// Lines of the section, with nested items still indented.
+    pub lines: Vec<&'a str>,
+}
+
+impl Release<'_> {
+    pub fn is_unreleased(&self) -> bool {
+        self.version == UNRELEASED
+    }
+
+    /// Whether this release comes after `version`.
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        self.is_unreleased() || compare_versions(self.version, version) == Ordering::Greater
+    }
+
+    /// Sections describing new features or behavior changes, as opposed to
+    /// bug fixes.
+    pub fn notable_sections(&self) -> impl Iterator<Item = &Section<'_>> {
+        self.sections
+            .iter()
+            .filter(|section| section.heading != "Fixed" && !section.lines.is_empty())
+    }
+}
+
+/// Parses releases from a changelog, newest first.
+pub fn parse(changelog: &str) -> Vec<Release<'_>> {
+    let mut releases: Vec<Release> = Vec::new();
+    // link reference definitions end the section they follow
+    let mut in_section = false;
+    for line in changelog.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            // "[version] - date"
+            let (version, date) = heading.split_once(" - ").unwrap_or((heading, ""));
+            releases.push(Release {
+                version: version.trim_matches(|chr| chr == '[' || chr == ']'),
+                date: Some(date.trim()).filter(|date| !date.is_empty()),
+                sections: Vec::new(),
+            });
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            if let Some(release) = releases.last_mut() {
+                release.sections.push(Section {
+                    heading: heading.trim(),
+                    lines: Vec::new(),
+                });
+                in_section = true;
+            }
+        } else if is_link_reference(line) {
+            in_section = false;
+        } else if in_section && !line.trim().is_empty() {
+            if let Some(section) = releases
+                .last_mut()
+                .and_then(|release| release.sections.last_mut())
+            {
+                section.lines.push(line);
+            }
+        }
+    }
+    releases
+}
+
+/// Whether `line` defines a link reference, as in
+/// `"[0.1.0]: https://example.com"`.
+fn is_link_reference(line: &str) -> bool {
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+        .is_some_and(|(label, _)| !label.is_empty())
+}
+
+/// The changelog of this version of `sw`.
+pub fn releases() -> Vec<Release<'static>> {
+    parse(CHANGELOG)
+}
+
+/// Compares dotted version numbers component-wise. Components which aren't
+/// numbers compare as zero.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.').map(|part| part.parse::<u64>().unwrap_or(0));
+    let mut b = b.split('.').map(|part| part.parse::<u64>().unwrap_or(0));
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => break Ordering::Equal,
+            (x, y) => match x.unwrap_or(0).cmp(&y.unwrap_or(0)) {
+                Ordering::Equal => (),
+                unequal => break unequal,
+            },
+        }
+    }
+}
+
+/// Records that this version of `sw` has been run. Returns the previously
+/// recorded version if `sw` has since been upgraded.
+pub fn record_version() -> io::Result<Option<String>> {
+    let path = persist::state_dir()?.join("last-version");
+    let last = match fs::read_to_string(&path) {
+        Ok(last) => Some(last.trim().to_owned()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    if last.as_deref() != Some(VERSION) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, VERSION)?;
+    }
+    Ok(last.filter(|last| compare_versions(VERSION, last) == Ordering::Greater))
+}
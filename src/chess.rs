@@ -0,0 +1,51 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use std::mem;
+use std::time::Instant;
+
+/// Second stopwatch of a chess clock. The stopwatch of the side to move is
+/// kept by the caller, so that every other command operates on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chess {
+    /// Stopwatch of the side which is waiting for its turn.
+    pub waiting: Sw,
+    /// Side to move, counting from zero.
+    to_move: u8,
+}
+
+impl Chess {
+    pub const fn new() -> Self {
+        Self {
+            waiting: Sw::new(),
+            to_move: 0,
+        }
+    }
+
+    /// Number of the side to move, counting from one.
+    pub const fn side_to_move(self) -> u8 {
+        self.to_move + 1
+    }
+
+    /// Stops `sw`, the stopwatch of the side to move, and starts the other
+    /// side's stopwatch in its place.
+    pub fn pass_at(&mut self, sw: &mut Sw, now: Instant) {
+        sw.stop_at(now);
+        mem::swap(sw, &mut self.waiting);
+        sw.start_at(now);
+        self.to_move ^= 1;
+    }
+
+    /// Stopwatches of both sides in order, given `sw`, the stopwatch of the
+    /// side to move.
+    pub fn sides<'a>(&'a self, sw: &'a Sw) -> [&'a Sw; 2] {
+        if self.to_move == 0 {
+            [sw, &self.waiting]
+        } else {
+            [&self.waiting, sw]
+        }
+    }
+}
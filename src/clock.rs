@@ -0,0 +1,141 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::str::FromStr;
+use core::time::Duration;
+use std::thread;
+use std::time::{Instant, SystemTime};
+
+/// Where the shell reads the time from, so that tests can control it.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sys_now(&self) -> SystemTime;
+
+    /// Waits for `dur` to pass on this clock.
+    fn sleep(&self, dur: Duration) {
+        thread::sleep(dur);
+    }
+}
+
+/// The system's own clocks.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sys_now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock which the shell's stopwatch keeps time by, as given to `--clock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Never jumps, but may stand still while the system is suspended.
+    Monotonic,
+    /// Keeps counting while the system is suspended, by catching up with the
+    /// wall clock.
+    Wall,
+    /// Monotonic, unless it stands still while suspended on this platform.
+    Auto,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "monotonic" => Ok(Self::Monotonic),
+            "wall" => Ok(Self::Wall),
+            "auto" => Ok(Self::Auto),
+            _ => Err(format!(
+                "expected 'monotonic', 'wall', or 'auto', found '{s}'"
+            )), // @alloc
+        }
+    }
+}
+
+impl Source {
+    /// Whether the monotonic clock stands still while the system is
+    /// suspended, as it does on Linux and macOS.
+    const MONOTONIC_SUSPENDS: bool = cfg!(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    ));
+
+    /// Decides which clock `Auto` means here, along with whether it fell back
+    /// to the wall clock.
+    pub const fn resolve(self) -> (Self, bool) {
+        match self {
+            Self::Auto if Self::MONOTONIC_SUSPENDS => (Self::Wall, true),
+            Self::Auto => (Self::Monotonic, false),
+            source => (source, false),
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Monotonic => "monotonic",
+            Self::Wall => "wall",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+/// Difference between the time which passed on the wall clock and on the
+/// monotonic clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jump {
+    /// More passed on the wall clock, as when the system was suspended or its
+    /// clock was set forward.
+    Ahead(Duration),
+    /// Less passed on the wall clock, as when its clock was set back.
+    Back(Duration),
+}
+
+impl Jump {
+    pub const fn gap(self) -> Duration {
+        match self {
+            Self::Ahead(gap) | Self::Back(gap) => gap,
+        }
+    }
+}
+
+/// Compares the wall clock with the monotonic clock, to notice time which
+/// passed on one but not the other, such as while suspended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WallAnchor {
+    mono: Instant,
+    wall: SystemTime,
+}
+
+impl WallAnchor {
+    /// Differences smaller than this are ignored, since the two clocks can't
+    /// be read at exactly the same moment.
+    const TOLERANCE: Duration = Duration::from_secs(1);
+
+    pub const fn new(now: Instant, sys_now: SystemTime) -> Self {
+        Self {
+            mono: now,
+            wall: sys_now,
+        }
+    }
+
+    /// How far the wall clock jumped from the monotonic clock since the last
+    /// check, if it did.
+    pub fn check(&mut self, now: Instant, sys_now: SystemTime) -> Option<Jump> {
+        let mono = now.saturating_duration_since(self.mono);
+        let jump = match sys_now.duration_since(self.wall) {
+            Ok(wall) if wall >= mono => Jump::Ahead(wall.saturating_sub(mono)),
+            Ok(wall) => Jump::Back(mono.saturating_sub(wall)),
+            Err(err) => Jump::Back(mono.saturating_add(err.duration())),
+        };
+        *self = Self::new(now, sys_now);
+        Some(jump).filter(|jump| jump.gap() >= Self::TOLERANCE)
+    }
+}
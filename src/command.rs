@@ -4,18 +4,56 @@
 
 use core::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Command {
     Help,
     Display,
+    Big,
+    Freeze,
+    Unfreeze,
+    Idle,
+    Total,
+    Lap,
+    Laps,
+    Label,
+    Unlap,
+    Mark,
+    Marks,
+    Announce,
+    Predict,
+    Alarm,
+    Alarms,
+    Ack,
+    Unalarm,
+    Sequence,
+    Countdown,
+    Pause,
+    Resume,
+    Extend,
+    Chess,
     Toggle,
     Reset,
+    Unreset,
+    New,
     Change,
     Offset,
+    Undo,
+    Recover,
+    Export,
+    Json,
     Name,
+    Info,
+    Summary,
+    Group,
+    Groups,
     Precision,
     Visuals,
+    Theme,
+    Mute,
     License,
+    Version,
+    WhatsNew,
+    Insights,
     Quit,
     QuitAbrupt,
 }
@@ -23,19 +61,83 @@ pub enum Command {
 #[allow(clippy::enum_glob_use)]
 use Command::*;
 
+/// Group of related commands, under which help lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Commands which change the elapsed time, or keep time alongside it.
+    Timing,
+    Display,
+    /// Commands about the session as a whole, such as naming and exporting
+    /// it.
+    Session,
+    /// Commands about `sw` itself.
+    Meta,
+}
+
+impl Category {
+    pub const ALL: [Self; 4] = [Self::Timing, Self::Display, Self::Session, Self::Meta];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Timing => "timing",
+            Self::Display => "display",
+            Self::Session => "session",
+            Self::Meta => "meta",
+        }
+    }
+}
+
 impl Command {
     pub const fn short_name_literal(self) -> &'static str {
         match self {
             Help => "h",
             Display => "",
+            Big => "b",
+            Freeze => "f",
+            Unfreeze => "uf",
+            Idle => "z",
+            Total => "t",
+            Lap => "a",
+            Laps => "la",
+            Label => "lb",
+            Unlap => "ul",
+            Mark => "mk",
+            Marks => "mks",
+            Announce => "an",
+            Predict => "pr",
+            Alarm => "al",
+            Alarms => "als",
+            Ack => "k",
+            Unalarm => "ual",
+            Sequence => "sq",
+            Countdown => "cd",
+            Pause => "pa",
+            Resume => "re",
+            Extend => "e",
+            Chess => "ch",
             Toggle => "s",
             Reset => "r",
+            Unreset => "ur",
+            New => "nw",
             Change => "c",
             Offset => "o",
+            Undo => "u",
+            Recover => "rc",
+            Export => "x",
+            Json => "j",
             Name => "n",
+            Info => "i",
+            Summary => "su",
+            Group => "g",
+            Groups => "gs",
             Precision => "p",
             Visuals => "v",
+            Theme => "th",
+            Mute => "m",
             License => "l",
+            Version => "ver",
+            WhatsNew => "w",
+            Insights => "in",
             Quit | QuitAbrupt => "q",
         }
     }
@@ -51,14 +153,52 @@ impl Command {
         match self {
             Help => "help",
             Display => "display",
+            Big => "big",
+            Freeze => "freeze",
+            Unfreeze => "unfreeze",
+            Idle => "idle",
+            Total => "total",
+            Lap => "lap",
+            Laps => "laps",
+            Label => "label",
+            Unlap => "unlap",
+            Mark => "mark",
+            Marks => "marks",
+            Announce => "announce",
+            Predict => "predict",
+            Alarm => "alarm",
+            Alarms => "alarms",
+            Ack => "ack",
+            Unalarm => "unalarm",
+            Sequence => "sequence",
+            Countdown => "countdown",
+            Pause => "pause",
+            Resume => "resume",
+            Extend => "extend",
+            Chess => "chess",
             Toggle => "toggle",
             Reset => "reset",
+            Unreset => "unreset",
+            New => "new",
             Change => "change",
             Offset => "offset",
+            Undo => "undo",
+            Recover => "recover",
+            Export => "export",
+            Json => "json",
             Name => "name",
+            Info => "info",
+            Summary => "summary",
+            Group => "group",
+            Groups => "groups",
             Precision => "precision",
             Visuals => "visuals",
+            Theme => "theme",
+            Mute => "mute",
             License => "license",
+            Version => "version",
+            WhatsNew => "whatsnew",
+            Insights => "insights",
             Quit | QuitAbrupt => "quit",
         }
     }
@@ -67,21 +207,110 @@ impl Command {
         match self {
             Help => "show help",
             Display => "display elapsed time",
+            Big => "display elapsed time in big digits",
+            Freeze => "freeze displayed time",
+            Unfreeze => "unfreeze displayed time",
+            Idle => "show time since stopwatch stopped",
+            Total => "show total time across resets",
+            Lap => "record lap, with an optional value",
+            Laps => "list laps",
+            Label => "label lap",
+            Unlap => "delete lap",
+            Mark => "mark elapsed time under a name",
+            Marks => "list marks",
+            Announce => "configure lap announcements",
+            Predict => "predict finish from lap pace",
+            Alarm => "set alarm",
+            Alarms => "list alarms",
+            Ack => "acknowledge alarms",
+            Unalarm => "remove alarm",
+            Sequence => "count down, then start stopwatch",
+            Countdown => "start countdown",
+            Pause => "pause countdown",
+            Resume => "resume countdown",
+            Extend => "extend countdown",
+            Chess => "toggle chess clock mode",
             Toggle => "toggle stopwatch",
             Reset => "reset stopwatch",
+            Unreset => "restore time from before reset",
+            New => "start session from template",
             Change => "change elapsed time",
             Offset => "offset elapsed time",
+            Undo => "undo last change",
+            Recover => "recover from overflowing elapsed time",
+            Export => "export events as CSV",
+            Json => "print state as JSON",
             Name => "name stopwatch",
+            Info => "show session overview",
+            Summary => "summarize all stopwatches",
+            Group => "set stopwatch group",
+            Groups => "show elapsed time per group",
             Precision => "set display precision",
             Visuals => "toggle visual cues",
+            Theme => "switch color theme",
+            Mute => "toggle sounds",
             License => "print license info",
+            Version => "print version and build info",
+            WhatsNew => "show what's new",
+            Insights => "suggest shortcuts from usage",
             Quit | QuitAbrupt => "Abandon all Data",
         }
     }
 
+    /// Command whose short name is the single character `key`, which runs
+    /// without enter in single-key mode.
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::iter().iter().copied().find(|cmd| {
+            let short = cmd.short_name_literal();
+            short.len() == 1 && key.eq_ignore_ascii_case(short)
+        })
+    }
+
+    /// What may follow the command on the same line, written as in the
+    /// manual. Without it, the command asks for what it needs, if anything.
+    pub const fn argument(self) -> Option<&'static str> {
+        Some(match self {
+            Lap => "[value] [label]",
+            Predict => "[laps or duration]",
+            Group => "[group]",
+            Label => "<lap number> [label]",
+            Unlap => "<lap number>",
+            Mark => "<name>",
+            Alarm | Sequence | Extend => "<duration>",
+            Countdown => "<duration> | until <time of day>",
+            Unalarm => "<alarm number>",
+            New => "<template key>",
+            Export => "<path>",
+            Theme => "<theme>",
+            _ => return None,
+        })
+    }
+
+    pub const fn category(self) -> Category {
+        match self {
+            Lap | Label | Unlap | Mark | Alarm | Ack | Unalarm | Sequence | Countdown | Pause
+            | Resume | Extend | Chess | Toggle | Reset | Unreset | Change | Offset | Undo
+            | Recover => Category::Timing,
+            Display | Big | Freeze | Unfreeze | Idle | Total | Laps | Marks | Announce
+            | Predict | Alarms | Precision | Visuals | Theme | Mute => Category::Display,
+            New | Export | Json | Name | Info | Summary | Group | Groups | Insights | Quit
+            | QuitAbrupt => Category::Session,
+            Help | License | Version | WhatsNew => Category::Meta,
+        }
+    }
+
+    /// Whether the command may be followed by an argument on the same line.
+    pub const fn takes_argument(self) -> bool {
+        self.argument().is_some()
+    }
+
     pub const fn iter() -> &'static [Self] {
         &[
-            Help, Display, Toggle, Reset, Change, Offset, Name, Precision, Visuals, License, Quit,
+            Help, Display, Big, Freeze, Unfreeze, Idle, Total, Lap, Laps, Label, Unlap, Mark,
+            Marks, Announce, Predict, Alarm, Alarms, Ack, Unalarm, Sequence, Countdown, Pause,
+            Resume, Extend, Chess, Toggle, Reset, Unreset, New, Change, Offset, Undo, Recover,
+            Export, Json, Name, Info, Summary, Group, Groups, Precision, Visuals, Theme, Mute,
+            License, Version, WhatsNew, Insights, Quit,
         ]
     }
 }
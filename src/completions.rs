@@ -0,0 +1,291 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt::Write as _;
+use core::str::FromStr;
+
+/// Shell to write a completion script for, as given to `--completions`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => Err(format!("expected 'bash', 'zsh', or 'fish', found '{s}'")), // @alloc
+        }
+    }
+}
+
+/// Option of `sw` or one of its subcommands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flag<'a> {
+    pub short: Option<char>,
+    pub long: &'a str,
+    /// Whether the option is followed by a value, rather than being a switch.
+    pub value: bool,
+    pub description: String,
+}
+
+/// Options and subcommands, as listed by the usage information which `argh`
+/// generates from the argument definitions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Usage<'a> {
+    pub flags: Vec<Flag<'a>>,
+    /// Name and description of each subcommand.
+    pub commands: Vec<(&'a str, String)>,
+}
+
+impl<'a> Usage<'a> {
+    /// Column at which descriptions continue when wrapped.
+    const WRAP_INDENT: &'static str = "                    ";
+
+    /// Reads the output of `--help`.
+    pub fn parse(help: &'a str) -> Self {
+        let usage_line = help.lines().next().unwrap_or_default();
+        let mut usage = Self::default();
+        let mut section = "";
+        // the last entry, along with its description so far
+        let mut entry: Option<(&str, String)> = None;
+        for line in help.lines().chain([""]) {
+            if let Some(rest) = line.strip_prefix(Self::WRAP_INDENT) {
+                if let Some((_, description)) = &mut entry {
+                    if !description.is_empty() {
+                        description.push(' ');
+                    }
+                    description.push_str(rest.trim());
+                }
+                continue;
+            }
+            if let Some((names, description)) = entry.take() {
+                usage.add(section, usage_line, names, description);
+            }
+            if let Some(names) = line.strip_prefix("  ") {
+                let (names, description) = names.split_once("  ").unwrap_or((names, ""));
+                entry = Some((names, description.trim().to_string())); // @alloc
+            } else if !line.is_empty() {
+                section = line;
+            }
+        }
+        usage
+    }
+
+    fn add(&mut self, section: &str, usage_line: &str, names: &'a str, description: String) {
+        match section {
+            "Options:" => {
+                let mut short = None;
+                let mut long = None;
+                for name in names.split(", ") {
+                    if let Some(name) = name.strip_prefix("--") {
+                        long = Some(name);
+                    } else if let Some(name) = name.strip_prefix('-') {
+                        short = name.chars().next();
+                    }
+                }
+                let Some(long) = long else {
+                    return;
+                };
+                // options which take values are shown with them in the usage
+                let value = [
+                    format!("--{long} <"),
+                    format!("-{} <", short.unwrap_or(' ')),
+                ]
+                .iter()
+                .any(|written| usage_line.contains(written.as_str())); // @alloc
+                self.flags.push(Flag {
+                    short,
+                    long,
+                    value,
+                    description,
+                }); // @alloc
+            }
+            "Commands:" => self.commands.push((names, description)), // @alloc
+            _ => (),
+        }
+    }
+}
+
+/// Writes a completion script for `sw`, given its usage and the usage of each
+/// of its subcommands, in the same order.
+pub fn script(target: Target, usage: &Usage, subcommands: &[Usage]) -> String {
+    let mut out = String::new(); // @alloc
+    match target {
+        Target::Bash => bash(&mut out, usage, subcommands),
+        Target::Zsh => zsh(&mut out, usage, subcommands),
+        Target::Fish => fish(&mut out, usage, subcommands),
+    }
+    out
+}
+
+/// Names of the subcommands, separated by `|` as in a shell pattern.
+fn pattern(usage: &Usage) -> String {
+    let names = usage.commands.iter().map(|(name, _)| *name);
+    names.collect::<Vec<_>>().join("|") // @alloc
+}
+
+/// Every way to write each flag which `keep` holds for, separated by spaces.
+fn flag_words(usage: &Usage, keep: fn(&Flag) -> bool) -> String {
+    let mut words = Vec::new(); // @alloc
+    for flag in usage.flags.iter().filter(|flag| keep(flag)) {
+        words.extend(flag.short.map(|short| format!("-{short}"))); // @alloc
+        words.push(format!("--{}", flag.long)); // @alloc
+    }
+    words.join(" ")
+}
+
+fn bash(out: &mut String, usage: &Usage, subcommands: &[Usage]) {
+    let commands = pattern(usage);
+    let mut cases = String::new(); // @alloc
+    let all = |_: &Flag| true;
+    let valued = |flag: &Flag| flag.value;
+    let top_words = usage.commands.iter().map(|(name, _)| *name);
+    let top_words = [flag_words(usage, all)]
+        .into_iter()
+        .chain(top_words.map(str::to_string));
+    _ = writeln!(
+        cases,
+        "        \"\") words=\"{}\"; values=\"{}\" ;;",
+        top_words.collect::<Vec<_>>().join(" "), // @alloc
+        flag_words(usage, valued)
+    );
+    for ((name, _), sub) in usage.commands.iter().zip(subcommands) {
+        _ = writeln!(
+            cases,
+            "        {name}) words=\"{}\"; values=\"{}\" ;;",
+            flag_words(sub, all),
+            flag_words(sub, valued)
+        );
+    }
+    _ = write!(
+        out,
+        r#"_sw() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    local cmd="" words="" values="" i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${{COMP_WORDS[i]}}" in
+            {commands}) cmd="${{COMP_WORDS[i]}}"; break ;;
+        esac
+    done
+    case "$cmd" in
+{cases}    esac
+    # values are left to the default completion
+    case " $values " in
+        *" $prev "*) COMPREPLY=(); return ;;
+    esac
+    COMPREPLY=($(compgen -W "$words" -- "$cur"))
+}}
+complete -o default -F _sw sw
+"#
+    );
+}
+
+fn zsh(out: &mut String, usage: &Usage, subcommands: &[Usage]) {
+    fn specs(usage: &Usage) -> String {
+        let mut out = String::new(); // @alloc
+        for flag in &usage.flags {
+            let long = flag.long;
+            let description = zsh_escape(&flag.description);
+            let value = if flag.value { ":value:_default" } else { "" };
+            match flag.short {
+                Some(short) => _ = write!(
+                    out,
+                    " \\\n        '(-{short} --{long})'{{-{short},--{long}}}'[{description}]{value}'"
+                ),
+                None => _ = write!(out, " \\\n        '--{long}[{description}]{value}'"),
+            }
+        }
+        out
+    }
+
+    let mut commands = String::new(); // @alloc
+    for (name, description) in &usage.commands {
+        _ = writeln!(commands, "        '{name}:{}'", zsh_escape(description));
+    }
+    let mut cases = String::new(); // @alloc
+    for ((name, _), sub) in usage.commands.iter().zip(subcommands) {
+        _ = writeln!(
+            cases,
+            "    {name})\n        _arguments{} \\\n        '*:name:_default' ;;",
+            specs(sub)
+        );
+    }
+    _ = write!(
+        out,
+        r#"#compdef sw
+
+_sw() {{
+    local -a commands
+    commands=(
+{commands}    )
+    case ${{words[(r)({pattern})]}} in
+{cases}    *)
+        _arguments -s{specs} \
+        '*: :{{_describe command commands}}' ;;
+    esac
+}}
+
+_sw "$@"
+"#,
+        pattern = pattern(usage),
+        specs = specs(usage),
+    );
+}
+
+fn fish(out: &mut String, usage: &Usage, subcommands: &[Usage]) {
+    fn complete(out: &mut String, condition: &str, usage: &Usage) {
+        for flag in &usage.flags {
+            _ = write!(out, "complete -c sw -n '{condition}'");
+            if let Some(short) = flag.short {
+                _ = write!(out, " -s {short}");
+            }
+            _ = write!(out, " -l {}", flag.long);
+            if flag.value {
+                _ = write!(out, " -r");
+            }
+            _ = writeln!(out, " -d '{}'", fish_escape(&flag.description));
+        }
+    }
+
+    complete(out, "__fish_use_subcommand", usage);
+    for (name, description) in &usage.commands {
+        _ = writeln!(
+            out,
+            "complete -c sw -n '__fish_use_subcommand' -f -a {name} -d '{}'",
+            fish_escape(description)
+        );
+    }
+    for ((name, _), sub) in usage.commands.iter().zip(subcommands) {
+        let condition = format!("__fish_seen_subcommand_from {name}"); // @alloc
+        complete(out, &condition, sub);
+    }
+}
+
+/// Escapes `s` for a description within a single-quoted `_arguments` spec.
+fn zsh_escape(s: &str) -> String {
+    let mut out = String::new(); // @alloc
+    for c in s.chars() {
+        match c {
+            '\'' => out.push_str("'\\''"),
+            '[' | ']' | ':' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes `s` for a single-quoted fish string.
+fn fish_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'") // @alloc
+}
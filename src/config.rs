@@ -0,0 +1,291 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt;
+use core::time::Duration;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::escalate::Policy;
+use crate::hook::Hooks;
+use crate::laps::Target;
+use crate::locale::Locale;
+use crate::parse::ReadDur;
+use crate::precision::{AutoPrecision, Rounding};
+use crate::sound::{Event, Sounds};
+use crate::template::{Template, Templates};
+use crate::theme::{self, Theme, Thresholds};
+
+/// Settings read from the configuration file.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub sounds: Sounds,
+    pub escalation: Policy,
+    pub hooks: Hooks,
+    pub templates: Templates,
+    pub theme: Theme,
+    /// Colors of the elapsed time by how long it has been running.
+    pub thresholds: Thresholds,
+    /// Whether pressing enter at a prompt accepts the previous answer.
+    pub prompt_defaults: bool,
+    /// Whether the command prompt shows the elapsed time.
+    pub prompt_elapsed: bool,
+    /// Whether the elapsed time is shown after commands which change it.
+    pub sticky_display: bool,
+    /// Whether durations are checked while they're typed.
+    pub live_input: bool,
+    /// Whether commands named by a single key run as soon as it's pressed.
+    pub single_key: bool,
+    /// Whether to avoid animation and output which is redrawn in place.
+    pub reduced_motion: bool,
+    /// Whether the terminal's title shows the stopwatch's state.
+    pub title: bool,
+    /// Whether to count the commands run, for the insights command.
+    pub record_usage: bool,
+    /// Whether the precision scales with durations from the start.
+    pub auto_precision: bool,
+    pub precision_thresholds: AutoPrecision,
+    /// Elapsed time below which negative offsets don't go.
+    pub offset_floor: Duration,
+    /// Percentage of similarity from which an unknown command's name is
+    /// suggested a command, if not the default.
+    pub suggest_threshold: Option<u8>,
+    /// Number of columns the stopwatch's name may take up in the prompt, if
+    /// not the default.
+    pub name_width: Option<u16>,
+    /// How the displayed elapsed time is rounded to the precision.
+    pub rounding: Rounding,
+    /// Whether the displayed elapsed time leaves out the hours while there
+    /// are none.
+    pub hide_zero_hours: bool,
+    /// Whether the digits of a large number of days are grouped.
+    pub group_digits: bool,
+    /// Language in which the elapsed time is written out, if not the one
+    /// given by the environment.
+    pub locale: Option<Locale>,
+    /// Goal which laps work towards, from which the finish is predicted.
+    pub lap_target: Option<Target>,
+}
+
+impl Config {
+    /// Reads the configuration file, along with a warning for each line which
+    /// was ignored. A missing file is the same as an empty one, and one which
+    /// can't be read is warned about and ignored.
+    pub fn load() -> (Self, Vec<String>) {
+        let Some(path) = config_path() else {
+            return (Self::default(), Vec::new());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents, &path),
+            Err(err) if err.kind() == ErrorKind::NotFound => (Self::default(), Vec::new()),
+            Err(err) => (
+                Self::default(),
+                vec![format!(
+                    "{}: using the default configuration, can't read the file: {err}",
+                    path.display()
+                )], // @alloc
+            ),
+        }
+    }
+
+    /// Parses the configuration at `path`, which consists of `key = value`
+    /// lines and `#` comments.
+    pub fn parse(s: &str, path: &Path) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut warnings = Vec::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut warn = |msg: fmt::Arguments| {
+                warnings.push(format!("{}:{}: {msg}", path.display(), idx + 1));
+                // @alloc
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                warn(format_args!("expected 'key = value', ignoring line"));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "alarm.escalate_hook" => config.escalation.hook = Some(value.to_string()),
+                "suggest.threshold" => match value.parse::<u8>() {
+                    Ok(percent) if percent <= 100 => config.suggest_threshold = Some(percent),
+                    _ => warn(format_args!("expected a percentage, ignoring '{key}'")),
+                },
+                "name.max_width" => match value.parse::<u16>() {
+                    Ok(width) if width > 0 => config.name_width = Some(width),
+                    _ => warn(format_args!(
+                        "expected a number of columns, ignoring '{key}'"
+                    )),
+                },
+                "locale" => match value.parse() {
+                    Ok(locale) => config.locale = Some(locale),
+                    Err(err) => warn(format_args!("{err}, ignoring '{key}'")),
+                },
+                "laps.target" => match value.parse() {
+                    Ok(target) => config.lap_target = Some(target),
+                    Err(err) => warn(format_args!("{err}, ignoring '{key}'")),
+                },
+                "display.rounding" => match value.parse() {
+                    Ok(rounding) => config.rounding = rounding,
+                    Err(err) => warn(format_args!("{err}, ignoring '{key}'")),
+                },
+                "display.thresholds" => match Thresholds::parse(value) {
+                    Some(thresholds) => config.thresholds = thresholds,
+                    None => warn(format_args!("invalid thresholds, ignoring '{key}'")),
+                },
+                "theme" => match Theme::preset(value) {
+                    Some(preset) => config.theme = preset,
+                    None => warn(format_args!("unknown theme '{value}', ignoring '{key}'")),
+                },
+                "sound.player" => config.sounds.player = Some(value.to_string()),
+                _ => {
+                    if let Some(field) = config.duration_mut(key) {
+                        match ReadDur::parse(value, false) {
+                            Some(Ok(ReadDur { dur, .. })) => *field = dur,
+                            Some(Err(_)) | None => {
+                                warn(format_args!("invalid duration, ignoring '{key}'"));
+                            }
+                        }
+                    } else if let Some(flag) = config.flag_mut(key) {
+                        match parse_flag(value) {
+                            Some(new) => *flag = new,
+                            None => {
+                                warn(format_args!("expected 'true' or 'false', ignoring '{key}'"));
+                            }
+                        }
+                    } else if let Some(event) =
+                        key.strip_prefix("sound.").and_then(Event::from_name)
+                    {
+                        config.sounds.set(event, PathBuf::from(value));
+                    } else if let Some(event) = key.strip_prefix("hook.").and_then(Event::from_name)
+                    {
+                        config.hooks.set(event, value.to_string());
+                    } else if let Some(color) =
+                        (key.strip_prefix("theme.")).and_then(|role| config.theme.role_mut(role))
+                    {
+                        match theme::parse_color(value) {
+                            Some(new) => *color = new,
+                            None => warn(format_args!("invalid color, ignoring '{key}'")),
+                        }
+                    } else if let Some((template, field)) = key
+                        .strip_prefix("template.")
+                        .and_then(|rest| rest.rsplit_once('.'))
+                        .filter(|(template, field)| {
+                            !template.is_empty() && Template::FIELDS.contains(field)
+                        })
+                    {
+                        if set_template(&mut config.templates, template, field, value).is_none() {
+                            warn(format_args!("invalid duration, ignoring '{key}'"));
+                        }
+                    } else {
+                        warn(format_args!("unknown key '{key}'"));
+                    }
+                }
+            }
+        }
+        (config, warnings)
+    }
+
+    /// Setting which is a duration given by `key`.
+    fn duration_mut(&mut self, key: &str) -> Option<&mut Duration> {
+        match key {
+            "alarm.escalate_after" => Some(&mut self.escalation.hook_after),
+            "precision.fine_below" => Some(&mut self.precision_thresholds.fine_below),
+            "precision.coarse_from" => Some(&mut self.precision_thresholds.coarse_from),
+            "offset.floor" => Some(&mut self.offset_floor),
+            _ => None,
+        }
+    }
+
+    /// Setting which is turned on or off by `key`.
+    fn flag_mut(&mut self, key: &str) -> Option<&mut bool> {
+        match key {
+            "precision.auto" => Some(&mut self.auto_precision),
+            "sound.mute" => Some(&mut self.sounds.muted),
+            "prompt.defaults" => Some(&mut self.prompt_defaults),
+            "prompt.elapsed" => Some(&mut self.prompt_elapsed),
+            "display.sticky" => Some(&mut self.sticky_display),
+            "display.hide_zero_hours" => Some(&mut self.hide_zero_hours),
+            "display.group_digits" => Some(&mut self.group_digits),
+            "input.live" => Some(&mut self.live_input),
+            "input.single_key" => Some(&mut self.single_key),
+            "motion.reduced" => Some(&mut self.reduced_motion),
+            "insights.record" => Some(&mut self.record_usage),
+            "title" => Some(&mut self.title),
+            _ => None,
+        }
+    }
+}
+
+/// Sets `field` of the template called `key`, returning `None` if `value`
+/// has an invalid duration.
+fn set_template(templates: &mut Templates, key: &str, field: &str, value: &str) -> Option<()> {
+    let list = || {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+    };
+    let parse_dur = |s| match ReadDur::parse(s, false) {
+        Some(Ok(ReadDur { dur, .. })) => Some(dur),
+        Some(Err(_)) | None => None,
+    };
+    match field {
+        "name" => templates.entry(key).name = Some(value.to_string()),
+        "tags" => templates.entry(key).tags = list().map(str::to_string).collect(), // @alloc
+        "target" => templates.entry(key).target = Some(parse_dur(value)?),
+        "alarms" => templates.entry(key).alarms = list().map(parse_dur).collect::<Option<_>>()?, // @alloc
+        _ => unreachable!("unknown template field '{field}'"),
+    }
+    Some(())
+}
+
+/// Separator between groups of digits in the locale given by the
+/// environment, falling back to a comma.
+pub fn digit_separator() -> char {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default();
+    separator_for(&locale)
+}
+
+/// Separator between groups of digits in `locale`, such as `de_DE.UTF-8`.
+pub fn separator_for(locale: &str) -> char {
+    let language = locale
+        .split(['_', '.', '@', '-'])
+        .next()
+        .unwrap_or_default();
+    match language {
+        "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" => '.',
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg" => {
+            '\u{a0}'
+        }
+        _ => ',',
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Path to the configuration file, following the XDG base directory
+/// specification.
+pub fn config_path() -> Option<PathBuf> {
+    let dir = if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(env::var_os("HOME").filter(|dir| !dir.is_empty())?).join(".config")
+    };
+    Some(dir.join(env!("CARGO_PKG_NAME")).join("config"))
+}
@@ -0,0 +1,269 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use core::fmt::Write as _;
+use core::time::Duration;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Instant, SystemTime};
+
+use crate::chess::Chess;
+use crate::command::Command;
+use crate::hook::Hooks;
+use crate::plumbing;
+use crate::precision::Precision;
+use crate::sound::Event;
+use crate::state::DurationFmt;
+use crate::status::Broadcast;
+
+/// Stopwatch as seen by other programs while the shell waits for input.
+struct Shared {
+    /// Whether the shell is waiting for input. Otherwise, the shell owns the
+    /// stopwatch and requests are turned away.
+    waiting: bool,
+    sw: Sw,
+    chess: Option<Chess>,
+    prec: Precision,
+    broadcast: Option<Broadcast>,
+    hooks: Hooks,
+    name: String,
+    done: Vec<Remote>,
+}
+
+/// Change made by another program, to be recorded by the shell. The change
+/// has already taken effect: status files are up to date and its hook has
+/// run.
+pub struct Remote {
+    pub command: Command,
+    pub before: (Sw, Option<Chess>),
+    pub after: (Sw, Option<Chess>),
+    /// Name of the change, as used in exported events.
+    pub event: &'static str,
+    pub message: &'static str,
+    /// When the change was made, and the elapsed time then.
+    pub at: (SystemTime, Duration),
+    /// Why the hook of the change failed to run, if it did.
+    pub hook_failed: Option<String>,
+}
+
+/// Listener on a unix domain socket, through which other programs can run
+/// simple commands on the stopwatch, one per line.
+pub struct Listener {
+    path: PathBuf,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Listener {
+    /// Starts listening on `path` in the background. A socket left behind by
+    /// a session which has since exited is replaced. Changes run `hooks` for
+    /// the stopwatch called `name` as they're made.
+    #[cfg(unix)]
+    pub fn bind(path: PathBuf, hooks: Hooks, name: String) -> io::Result<Self> {
+        use std::io::{BufRead, BufReader, ErrorKind, Write};
+        use std::os::unix::net::{UnixListener, UnixStream};
+        use std::thread;
+        use std::{fs, time::Duration};
+
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("another session is listening on {}", path.display()),
+                ));
+            }
+            fs::remove_file(&path)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            waiting: false,
+            sw: Sw::new(),
+            chess: None,
+            prec: Precision::Fixed(0),
+            broadcast: None,
+            hooks,
+            name,
+            done: Vec::new(),
+        })); // @alloc
+        let accept_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                // each client has a thread of its own, so that one which never
+                // finishes its request doesn't hold up the others, and one
+                // which goes quiet is let go of
+                _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                let shared = Arc::clone(&accept_shared);
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(reader);
+                    let mut line = String::new(); // @alloc
+                    loop {
+                        line.clear();
+                        if !matches!(reader.read_line(&mut line), Ok(bytes) if bytes != 0) {
+                            break;
+                        }
+                        let response = Self::handle(&shared, line.trim());
+                        if stream.write_all(response.as_bytes()).is_err() {
+                            break;
+                        }
+                    }
+                }); // @alloc
+            }
+        }); // @alloc
+
+        Ok(Self { path, shared })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_path: PathBuf, _hooks: Hooks, _name: String) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "control sockets are only supported on unix",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Hands the stopwatch over to other programs while the shell waits for
+    /// input. Their changes are fed to `broadcast` right away.
+    pub fn publish(
+        &self,
+        sw: Sw,
+        chess: Option<Chess>,
+        prec: Precision,
+        broadcast: Option<&Broadcast>,
+    ) {
+        let mut shared = self.lock();
+        shared.waiting = true;
+        shared.sw = sw;
+        shared.chess = chess;
+        shared.prec = prec;
+        shared.broadcast = broadcast.cloned();
+    }
+
+    /// Takes the stopwatch back once input has been read, returning the
+    /// changes made to it in the meantime.
+    pub fn reclaim(&self) -> Vec<Remote> {
+        let mut shared = self.lock();
+        shared.waiting = false;
+        shared.done.drain(..).collect() // @alloc
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn handle(shared: &Mutex<Shared>, request: &str) -> String {
+        let mut shared = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut response = String::new(); // @alloc
+        if !shared.waiting {
+            response.push_str("error: busy, try again\n");
+            return response;
+        }
+        let now = Instant::now();
+        let before = (shared.sw, shared.chess);
+        let (command, event, message) = match request {
+            "show" => {
+                let state = if shared.sw.is_running() {
+                    "running"
+                } else {
+                    "stopped"
+                };
+                _ = writeln!(
+                    response,
+                    "{} {state}",
+                    DurationFmt::scaled(shared.sw.elapsed_at(now), shared.prec, true)
+                );
+                return response;
+            }
+            "toggle" => {
+                let Shared { sw, chess, .. } = &mut *shared;
+                if let (true, Some(chess)) = (sw.is_running(), chess) {
+                    chess.pass_at(sw, now);
+                    (Command::Toggle, "pass", "passed turn")
+                } else if sw.is_running() {
+                    sw.stop_at(now);
+                    (Command::Toggle, "stop", "stopped stopwatch")
+                } else {
+                    sw.start_at(now);
+                    (Command::Toggle, "start", "started stopwatch")
+                }
+            }
+            request if request.starts_with(':') => {
+                match plumbing::run(request, &mut shared.sw, now) {
+                    Ok((answer, Some(change))) => {
+                        _ = writeln!(response, "{answer}");
+                        (change.command, change.event, change.message)
+                    }
+                    Ok((answer, None)) => {
+                        _ = writeln!(response, "{answer}");
+                        return response;
+                    }
+                    Err(err) => {
+                        _ = writeln!(response, "error: {err}");
+                        return response;
+                    }
+                }
+            }
+            "reset" => {
+                shared.sw.reset();
+                if shared.chess.is_some() {
+                    shared.chess = Some(Chess::new());
+                }
+                (Command::Reset, "reset", "reset stopwatch")
+            }
+            _ => {
+                _ = writeln!(
+                    response,
+                    "error: unknown command '{request}' (expected show, toggle, or reset)"
+                );
+                return response;
+            }
+        };
+        let after = (shared.sw, shared.chess);
+        let elapsed = shared.sw.elapsed_at(now);
+
+        // the change takes effect now, rather than once the shell reads input
+        if let Some(broadcast) = &shared.broadcast {
+            broadcast.update(shared.sw);
+        }
+        let hook_failed = Event::from_name(event).and_then(|kind| {
+            let err = shared.hooks.run(kind, &shared.name, elapsed).err()?;
+            Some(format!("failed to run {} hook: {err}", kind.name())) // @alloc
+        });
+        shared.done.push(Remote {
+            command,
+            before,
+            after,
+            event,
+            message,
+            at: (SystemTime::now(), elapsed),
+            hook_failed,
+        });
+        // plumbing commands have answered already
+        if response.is_empty() {
+            _ = writeln!(response, "{message}");
+        }
+        response
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        _ = std::fs::remove_file(&self.path);
+    }
+}
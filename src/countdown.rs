@@ -0,0 +1,139 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use core::time::Duration;
+use std::time::Instant;
+
+/// A timer counting down to zero, kept as a stopwatch counting up to the
+/// target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Countdown {
+    sw: Sw,
+    pub target: Duration,
+}
+
+impl Countdown {
+    pub fn new_started_at(target: Duration, now: Instant) -> Self {
+        let mut sw = Sw::new();
+        sw.start_at(now);
+        Self { sw, target }
+    }
+
+    /// Time left until zero, which is zero once the countdown has expired.
+    pub fn remaining_at(&self, now: Instant) -> Duration {
+        self.target.saturating_sub(self.sw.elapsed_at(now))
+    }
+
+    /// How much of the countdown has passed, in thousandths.
+    pub fn permille_at(&self, now: Instant) -> u16 {
+        let target = self.target.as_nanos();
+        if target == 0 {
+            return 1000;
+        }
+        let passed = self.sw.elapsed_at(now).as_nanos().min(target);
+        u16::try_from(passed * 1000 / target).unwrap_or(1000)
+    }
+
+    /// Instant at which the countdown reaches zero, if it's running.
+    pub fn deadline(&self) -> Option<Instant> {
+        let start = self.sw.start?;
+        start.checked_add(self.target.saturating_sub(self.sw.elapsed))
+    }
+
+    pub const fn is_running(&self) -> bool {
+        self.sw.is_running()
+    }
+
+    /// Pauses the countdown, returning `false` if it was already paused.
+    pub fn pause_at(&mut self, now: Instant) -> bool {
+        let was_running = self.sw.is_running();
+        self.sw.stop_at(now);
+        was_running
+    }
+
+    /// Resumes the countdown, returning `false` if it was already running.
+    pub fn resume_at(&mut self, now: Instant) -> bool {
+        let was_stopped = self.sw.is_stopped();
+        if was_stopped {
+            self.sw.start_at(now);
+        }
+        was_stopped
+    }
+
+    /// Adds `by` to the time left, returning `false` if the target would
+    /// overflow.
+    pub fn extend(&mut self, by: Duration) -> bool {
+        self.target
+            .checked_add(by)
+            .map(|target| self.target = target)
+            .is_some()
+    }
+}
+
+/// Countdown which runs alongside the stopwatch, and which must be
+/// acknowledged once it expires.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timer {
+    countdown: Option<Countdown>,
+    /// When the last countdown expired, if it hasn't been acknowledged since.
+    pending_since: Option<Instant>,
+}
+
+impl Timer {
+    pub const fn new() -> Self {
+        Self {
+            countdown: None,
+            pending_since: None,
+        }
+    }
+
+    pub const fn countdown(&self) -> Option<&Countdown> {
+        self.countdown.as_ref()
+    }
+
+    pub fn countdown_mut(&mut self) -> Option<&mut Countdown> {
+        self.countdown.as_mut()
+    }
+
+    /// Starts counting down from `target`, returning the countdown it
+    /// replaces.
+    pub fn start_at(&mut self, target: Duration, now: Instant) -> Option<Countdown> {
+        self.countdown
+            .replace(Countdown::new_started_at(target, now))
+    }
+
+    /// Returns the countdown if it has expired since the last check, in which
+    /// case it's pending until acknowledged.
+    pub fn check(&mut self, now: Instant) -> Option<Countdown> {
+        let countdown = self.countdown?;
+        if !countdown.is_running() || !countdown.remaining_at(now).is_zero() {
+            return None;
+        }
+        self.countdown = None;
+        self.pending_since = Some(countdown.deadline().unwrap_or(now));
+        Some(countdown)
+    }
+
+    /// Instant at which the countdown expired without being acknowledged, or
+    /// at which it will expire.
+    pub fn due(&self) -> Option<Instant> {
+        self.pending_since.or_else(|| self.countdown?.deadline())
+    }
+
+    /// How long the countdown has run past zero as of `now`, which keeps
+    /// counting until it's acknowledged. Returns `None` if it hasn't expired.
+    pub fn overtime_at(&self, now: Instant) -> Option<Duration> {
+        let due = self.due()?;
+        (due <= now).then(|| now.saturating_duration_since(due))
+    }
+
+    /// Acknowledges the expired countdown, returning how long it went
+    /// unacknowledged as of `now`.
+    pub fn acknowledge(&mut self, now: Instant) -> Option<Duration> {
+        let since = self.pending_since.take()?;
+        Some(now.saturating_duration_since(since))
+    }
+}
@@ -0,0 +1,76 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::persist;
+
+/// Message and location of the last panic, as recorded by the hook.
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Records panics for the crash report, on top of printing them as usual.
+pub fn install_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        *LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner) = Some(info.to_string()); // @alloc
+        default(info);
+    })); // @alloc
+}
+
+/// Writes a crash report holding `details` about the session, such as the
+/// state of the stopwatch, to a new file in the state directory. Returns the
+/// path of the report.
+pub fn write_report(details: &str) -> io::Result<PathBuf> {
+    let dir = persist::state_dir()?;
+    fs::create_dir_all(&dir)?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let pid = process::id();
+
+    let mut report = String::new(); // @alloc
+    _ = writeln!(
+        report,
+        "{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    let last_panic = LAST_PANIC.lock().unwrap_or_else(PoisonError::into_inner);
+    _ = writeln!(
+        report,
+        "{}",
+        last_panic
+            .as_deref()
+            .unwrap_or("panicked for an unknown reason")
+    );
+    report.push_str(details);
+
+    // never overwrite an earlier report, even one from the same second
+    let mut attempt = 0_u32;
+    loop {
+        let path = if attempt == 0 {
+            dir.join(format!("crash-{secs}-{pid}.txt")) // @alloc
+        } else {
+            dir.join(format!("crash-{secs}-{pid}-{attempt}.txt")) // @alloc
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(report.as_bytes())?;
+                break Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => break Err(err),
+        }
+    }
+}
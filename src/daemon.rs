@@ -0,0 +1,215 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use termcolor::ColorChoice;
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::clock::Source;
+use crate::persist::Saved;
+
+/// Path to the socket which clients attach to the daemon of the stopwatch
+/// called `name` through.
+fn socket_path(name: &str) -> io::Result<PathBuf> {
+    Ok(Saved::path(name)?.with_extension("daemon.sock"))
+}
+
+/// Path to the socket which feeds the elapsed time of the stopwatch called
+/// `name` to whoever connects, such as status bars.
+fn status_socket_path(name: &str) -> io::Result<PathBuf> {
+    Ok(Saved::path(name)?.with_extension("status.sock"))
+}
+
+/// Runs the stopwatch called `name` without a terminal of its own, serving
+/// it to clients which attach with `sw attach`. Attaching from another
+/// terminal takes over from the client attached before. The stopwatch is
+/// persisted, and keeps running while no client is attached.
+///
+/// Any number of status bars and such may subscribe to the elapsed time,
+/// through `status_file` or by connecting to a socket of their own, and are
+/// all fed by the same broadcast.
+#[cfg(unix)]
+pub fn serve(
+    name: &str,
+    choice: ColorChoice,
+    read_limit: u16,
+    visual_cues: bool,
+    status_file: Option<PathBuf>,
+    clock: Option<Source>,
+) -> io::Result<()> {
+    use std::fs;
+    use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+    use std::net::Shutdown;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::{mpsc, Arc, Mutex, PoisonError};
+    use std::thread;
+    use termcolor::{Ansi, NoColor, WriteColor};
+
+    use crate::clock::SystemClock;
+    use crate::config::Config;
+    use crate::shell::Shell;
+    use crate::state::State;
+    use crate::status::Subscriber;
+
+    let path = socket_path(name)?;
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("a daemon is already serving '{name}'"),
+            ));
+        }
+        fs::remove_file(&path)?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    let status_path = status_socket_path(name)?;
+    if status_path.exists() {
+        fs::remove_file(&status_path)?;
+    }
+    let status_listener = UnixListener::bind(&status_path)?;
+
+    // the attached client, which is disconnected once another attaches
+    let current: Arc<Mutex<Option<UnixStream>>> = Arc::new(Mutex::new(None)); // @alloc
+    let (clients, attached) = mpsc::channel();
+    let accept_current = Arc::clone(&current);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let previous = accept_current
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(mut previous) = previous {
+                _ = previous.write_all(b"\ndetached, since another terminal attached\n");
+                _ = previous.shutdown(Shutdown::Both);
+            }
+            if clients.send(stream).is_err() {
+                return;
+            }
+        }
+    }); // @alloc
+
+    let mut shell = Shell::new_batch(choice, read_limit, visual_cues, Box::new(io::empty())); // @alloc
+    let (config, warnings) = Config::load();
+    shell.set_theme(config.theme);
+    shell.set_thresholds(config.thresholds.clone()); // @alloc
+    let mut cb = shell.create_cmd_buf();
+    for warning in warnings {
+        cb.warn(format_args!("{warning}"))?;
+    }
+    cb.info_idle(format_args!(
+        r#"serving stopwatch on {} (attach with "{} attach {name}")"#,
+        path.display(),
+        env!("CARGO_PKG_NAME")
+    ))?;
+    cb.info_idle(format_args!(
+        "broadcasting elapsed time on {}",
+        status_path.display()
+    ))?;
+    cb.flush()?;
+
+    let system = Box::new(SystemClock); // @alloc
+    let mut state = State::new(&mut shell, name.to_owned(), None, config, system);
+    state.resume(Saved::path(name)?, name)?;
+    state.set_clock(clock)?;
+    state.detach();
+    if let Some(path) = status_file {
+        state.write_status_to(path);
+    }
+    let broadcast = state.broadcast().clone();
+    thread::spawn(move || {
+        for stream in status_listener.incoming().flatten() {
+            broadcast.subscribe(Subscriber::Stream(Box::new(stream))); // @alloc
+        }
+    }); // @alloc
+    for stream in attached {
+        // clients introduce themselves with the terminal they attach from
+        let mut reader = BufReader::new(stream.try_clone()?); // @alloc
+        let mut client = String::new(); // @alloc
+        reader.read_line(&mut client)?;
+        *current.lock().unwrap_or_else(PoisonError::into_inner) = Some(stream.try_clone()?);
+        let writer = BufWriter::new(stream); // @alloc
+        let writer: Box<dyn WriteColor> = if choice == ColorChoice::Never {
+            Box::new(NoColor::new(writer)) // @alloc
+        } else {
+            Box::new(Ansi::new(writer)) // @alloc
+        };
+        state.attach(Box::new(reader), writer, client.trim_end())?; // @alloc
+
+        // a client which went away is no reason to stop serving
+        while let Ok(None) = state.update() {}
+        state.detach();
+        state.store(false)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(
+    _name: &str,
+    _choice: ColorChoice,
+    _read_limit: u16,
+    _visual_cues: bool,
+    _status_file: Option<PathBuf>,
+    _clock: Option<Source>,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon is only supported on unix",
+    ))
+}
+
+/// Connects the terminal to the daemon serving the stopwatch called `name`,
+/// until either side hangs up.
+#[cfg(unix)]
+pub fn attach(name: &str) -> io::Result<()> {
+    use std::io::{stdin, stdout, Read, Write};
+    use std::net::Shutdown;
+    use std::os;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    let path = socket_path(name)?;
+    let mut output = UnixStream::connect(&path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!(
+                r#"failed to attach to '{name}': {err} (is "{} daemon {name}" running?)"#,
+                env!("CARGO_PKG_NAME")
+            ),
+        )
+    })?;
+    // the shell this was run from stands for the terminal, so that the daemon
+    // can tell what changed since it was last attached from here
+    writeln!(output, "{}", os::unix::process::parent_id())?;
+    let mut input = output.try_clone()?;
+    thread::spawn(move || {
+        _ = io::copy(&mut stdin().lock(), &mut input);
+        // tell the daemon that input ended, as if its own terminal closed
+        _ = input.shutdown(Shutdown::Write);
+    }); // @alloc
+
+    // the daemon prompts without a newline, so output is passed on as it comes
+    let mut stdout = stdout().lock();
+    let mut buf = [0; 4096];
+    loop {
+        let bytes = output.read(&mut buf)?;
+        if bytes == 0 {
+            return Ok(());
+        }
+        stdout.write_all(&buf[..bytes])?;
+        stdout.flush()?;
+    }
+}
+
+#[cfg(not(unix))]
+pub fn attach(_name: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the daemon is only supported on unix",
+    ))
+}
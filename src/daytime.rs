@@ -0,0 +1,97 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt;
+use core::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::parse;
+
+const SEC_PER_DAY: u64 = 86400;
+
+/// Time of day which `input` refers to, as in `since 09:15` with `word` being
+/// `since`, or `@09:15`, if it's written that way.
+pub fn strip<'a>(input: &'a str, word: &str) -> Option<&'a str> {
+    input
+        .strip_prefix('@')
+        .or_else(|| input.strip_prefix(word)?.strip_prefix(' '))
+        .map(str::trim)
+}
+
+/// Wall-clock time of day, such as `09:15`, to the second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeOfDay {
+    /// Seconds since midnight.
+    secs: u32,
+}
+
+impl TimeOfDay {
+    /// Parses a 24-hour time written as `H:MM` or `H:MM:SS`, such as `9:15`
+    /// or `17:30:00`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = parse::normalize_digits(s.trim());
+        let fields = s.split(':').collect::<Vec<_>>(); // @alloc
+        if !(2..=3).contains(&fields.len()) {
+            return None;
+        }
+        let mut secs = 0;
+        for (idx, (field, max)) in fields.iter().zip([23, 59, 59]).enumerate() {
+            // only the hours may be a single digit
+            let len_ok = field.len() == 2 || (idx == 0 && field.len() == 1);
+            if !len_ok || !field.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let field = field.parse::<u32>().ok().filter(|field| *field <= max)?;
+            secs += field * [3600, 60, 1][idx];
+        }
+        Some(Self { secs })
+    }
+
+    /// Time of day in local time at `now`, to the second, given the offset of
+    /// local time from UTC in seconds.
+    pub fn at(now: SystemTime, offset: i64) -> Self {
+        let secs = Self::local_now(now, offset).as_secs();
+        Self {
+            secs: u32::try_from(secs).unwrap_or_default(),
+        }
+    }
+
+    /// Time since midnight in local time at `now`, given the offset of local
+    /// time from UTC in seconds.
+    pub fn local_now(now: SystemTime, offset: i64) -> Duration {
+        let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = i128::from(since_epoch.as_secs()) + i128::from(offset);
+        let of_day = u64::try_from(secs.rem_euclid(SEC_PER_DAY.into())).unwrap_or_default();
+        Duration::new(of_day, since_epoch.subsec_nanos())
+    }
+
+    /// How long ago this time of day last was, when it's `now` since
+    /// midnight. A time later than `now` was yesterday.
+    pub fn since(self, now: Duration) -> Duration {
+        let at = Duration::from_secs(self.secs.into());
+        now.checked_sub(at)
+            .unwrap_or_else(|| (now + Duration::from_secs(SEC_PER_DAY)).saturating_sub(at))
+    }
+
+    /// How long until this time of day next is, when it's `now` since
+    /// midnight. A time earlier than `now` is tomorrow, as is the time it is
+    /// now, to the nanosecond.
+    pub fn until(self, now: Duration) -> Duration {
+        let at = Duration::from_secs(self.secs.into());
+        at.checked_sub(now)
+            .filter(|until| !until.is_zero())
+            .unwrap_or_else(|| (at + Duration::from_secs(SEC_PER_DAY)).saturating_sub(now))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (hours, mins, secs) = (self.secs / 3600, self.secs / 60 % 60, self.secs % 60);
+        write!(f, "{hours:02}:{mins:02}")?;
+        if secs != 0 {
+            write!(f, ":{secs:02}")?;
+        }
+        Ok(())
+    }
+}
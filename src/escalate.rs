@@ -0,0 +1,126 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::time::Duration;
+use std::cmp;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Instant;
+
+use crate::sound::{Event, Sounds};
+
+/// How to escalate alarms which go unacknowledged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// Command which is run once an alarm has been ignored for `hook_after`.
+    pub hook: Option<String>,
+    pub hook_after: Duration,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            hook: None,
+            hook_after: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Background thread which rings the terminal bell while alarms go
+/// unacknowledged, more often the longer they're ignored. The thread exits
+/// once the escalator is stopped or dropped.
+pub struct Escalator {
+    first: Instant,
+    stop: Sender<()>,
+}
+
+impl Escalator {
+    const FIRST_INTERVAL: Duration = Duration::from_secs(30);
+    const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Starts escalating from the earliest of `due`, the instants at which
+    /// alarms go off. Rings which were due before now are skipped, so that
+    /// escalation picks up where it left off.
+    pub fn spawn(due: &[Instant], policy: Policy, sounds: Sounds) -> Option<Self> {
+        let first = *due.iter().min()?;
+        let (stop, stopped) = mpsc::channel();
+        thread::spawn(move || {
+            let now = Instant::now();
+            let mut next_ring = Self::next_ring(first, now);
+            let mut hook_at = (policy.hook.as_ref())
+                .and_then(|_| first.checked_add(policy.hook_after))
+                .filter(|hook_at| *hook_at >= now);
+            loop {
+                let now = Instant::now();
+                if now >= next_ring {
+                    Self::ring(&sounds);
+                    next_ring = Self::next_ring(first, now);
+                }
+                if hook_at.is_some_and(|hook_at| now >= hook_at) {
+                    hook_at = None;
+                    if let Some(hook) = &policy.hook {
+                        Self::run_hook(hook);
+                    }
+                }
+                let wake = hook_at.map_or(next_ring, |hook_at| cmp::min(next_ring, hook_at));
+                if stopped.recv_timeout(wake.saturating_duration_since(now))
+                    != Err(RecvTimeoutError::Timeout)
+                {
+                    return;
+                }
+            }
+        }); // @alloc
+        Some(Self { first, stop })
+    }
+
+    /// Instant at which the alarms being escalated first went off.
+    pub const fn first(&self) -> Instant {
+        self.first
+    }
+
+    /// First instant after `now` at which the bell rings for alarms which
+    /// went off at `first`. It rings when they go off, then again after
+    /// shorter and shorter intervals.
+    pub fn next_ring(first: Instant, now: Instant) -> Instant {
+        let mut ring = first;
+        let mut interval = Self::FIRST_INTERVAL;
+        while ring <= now {
+            ring += interval;
+            interval = cmp::max(interval / 2, Self::MIN_INTERVAL);
+        }
+        ring
+    }
+
+    /// Stops escalating, such as once the alarms are acknowledged. The thread
+    /// exits on its own, without being waited for.
+    pub fn stop(self) {
+        _ = self.stop.send(());
+    }
+
+    fn ring(sounds: &Sounds) {
+        let mut stdout = io::stdout().lock();
+        _ = stdout.write_all(b"\x07");
+        _ = stdout.flush();
+        _ = sounds.play(Event::Alarm);
+    }
+
+    fn run_hook(hook: &str) {
+        let mut words = hook.split_whitespace();
+        let Some(program) = words.next() else {
+            return;
+        };
+        if let Ok(mut child) = Command::new(program)
+            .args(words)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            // reap the hook once it's done, without holding up the bell
+            thread::spawn(move || child.wait()); // @alloc
+        }
+    }
+}
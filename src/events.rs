@@ -0,0 +1,80 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::time::Duration;
+use std::time::SystemTime;
+
+/// Something that happened during the session, as recorded for export.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    /// Wall-clock time at which it happened.
+    pub wall: SystemTime,
+    /// What happened, such as `"start"` or `"lap"`.
+    pub name: &'static str,
+    /// Elapsed time afterwards.
+    pub elapsed: Duration,
+    /// Whether the stopwatch was running afterwards.
+    pub running: bool,
+    /// Number of the lap which was recorded, counting from one.
+    pub lap: Option<usize>,
+    /// Length of the lap which was recorded, or how long an alarm went
+    /// unacknowledged.
+    pub duration: Option<Duration>,
+    /// Reading logged with a lap.
+    pub value: Option<f64>,
+}
+
+impl Event {
+    pub const fn new(
+        name: &'static str,
+        elapsed: Duration,
+        running: bool,
+        wall: SystemTime,
+    ) -> Self {
+        Self {
+            wall,
+            name,
+            elapsed,
+            running,
+            lap: None,
+            duration: None,
+            value: None,
+        }
+    }
+}
+
+/// Events of the session, oldest first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Events(Vec<Event>);
+
+impl Events {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn as_slice(&self) -> &[Event] {
+        &self.0
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.0.push(event); // @alloc
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Counts the events after the first `skip` by name, in the order each
+    /// name first happened.
+    pub fn tally(&self, skip: usize) -> Vec<(&'static str, usize)> {
+        let mut tally: Vec<(&'static str, usize)> = Vec::new(); // @alloc
+        for event in self.0.iter().skip(skip) {
+            match tally.iter_mut().find(|(name, _)| *name == event.name) {
+                Some((_, count)) => *count += 1,
+                None => tally.push((event.name, 1)), // @alloc
+            }
+        }
+        tally
+    }
+}
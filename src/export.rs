@@ -0,0 +1,437 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt::{self, Write as _};
+use core::time::Duration;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::events::Event;
+use crate::laps::Lap;
+use crate::state::DurationFmt;
+
+const CSV_HEADER: &str = "timestamp,event,elapsed,lap,duration,value";
+
+/// Version of the export formats, written in their verification. It's
+/// bumped when columns or keys are added, removed, or change meaning.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Renders `events` as CSV, with timestamps in RFC 3339 format and
+/// durations in seconds.
+pub fn csv(events: &[Event]) -> String {
+    let mut out = String::from(CSV_HEADER); // @alloc
+    out.push_str("\r\n");
+    for event in events {
+        _ = write!(
+            out,
+            "{},{},{},",
+            Timestamp(event.wall),
+            event.name,
+            Seconds(event.elapsed)
+        );
+        if let Some(lap) = event.lap {
+            _ = write!(out, "{lap}");
+        }
+        out.push(',');
+        if let Some(duration) = event.duration {
+            _ = write!(out, "{}", Seconds(duration));
+        }
+        out.push(',');
+        if let Some(value) = event.value {
+            _ = write!(out, "{value}");
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Verification of exported bytes, such as `sw 0.16.0, format 1, crc32
+/// cbf43926`, so that a truncated or edited export can be told apart.
+pub struct Verification<'a>(pub &'a [u8]);
+
+impl fmt::Display for Verification<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, format {FORMAT_VERSION}, crc32 {:08x}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            crc32(self.0)
+        )
+    }
+}
+
+/// CRC-32 of `bytes`, the same as gzip and zlib compute.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Writes `events` of the stopwatch called `name` to `path`, in the format
+/// chosen by its extension. Files ending in `.timeclock` are written as
+/// timeclock entries, those ending in `.json` as timewarrior intervals
+/// tagged with `tags`, and anything else as CSV. Its verification is written
+/// next to it, to the same path ending in `.check`, so that the export
+/// itself stays as readers expect. Returns the path of the verification.
+pub fn write(path: &Path, events: &[Event], name: &str, tags: &[String]) -> io::Result<PathBuf> {
+    let contents = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("timeclock") => timeclock(events, name, &local_offset_at),
+        Some("json") => timewarrior(events, name, tags),
+        _ => csv(events),
+    };
+    fs::write(path, &contents)?;
+    let mut check = path.as_os_str().to_owned();
+    check.push(".check");
+    let check = PathBuf::from(check);
+    fs::write(&check, format!("{}\n", Verification(contents.as_bytes())))?; // @alloc
+    Ok(check)
+}
+
+/// Finds the intervals during which the stopwatch ran, as the wall-clock
+/// times at which each started and stopped, going by whether it was running
+/// after each event.
+///
+/// Since events only cover the session, an interval which started before it
+/// is left out, and one which is still running has no end.
+fn intervals(events: &[Event]) -> Vec<(SystemTime, Option<SystemTime>)> {
+    let mut intervals = Vec::new(); // @alloc
+    let mut open = None;
+    // whether it was running before the first event isn't known, so only
+    // starting it then counts
+    let mut was_running = events
+        .first()
+        .is_some_and(|first| first.running && !matches!(first.name, "start" | "sequence" | "new"));
+    for event in events {
+        match (was_running, event.running) {
+            (false, true) => open = Some(event.wall),
+            (true, false) => {
+                if let Some(start) = open.take() {
+                    intervals.push((start, Some(event.wall)));
+                }
+            }
+            // starts over, even if it was running
+            (true, true) if event.name == "new" => {
+                if let Some(start) = open.replace(event.wall) {
+                    intervals.push((start, Some(event.wall)));
+                }
+            }
+            _ => (),
+        }
+        was_running = event.running;
+    }
+    if let Some(start) = open {
+        intervals.push((start, None));
+    }
+    intervals
+}
+
+/// Renders the intervals during which the stopwatch ran as timeclock
+/// entries, as read by hledger and ledger, with `name` as the account.
+/// Timestamps are in local time, as many seconds ahead of UTC as `offset`
+/// gives for each. An interval which is still running is left open.
+pub fn timeclock(events: &[Event], name: &str, offset: &dyn Fn(SystemTime) -> i64) -> String {
+    // account names end at two spaces
+    let account = name.split_whitespace().collect::<Vec<_>>().join(" "); // @alloc
+    let account = if account.is_empty() {
+        env!("CARGO_PKG_NAME")
+    } else {
+        &account
+    };
+    let mut out = String::new(); // @alloc
+    for (start, end) in intervals(events) {
+        _ = writeln!(out, "i {} {account}", LocalTime(start, offset(start)));
+        if let Some(end) = end {
+            _ = writeln!(out, "o {}", LocalTime(end, offset(end)));
+        }
+    }
+    out
+}
+
+/// Renders the intervals during which the stopwatch ran as a JSON array, as
+/// read by `timew import`. Each interval is tagged with `name`, if it isn't
+/// empty, followed by `tags`. An interval which is still running has no end.
+pub fn timewarrior(events: &[Event], name: &str, tags: &[String]) -> String {
+    let name = name.trim();
+    let tags = (!name.is_empty())
+        .then_some(name)
+        .into_iter()
+        .chain(tags.iter().map(String::as_str));
+    let mut out = String::from("["); // @alloc
+    for (idx, (start, end)) in intervals(events).into_iter().enumerate() {
+        if idx != 0 {
+            out.push(',');
+        }
+        _ = write!(out, "{{\"start\":\"{}\",", Compact(start));
+        if let Some(end) = end {
+            _ = write!(out, "\"end\":\"{}\",", Compact(end));
+        }
+        out.push_str("\"tags\":[");
+        for (idx, tag) in tags.clone().enumerate() {
+            if idx != 0 {
+                out.push(',');
+            }
+            json_str(&mut out, tag);
+        }
+        out.push_str("]}");
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Offset of local time from UTC in seconds, as reported by `date`. Times
+/// are written in UTC if it can't be determined.
+pub fn local_offset() -> i64 {
+    date_offset(&[]).unwrap_or(0)
+}
+
+/// Offset of local time from UTC in seconds at `time`, which differs from
+/// [`local_offset`] across a change to or from daylight saving time.
+pub fn local_offset_at(time: SystemTime) -> i64 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    // GNU `date` takes the time with `-d`, and BSD `date` with `-r`
+    let gnu = format!("@{secs}"); // @alloc
+    let bsd = secs.to_string(); // @alloc
+    date_offset(&["-d", &gnu])
+        .or_else(|| date_offset(&["-r", &bsd]))
+        .unwrap_or(0)
+}
+
+/// Offset of local time from UTC in seconds, as reported by `date` when
+/// passed `args`, or [`None`] if it can't be determined.
+fn date_offset(args: &[&str]) -> Option<i64> {
+    let output = Command::new("date")
+        .args(args)
+        .arg("+%z")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    // such as "+0530"
+    let offset = String::from_utf8_lossy(&output.stdout);
+    let offset = offset.trim();
+    let (sign, digits) = match offset.split_at(offset.len().min(1)) {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, mins) = digits.split_at(2);
+    let (Ok(hours), Ok(mins)) = (hours.parse::<i64>(), mins.parse::<i64>()) else {
+        return None;
+    };
+    Some(sign * (hours * 3600 + mins * 60))
+}
+
+/// State of a stopwatch, for machine-readable output.
+pub struct Snapshot<'a> {
+    pub name: &'a str,
+    pub tags: &'a [String],
+    pub elapsed: Duration,
+    pub running: bool,
+    pub precision: u8,
+    pub laps: &'a [Lap],
+    /// How long the countdown has run past zero, if it expired without being
+    /// acknowledged.
+    pub overtime: Option<Duration>,
+    /// Clock the elapsed time is kept by, such as `"monotonic"`.
+    pub clock: &'static str,
+}
+
+/// Renders `snapshot` as a single line of JSON, with durations in seconds.
+/// The last key is `checksum`, holding the [`Verification`] of every byte
+/// before it.
+pub fn json(snapshot: &Snapshot) -> String {
+    let mut out = String::from("{\"name\":"); // @alloc
+    json_str(&mut out, snapshot.name);
+    out.push_str(",\"tags\":[");
+    for (idx, tag) in snapshot.tags.iter().enumerate() {
+        if idx != 0 {
+            out.push(',');
+        }
+        json_str(&mut out, tag);
+    }
+    out.push(']');
+    _ = write!(
+        out,
+        ",\"elapsed\":{},\"display\":\"{}\",\"running\":{},\"precision\":{},\"laps\":[",
+        Seconds(snapshot.elapsed),
+        DurationFmt::new(snapshot.elapsed, snapshot.precision, true),
+        snapshot.running,
+        snapshot.precision
+    );
+    for (idx, lap) in snapshot.laps.iter().enumerate() {
+        if idx != 0 {
+            out.push(',');
+        }
+        _ = write!(
+            out,
+            "{{\"split\":{},\"time\":{},\"value\":",
+            Seconds(lap.split),
+            Seconds(lap.time)
+        );
+        match lap.value {
+            Some(value) => _ = write!(out, "{value}"),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"label\":");
+        match &lap.label {
+            Some(label) => json_str(&mut out, label),
+            None => out.push_str("null"),
+        }
+        out.push('}');
+    }
+    out.push_str("],\"overtime\":");
+    match snapshot.overtime {
+        Some(overtime) => _ = write!(out, "{}", Seconds(overtime)),
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"clock\":");
+    json_str(&mut out, snapshot.clock);
+    // verifies everything before it
+    let verification = Verification(out.as_bytes()).to_string(); // @alloc
+    out.push_str(",\"checksum\":");
+    json_str(&mut out, &verification);
+    out.push('}');
+    out
+}
+
+/// Renders the shell's commands as a JSON array, for launchers and
+/// completion engines. Each command has its long name, its short name (empty
+/// for display, which runs on enter), the grammar of its argument or `null`,
+/// and its description.
+pub fn commands() -> String {
+    let mut out = String::from("["); // @alloc
+    for (idx, cmd) in crate::command::Command::iter().iter().enumerate() {
+        if idx != 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        json_str(&mut out, cmd.long_name());
+        out.push_str(",\"short\":");
+        json_str(&mut out, cmd.short_name_literal());
+        out.push_str(",\"argument\":");
+        match cmd.argument() {
+            Some(argument) => json_str(&mut out, argument),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"description\":");
+        json_str(&mut out, cmd.description());
+        out.push('}');
+    }
+    out.push(']');
+    out
+}
+
+/// Writes `s` as a JSON string, quoted and escaped.
+fn json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for chr in s.chars() {
+        match chr {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            chr if chr.is_control() => _ = write!(out, "\\u{:04x}", u32::from(chr)),
+            chr => out.push(chr),
+        }
+    }
+    out.push('"');
+}
+
+/// Duration written as decimal seconds, such as `83.250000000`.
+pub struct Seconds(pub Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:09}", self.0.as_secs(), self.0.subsec_nanos())
+    }
+}
+
+/// Wall-clock time written in RFC 3339 format in UTC, such as
+/// `2024-01-02T03:04:05.678Z`.
+pub struct Timestamp(pub SystemTime);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let (year, month, day, hour, min, sec) = civil_time(since_epoch.as_secs());
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{min:02}:{sec:02}.{:03}Z",
+            since_epoch.subsec_millis()
+        )
+    }
+}
+
+/// Wall-clock time written in UTC in the compact ISO 8601 format used by
+/// timewarrior, such as `20240102T030405Z`.
+pub struct Compact(pub SystemTime);
+
+impl fmt::Display for Compact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let (year, month, day, hour, min, sec) = civil_time(since_epoch.as_secs());
+        write!(f, "{year:04}{month:02}{day:02}T{hour:02}{min:02}{sec:02}Z")
+    }
+}
+
+/// Wall-clock time written as local time in timeclock format, such as
+/// `2024/01/02 03:04:05`, given the offset of local time from UTC in
+/// seconds.
+pub struct LocalTime(pub SystemTime, pub i64);
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let utc = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = i64::try_from(utc.as_secs()).unwrap_or(i64::MAX);
+        let secs = u64::try_from(secs.saturating_add(self.1)).unwrap_or(0);
+        let (year, month, day, hour, min, sec) = civil_time(secs);
+        write!(
+            f,
+            "{year:04}/{month:02}/{day:02} {hour:02}:{min:02}:{sec:02}"
+        )
+    }
+}
+
+/// Splits seconds since the Unix epoch into a date and time of day, as the
+/// year, month, day, hour, minute, and second.
+fn civil_time(secs: u64) -> (u64, u64, u64, u64, u64, u64) {
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let secs_of_day = secs % 86_400;
+    let (hour, min, sec) = (secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60);
+    (year, month, day, hour, min, sec)
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian calendar
+/// date, following Howard Hinnant's `civil_from_days`.
+pub fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,48 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::time::Duration;
+use std::io;
+use std::process::{self, Stdio};
+use std::thread;
+
+use crate::export::Seconds;
+use crate::sound::Event;
+
+/// Commands run as events happen, such as to log time with another program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hooks {
+    commands: [Option<String>; Event::ALL.len()],
+}
+
+impl Hooks {
+    pub fn set(&mut self, event: Event, command: String) {
+        self.commands[event as usize] = Some(command);
+    }
+
+    /// Starts the command of `event`, if it has one. The command runs in the
+    /// background, with the event, the name of the stopwatch, and its elapsed
+    /// time in seconds in the environment.
+    pub fn run(&self, event: Event, name: &str, elapsed: Duration) -> io::Result<()> {
+        let Some(command) = &self.commands[event as usize] else {
+            return Ok(());
+        };
+        let mut words = command.split_whitespace();
+        let Some(program) = words.next() else {
+            return Ok(());
+        };
+        let mut child = process::Command::new(program)
+            .args(words)
+            .env("SW_EVENT", event.name())
+            .env("SW_NAME", name)
+            .env("SW_ELAPSED", Seconds(elapsed).to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // reap the command once it's done, without holding up the shell
+        thread::spawn(move || child.wait()); // @alloc
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::str::FromStr;
+use core::time::Duration;
+
+use crate::parse::ReadDur;
+use crate::shell::Escaped;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lap {
+    /// Elapsed time when the lap was recorded.
+    pub split: Duration,
+    /// Elapsed time since the previous lap.
+    pub time: Duration,
+    /// Auxiliary reading logged with the lap, such as a heart rate.
+    pub value: Option<f64>,
+    /// Note about the lap, such as `"first draft"`.
+    pub label: Option<String>,
+}
+
+/// Summary of the lap times, along with which laps were fastest and
+/// slowest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Index of the first of the fastest laps.
+    pub fastest: usize,
+    /// Index of the first of the slowest laps.
+    pub slowest: usize,
+}
+
+/// Splits what was given with a lap into its reading and label, as in
+/// `"156 uphill"`. Without a reading first, it's all the label.
+pub fn parse_arg(s: &str) -> (Option<f64>, Option<&str>) {
+    let (first, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+    let (value, label) = match parse_value(first) {
+        Ok(value) => (Some(value), rest.trim()),
+        Err(_) => (None, s.trim()),
+    };
+    (value, Some(label).filter(|label| !label.is_empty()))
+}
+
+/// Parses the number of a lap, counting from one, out of `count` laps.
+pub fn parse_number(s: &str, count: usize) -> Result<usize, String> {
+    match crate::parse::normalize_digits(s).parse::<usize>() {
+        Ok(number) if (1..=count).contains(&number) => Ok(number),
+        Ok(_) if count == 0 => Err(String::from("no laps recorded")), // @alloc
+        Ok(_) => Err(format!("no such lap (expected 1 to {count})")), // @alloc
+        Err(_) => Err(format!("invalid lap number '{}'", Escaped(s))), // @alloc
+    }
+}
+
+/// Parses the reading given with a lap, such as `"156"`.
+pub fn parse_value(s: &str) -> Result<f64, String> {
+    crate::parse::normalize_digits(s)
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("invalid lap value '{}' (expected a number)", Escaped(s)))
+}
+
+/// When to announce progress while recording laps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Announce {
+    /// Announce after every this many laps, or never if zero.
+    pub every: u32,
+}
+
+/// Goal which the laps work towards, used to predict the finish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Number of laps in the whole event.
+    Laps(u32),
+    /// Elapsed time at which the event ends, however many laps that takes.
+    Total(Duration),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parses a number of laps, such as `"10"`, or else a duration, such as
+    /// `"30m"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid lap target '{}' (expected a number of laps or a duration)",
+                Escaped(s)
+            ) // @alloc
+        };
+        if let Ok(count) = crate::parse::normalize_digits(s).parse::<u32>() {
+            return Some(count)
+                .filter(|count| *count != 0)
+                .map(Self::Laps)
+                .ok_or_else(invalid);
+        }
+        match ReadDur::parse(s, false) {
+            Some(Ok(ReadDur { dur, .. })) if !dur.is_zero() => Ok(Self::Total(dur)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Finish expected at the average lap time so far.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Prediction {
+    pub target: Target,
+    /// Elapsed time at the finish.
+    pub finish: Duration,
+    /// Number of laps by the finish, which may be fractional if the target is
+    /// a duration.
+    pub laps: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Laps {
+    recorded: Vec<Lap>,
+    pub announce: Announce,
+    pub target: Option<Target>,
+}
+
+impl Laps {
+    pub const fn new() -> Self {
+        Self {
+            recorded: Vec::new(),
+            announce: Announce { every: 0 },
+            target: None,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Lap] {
+        &self.recorded
+    }
+
+    pub fn clear(&mut self) {
+        self.recorded.clear();
+    }
+
+    /// Records a lap ending at `split`, along with an optional reading and
+    /// label. If the elapsed time went backwards since the previous lap, the
+    /// lap time is zero.
+    pub fn record(&mut self, split: Duration, value: Option<f64>, label: Option<&str>) -> &Lap {
+        let prev = self.recorded.last().map_or(Duration::ZERO, |lap| lap.split);
+        self.recorded.push(Lap {
+            split,
+            time: split.saturating_sub(prev),
+            value,
+            label: label.map(str::to_string), // @alloc
+        }); // @alloc
+        &self.recorded[self.recorded.len() - 1]
+    }
+
+    /// Replaces the label of lap `number`, counting from one, returning the
+    /// old one.
+    pub fn relabel(&mut self, number: usize, label: Option<&str>) -> Option<String> {
+        let lap = &mut self.recorded[number - 1];
+        core::mem::replace(&mut lap.label, label.map(str::to_string)) // @alloc
+    }
+
+    /// Deletes lap `number`, counting from one. Its time goes to the lap
+    /// after it, so that the splits stay the same.
+    pub fn remove(&mut self, number: usize) -> Lap {
+        let lap = self.recorded.remove(number - 1);
+        if let Some(next) = self.recorded.get_mut(number - 1) {
+            next.time = next.time.saturating_add(lap.time);
+        }
+        lap
+    }
+
+    /// Whether progress should be announced now that the latest lap has been
+    /// recorded.
+    pub fn should_announce(&self) -> bool {
+        let count = self.recorded.len();
+        self.announce.every != 0
+            && count != 0
+            && u32::try_from(count).is_ok_and(|count| count % self.announce.every == 0)
+    }
+
+    /// Average lap time, which is the pace used for projections.
+    pub fn average(&self) -> Option<Duration> {
+        let last = self.recorded.last()?;
+        let count = u32::try_from(self.recorded.len()).ok()?;
+        Some(last.split / count)
+    }
+
+    /// Summarizes the lap times, if any laps were recorded.
+    pub fn stats(&self) -> Option<Stats> {
+        let count = u32::try_from(self.recorded.len()).ok()?;
+        let mut times: Vec<Duration> = self.recorded.iter().map(|lap| lap.time).collect(); // @alloc
+        let (fastest, &min) = times.iter().enumerate().min_by_key(|(_, time)| **time)?;
+        let (slowest, &max) = times
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, time)| **time)?;
+        let total = times
+            .iter()
+            .fold(Duration::ZERO, |total, time| total.saturating_add(*time));
+        times.sort_unstable();
+        let mid = times.len() / 2;
+        let median = if times.len() % 2 == 0 {
+            // halfway between the middle two, without overflowing
+            let (low, high) = (times[mid - 1], times[mid]);
+            low + high.saturating_sub(low) / 2
+        } else {
+            times[mid]
+        };
+        Some(Stats {
+            min,
+            max,
+            mean: total / count,
+            median,
+            fastest,
+            slowest,
+        })
+    }
+
+    /// Elapsed time at which the last of `target` laps is expected to finish,
+    /// extrapolated from the average pace so far.
+    pub fn projected_finish(&self, target: u32) -> Option<Duration> {
+        self.average()?.checked_mul(target)
+    }
+
+    /// Predicts when `target` is reached, extrapolated from the average pace
+    /// so far.
+    pub fn predict(&self, target: Target) -> Option<Prediction> {
+        let (finish, laps) = match target {
+            Target::Laps(count) => (self.projected_finish(count)?, f64::from(count)),
+            Target::Total(total) => {
+                let average = self.average().filter(|average| !average.is_zero())?;
+                (total, total.as_secs_f64() / average.as_secs_f64())
+            }
+        };
+        Some(Prediction {
+            target,
+            finish,
+            laps,
+        })
+    }
+}
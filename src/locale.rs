@@ -0,0 +1,134 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::str::FromStr;
+use std::env;
+
+/// Language in which the elapsed time is written out in words, as when
+/// visual cues are disabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Spanish,
+    French,
+    Polish,
+    Russian,
+}
+
+/// Form which a word takes after a number, by the plural rules of a
+/// language.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plural {
+    One,
+    Few,
+    Many,
+    /// Used for fractions, and for whole numbers in languages with only two
+    /// forms.
+    Other,
+}
+
+/// Unit of a duration written out in words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Parses a language tag such as `fr` or a locale such as `fr_FR.UTF-8`,
+    /// by its language.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['_', '.', '@', '-']).next().unwrap_or_default();
+        match language {
+            "en" | "C" | "POSIX" => Ok(Self::English),
+            "de" => Ok(Self::German),
+            "es" => Ok(Self::Spanish),
+            "fr" => Ok(Self::French),
+            "pl" => Ok(Self::Polish),
+            "ru" => Ok(Self::Russian),
+            _ => Err(format!(
+                "expected 'en', 'de', 'es', 'fr', 'pl', or 'ru', found '{s}'"
+            )), // @alloc
+        }
+    }
+}
+
+impl Locale {
+    /// Locale given by the environment, or English if it's unset or has no
+    /// translation.
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+            .and_then(|locale| locale.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Form of a word after `count` of it, or after a fraction.
+    pub const fn plural(self, count: u64, fraction: bool) -> Plural {
+        let (last, last_two) = (count % 10, count % 100);
+        let few = 2 <= last && last <= 4 && !(12 <= last_two && last_two <= 14);
+        let one = match self {
+            // "1,5 seconde", as with anything below two
+            Self::French => count <= 1,
+            Self::Russian => last == 1 && last_two != 11,
+            _ => count == 1 && !fraction,
+        };
+        if fraction && !matches!(self, Self::French) {
+            Plural::Other
+        } else if one {
+            Plural::One
+        } else if !matches!(self, Self::Polish | Self::Russian) {
+            Plural::Other
+        } else if few {
+            Plural::Few
+        } else {
+            Plural::Many
+        }
+    }
+
+    /// Name of `unit` after `count` of it, or after a fraction.
+    pub const fn unit(self, unit: Unit, count: u64, fraction: bool) -> &'static str {
+        let plural = self.plural(count, fraction);
+        let forms = match (self, unit) {
+            (Self::English, Unit::Day) => ["day", "days", "days", "days"],
+            (Self::English, Unit::Hour) => ["hour", "hours", "hours", "hours"],
+            (Self::English | Self::French, Unit::Minute) => {
+                ["minute", "minutes", "minutes", "minutes"]
+            }
+            (Self::English, Unit::Second) => ["second", "seconds", "seconds", "seconds"],
+            (Self::German, Unit::Day) => ["Tag", "Tage", "Tage", "Tage"],
+            (Self::German, Unit::Hour) => ["Stunde", "Stunden", "Stunden", "Stunden"],
+            (Self::German, Unit::Minute) => ["Minute", "Minuten", "Minuten", "Minuten"],
+            (Self::German, Unit::Second) => ["Sekunde", "Sekunden", "Sekunden", "Sekunden"],
+            (Self::Spanish, Unit::Day) => ["día", "días", "días", "días"],
+            (Self::Spanish, Unit::Hour) => ["hora", "horas", "horas", "horas"],
+            (Self::Spanish, Unit::Minute) => ["minuto", "minutos", "minutos", "minutos"],
+            (Self::Spanish, Unit::Second) => ["segundo", "segundos", "segundos", "segundos"],
+            (Self::French, Unit::Day) => ["jour", "jours", "jours", "jours"],
+            (Self::French, Unit::Hour) => ["heure", "heures", "heures", "heures"],
+            (Self::French, Unit::Second) => ["seconde", "secondes", "secondes", "secondes"],
+            (Self::Polish, Unit::Day) => ["dzień", "dni", "dni", "dnia"],
+            (Self::Polish, Unit::Hour) => ["godzina", "godziny", "godzin", "godziny"],
+            (Self::Polish, Unit::Minute) => ["minuta", "minuty", "minut", "minuty"],
+            (Self::Polish, Unit::Second) => ["sekunda", "sekundy", "sekund", "sekundy"],
+            (Self::Russian, Unit::Day) => ["день", "дня", "дней", "дня"],
+            (Self::Russian, Unit::Hour) => ["час", "часа", "часов", "часа"],
+            (Self::Russian, Unit::Minute) => ["минута", "минуты", "минут", "минуты"],
+            (Self::Russian, Unit::Second) => ["секунда", "секунды", "секунд", "секунды"],
+        };
+        match plural {
+            Plural::One => forms[0],
+            Plural::Few => forms[1],
+            Plural::Many => forms[2],
+            Plural::Other => forms[3],
+        }
+    }
+}
@@ -5,25 +5,69 @@
 #![warn(clippy::cargo, clippy::pedantic)]
 #![forbid(unsafe_code)]
 
+mod alarm;
+mod big;
+mod changelog;
+mod chess;
+mod clock;
 mod command;
+mod completions;
+mod config;
+mod control;
+mod countdown;
+mod crash;
+mod daemon;
+mod daytime;
+mod escalate;
+mod events;
+mod export;
+mod hook;
+mod laps;
+mod locale;
+mod marks;
+mod meta;
+mod oneshot;
 mod parse;
+mod persist;
+mod plumbing;
+mod precision;
 mod shell;
+mod sound;
 mod state;
+mod status;
+mod template;
+mod theme;
+mod usage;
 
 const MAX_NANOS_CHARS: u8 = 9;
 const SHELL_READ_LIMIT: u16 = 1024;
 
 #[cfg(test)]
+#[allow(clippy::cast_possible_truncation, clippy::useless_conversion)]
 mod tests;
 
 use argh::FromArgs;
-use termcolor::ColorChoice;
+use termcolor::{ColorChoice, ColorSpec};
 
-use std::io::{self, stderr, stdin, stdout, BufWriter, IsTerminal, Write};
+use core::str::FromStr;
+use std::env;
+use std::fs::File;
+use std::io::{self, stderr, stdin, stdout, BufReader, BufWriter, IsTerminal, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::process::ExitCode;
 
+use crate::clock::{Source, SystemClock};
+use crate::completions::{Target, Usage};
+use crate::config::Config;
+use crate::export::Seconds;
+use crate::locale::Locale;
+use crate::oneshot::{Attach, Daemon, Oneshot};
+use crate::parse::ReadDur;
+use crate::persist::Saved;
+use crate::precision::Spec;
 use crate::shell::Shell;
-use crate::state::{Passback, State};
+use crate::state::{DisplayStyle, DurationFmt, Passback, State};
 
 /// Terminal stopwatch that runs as a shell.
 #[allow(clippy::struct_excessive_bools)]
@@ -33,7 +77,11 @@ struct Args {
     #[argh(short = 'v', switch)]
     no_visual_cues: bool,
 
-    /// disable the use of colors in output
+    /// when to use colors in output: "auto", "always", or "never"
+    #[argh(option, default = "ColorWhen::Auto")]
+    color: ColorWhen,
+
+    /// disable the use of colors in output, like --color never
     #[argh(short = 'c', switch)]
     no_colors: bool,
 
@@ -41,13 +89,144 @@ struct Args {
     #[argh(switch)]
     no_tty_check: bool,
 
+    /// run semicolon-separated commands against the saved stopwatch, then
+    /// exit
+    #[argh(option, short = 'e')]
+    exec: Option<String>,
+
+    /// run commands from a file, then exit
+    #[argh(option)]
+    script: Option<PathBuf>,
+
+    /// persist the stopwatch, keyed by the current directory
+    #[argh(switch)]
+    here: bool,
+
+    /// write the session's events to a file when quitting
+    #[argh(option)]
+    export: Option<PathBuf>,
+
+    /// keep the elapsed time written to a file or FIFO, for status bars
+    #[argh(option)]
+    status_file: Option<PathBuf>,
+
+    /// accept commands from other programs on a unix socket
+    #[argh(switch)]
+    socket: bool,
+
+    /// write only the final elapsed time to stdout, as "clock" or "seconds"
+    #[argh(option)]
+    print_on_exit: Option<ExitFormat>,
+
+    /// clock to keep time by: "monotonic", "wall", or "auto"
+    #[argh(option)]
+    clock: Option<Source>,
+
+    /// elapsed time to start from, such as "1:30:00"
+    #[argh(option)]
+    elapsed: Option<String>,
+
+    /// set the stopwatch name, which may then be followed by a duration
+    #[argh(option, long = "name")]
+    named: Option<String>,
+
+    /// number of subsecond digits to display, or "auto"
+    #[argh(option)]
+    precision: Option<Spec>,
+
+    /// start the stopwatch as soon as the shell opens
+    #[argh(switch)]
+    start: bool,
+
     /// display version
     #[argh(short = 'V', switch)]
     version: bool,
 
-    /// set the stopwatch name
+    /// print the shell's commands as JSON, for other programs
+    #[argh(switch)]
+    list_commands: bool,
+
+    /// print a completion script for "bash", "zsh", or "fish"
+    #[argh(option)]
+    completions: Option<Target>,
+
+    /// set the stopwatch name, or the elapsed time to start from if it's a
+    /// duration
     #[argh(positional)]
     name: Option<String>,
+
+    #[argh(subcommand)]
+    oneshot: Option<Oneshot>,
+}
+
+/// How `--print-on-exit` writes the elapsed time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExitFormat {
+    /// Like the shell displays it, such as `00:01:23.25`.
+    Clock,
+    /// Decimal seconds, such as `83.250000000`.
+    Seconds,
+}
+
+impl FromStr for ExitFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clock" => Ok(Self::Clock),
+            "seconds" => Ok(Self::Seconds),
+            _ => Err(format!("expected 'clock' or 'seconds', found '{s}'")), // @alloc
+        }
+    }
+}
+
+/// When to use colors, as given to `--color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorWhen {
+    /// If output goes to a terminal, and the environment doesn't say
+    /// otherwise.
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorWhen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "expected 'auto', 'always', or 'never', found '{s}'"
+            )), // @alloc
+        }
+    }
+}
+
+impl ColorWhen {
+    /// Decides whether to use colors. Automatically, they're forced on by a
+    /// `CLICOLOR_FORCE` other than `0`, and otherwise turned off by a
+    /// non-empty `NO_COLOR`, by `TERM=dumb`, or if output isn't a terminal.
+    fn choice(self, output_is_terminal: bool) -> ColorChoice {
+        let var = |name| env::var_os(name).filter(|value| !value.is_empty());
+        match self {
+            Self::Always => ColorChoice::Always,
+            Self::Never => ColorChoice::Never,
+            Self::Auto if var("CLICOLOR_FORCE").is_some_and(|force| force != "0") => {
+                ColorChoice::Always
+            }
+            Self::Auto
+                if var("NO_COLOR").is_some()
+                    || var("TERM").is_some_and(|term| term == "dumb")
+                    || !output_is_terminal =>
+            {
+                ColorChoice::Never
+            }
+            Self::Auto => ColorChoice::Auto,
+        }
+    }
 }
 
 fn main() -> ExitCode {
@@ -59,60 +238,317 @@ fn main() -> ExitCode {
     }
 
     let args: Args = argh::from_env();
-    if let Err(err) = try_main(args) {
-        _ = print_error(&err);
-        ExitCode::FAILURE
-    } else {
-        ExitCode::SUCCESS
+    match try_main(args) {
+        Ok(code) => code,
+        Err(err) => {
+            _ = print_error(&err);
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn try_main(args: Args) -> io::Result<()> {
+#[allow(clippy::too_many_lines)]
+fn try_main(args: Args) -> io::Result<ExitCode> {
     if args.version {
         let mut stdout = BufWriter::new(stdout()); // @alloc
-        writeln!(
+        for line in meta::version_lines() {
+            writeln!(stdout, "{line}")?;
+        }
+        stdout.flush()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if args.list_commands {
+        let mut stdout = BufWriter::new(stdout()); // @alloc
+        writeln!(stdout, "{}", export::commands())?;
+        stdout.flush()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(target) = args.completions {
+        // the usage information is generated from the definitions above
+        let help = |words: &[&str]| {
+            Args::from_args(&["sw"], words)
+                .err()
+                .map(|exit| exit.output)
+                .unwrap_or_default()
+        };
+        let help_top = help(&["--help"]);
+        let usage = Usage::parse(&help_top);
+        let help_subs = usage
+            .commands
+            .iter()
+            .map(|(name, _)| help(&[name, "--help"]))
+            .collect::<Vec<_>>(); // @alloc
+        let subcommands = help_subs
+            .iter()
+            .map(|help| Usage::parse(help))
+            .collect::<Vec<_>>(); // @alloc
+        let mut stdout = BufWriter::new(stdout()); // @alloc
+        write!(
             stdout,
-            "{name} {version}",
-            name = env!("CARGO_PKG_NAME"),
-            version = env!("CARGO_PKG_VERSION")
+            "{}",
+            completions::script(target, &usage, &subcommands)
         )?;
         stdout.flush()?;
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
-    if !args.no_tty_check {
-        if !stdout().is_terminal() {
+    // a lone duration is where to start from, rather than a name
+    let (name, elapsed) = match (args.name, args.elapsed) {
+        (Some(name), None) if matches!(ReadDur::parse(&name, false), Some(Ok(_))) => {
+            (None, Some(name))
+        }
+        given => given,
+    };
+    if args.named.is_some() && args.oneshot.is_some() {
+        // subcommands take the name as their own argument
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--name can't be used with a subcommand (name the stopwatch after the subcommand instead)",
+        ));
+    }
+    let name = match (args.named, name) {
+        (Some(named), Some(name)) => {
             return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "stdout is not a terminal (pass --no-tty-check to ignore)",
-            ));
-        } else if !stdin().is_terminal() {
+                io::ErrorKind::InvalidInput,
+                format!("name given twice, as '{named}' and '{name}'"),
+            ))
+        }
+        (named, name) => named.or(name),
+    };
+    let elapsed = match elapsed
+        .as_deref()
+        .map(|input| (input, ReadDur::parse(input, false)))
+    {
+        Some((_, Some(Ok(ReadDur { dur, .. })))) => Some(dur),
+        Some((input, Some(Err(err)))) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid elapsed time '{input}': {err}"),
+            ))
+        }
+        Some((_, None)) => {
             return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "stdin is not a terminal (pass --no-tty-check to ignore)",
-            ));
+                io::ErrorKind::InvalidInput,
+                "expected an elapsed time to start from",
+            ))
         }
+        None => None,
+    };
+
+    let color = if args.no_colors {
+        ColorWhen::Never
+    } else {
+        args.color
+    };
+    // output goes to stderr if stdout is for the caller
+    let cc = color.choice(if args.print_on_exit.is_some() {
+        stderr().is_terminal()
+    } else {
+        stdout().is_terminal()
+    });
+
+    match args.oneshot {
+        // these run the shell rather than a single action
+        Some(Oneshot::Daemon(Daemon { name })) => {
+            // output goes to the terminals of attached clients
+            let cc = color.choice(true);
+            daemon::serve(
+                &name,
+                cc,
+                SHELL_READ_LIMIT,
+                !args.no_visual_cues,
+                args.status_file,
+                args.clock,
+            )?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Oneshot::Attach(Attach { name })) => {
+            daemon::attach(&name)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(oneshot) => {
+            let mut shell = Shell::new(cc, SHELL_READ_LIMIT, !args.no_visual_cues);
+            oneshot.run(&mut shell)?;
+            shell.finish()?;
+            return Ok(if shell.errored() {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            });
+        }
+        None => (),
     }
 
-    let cc = if args.no_colors {
-        ColorChoice::Never
+    let mut upgraded_from = None;
+    let mut shell = if let Some(commands) = &args.exec {
+        // each command is read as if it were a line of a script
+        let script = commands
+            .split(';')
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n"); // @alloc
+        Shell::new_batch(
+            cc,
+            SHELL_READ_LIMIT,
+            !args.no_visual_cues,
+            Box::new(io::Cursor::new(script)), // @alloc
+        )
+    } else if let Some(path) = &args.script {
+        let file = File::open(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to open script {}: {err}", path.display()),
+            )
+        })?;
+        Shell::new_batch(
+            cc,
+            SHELL_READ_LIMIT,
+            !args.no_visual_cues,
+            Box::new(BufReader::new(file)), // @alloc
+        )
+    } else if args.no_tty_check && !stdin().is_terminal() {
+        Shell::new_batch(
+            cc,
+            SHELL_READ_LIMIT,
+            !args.no_visual_cues,
+            Box::new(stdin().lock()), // @alloc
+        )
     } else {
-        ColorChoice::Auto
+        if !args.no_tty_check {
+            // output goes to stderr if stdout is for the caller
+            let (output, output_is_terminal) = if args.print_on_exit.is_some() {
+                ("stderr", stderr().is_terminal())
+            } else {
+                ("stdout", stdout().is_terminal())
+            };
+            if !output_is_terminal {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{output} is not a terminal (pass --no-tty-check to ignore)"),
+                ));
+            } else if !stdin().is_terminal() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "stdin is not a terminal (pass --no-tty-check to ignore)",
+                ));
+            }
+        }
+
+        // not being able to record the version is no reason to stop
+        upgraded_from = changelog::record_version().ok().flatten();
+
+        Shell::new(cc, SHELL_READ_LIMIT, !args.no_visual_cues)
     };
-    let mut shell = Shell::new(cc, SHELL_READ_LIMIT, !args.no_visual_cues);
-    shell.splash_text()?;
+    if args.print_on_exit.is_some() {
+        shell.write_to_stderr();
+    }
+    if shell.is_interactive() {
+        shell.splash_text(upgraded_from.as_deref())?;
+    }
 
-    let name = args.name.unwrap_or_default();
-    let mut state = State::new(&mut shell, name);
-    loop {
+    let (config, warnings) = Config::load();
+    shell.set_live_input(config.live_input);
+    shell.set_reduced_motion(config.reduced_motion);
+    shell.set_single_key(config.single_key);
+    shell.set_display_style(DisplayStyle {
+        rounding: config.rounding,
+        hide_zero_hours: config.hide_zero_hours,
+        separator: config.group_digits.then(config::digit_separator),
+        locale: config.locale.unwrap_or_else(Locale::detect),
+    });
+    if let Some(width) = config.name_width {
+        shell.set_name_width(width);
+    }
+    shell.set_theme(config.theme);
+    shell.set_thresholds(config.thresholds.clone()); // @alloc
+    let mut cb = shell.create_cmd_buf();
+    for warning in warnings {
+        cb.warn(format_args!("{warning}"))?;
+    }
+
+    let name = name.unwrap_or_default();
+    // commands run against the stopwatch saved under its name, so that it
+    // lasts from one run to the next
+    let exec_path = args.exec.as_ref().map(|_| Saved::path(&name)).transpose()?;
+    let mut state = State::new(
+        &mut shell,
+        name,
+        upgraded_from,
+        config,
+        Box::new(SystemClock), // @alloc
+    );
+    if args.here {
+        let dir = env::current_dir()?;
+        let dir_name = dir.file_name().unwrap_or_default().to_string_lossy();
+        state.resume(Saved::path_for_dir(&dir)?, &dir_name)?;
+    } else if let Some(path) = exec_path {
+        state.resume(path, "")?;
+    } else {
+        state.recover_disconnected()?;
+    }
+    state.set_clock(args.clock)?;
+    if let Some(spec) = args.precision {
+        state.set_precision(spec)?;
+    }
+    if let Some(elapsed) = elapsed {
+        state.set_elapsed(elapsed)?;
+    }
+    if args.start {
+        state.start()?;
+    }
+    state.offer_backfill()?;
+    if let Some(path) = args.status_file {
+        state.write_status_to(path);
+    }
+    if args.socket {
+        state.listen()?;
+    }
+    crash::install_hook();
+    let run = panic::catch_unwind(AssertUnwindSafe(|| loop {
         if let Some(passback) = state.update()? {
             match passback {
-                Passback::Quit => break,
+                Passback::Quit => return Ok(()),
+                Passback::Disconnected => return state.disconnect(),
             }
         }
+    }));
+    let Ok(result) = run else {
+        let mut details = String::new(); // @alloc
+        state.describe(&mut details);
+        drop(state);
+        // colors are reset here, and raw mode was left while unwinding
+        _ = shell.writeln(&ColorSpec::new(), format_args!(""));
+        _ = shell.finish();
+        let msg = match crash::write_report(&details) {
+            Ok(path) => format!(
+                "crashed, and wrote a report to {} (please include it when reporting the bug)",
+                path.display()
+            ),
+            Err(err) => format!("crashed, and failed to write a report: {err}"),
+        }; // @alloc
+        return Err(io::Error::new(io::ErrorKind::Other, msg));
+    };
+    result?;
+    state.close()?;
+    if let Some(path) = &args.export {
+        state.export_to(path)?;
     }
+    let (elapsed, prec) = (state.elapsed(), state.precision());
 
     shell.finish()?;
 
-    Ok(())
+    if let Some(format) = args.print_on_exit {
+        let mut stdout = BufWriter::new(stdout()); // @alloc
+        match format {
+            ExitFormat::Clock => writeln!(stdout, "{}", DurationFmt::scaled(elapsed, prec, true))?,
+            ExitFormat::Seconds => writeln!(stdout, "{}", Seconds(elapsed))?,
+        }
+        stdout.flush()?;
+    }
+
+    if shell.errored() && !shell.is_interactive() {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
 }
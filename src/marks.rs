@@ -0,0 +1,45 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::time::Duration;
+
+/// Elapsed time saved under a name, for reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mark {
+    pub name: String,
+    pub at: Duration,
+}
+
+/// Named marks, ordered by the elapsed time they were set at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Marks(Vec<Mark>);
+
+impl Marks {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn as_slice(&self) -> &[Mark] {
+        &self.0
+    }
+
+    /// Sets the mark called `name` to `at`, returning where it was before if
+    /// there already was one.
+    pub fn set(&mut self, name: &str, at: Duration) -> Option<Duration> {
+        let old = self
+            .0
+            .iter()
+            .position(|mark| mark.name == name)
+            .map(|idx| self.0.remove(idx).at);
+        let idx = self.0.partition_point(|mark| mark.at <= at);
+        self.0.insert(
+            idx,
+            Mark {
+                name: name.to_string(), // @alloc
+                at,
+            },
+        ); // @alloc
+        old
+    }
+}
@@ -0,0 +1,99 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use unicode_width::UnicodeWidthStr;
+
+use core::{cmp, mem};
+
+use crate::export::civil_from_days;
+
+/// Library which `sw` depends on directly.
+pub struct Dependency {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// SPDX license expression, or `?` if unknown.
+    pub license: &'static str,
+    pub authors: &'static [&'static str],
+}
+
+// generated by build.rs from Cargo.lock, so that these never drift from what
+// `sw` was built with
+include!(concat!(env!("OUT_DIR"), "/meta.rs"));
+
+/// Describes this build of `sw`: its version, followed by the commit it was
+/// built from, when it was built, the compiler which built it, and the enabled
+/// cargo features.
+pub fn version_lines() -> Vec<String> {
+    let (year, month, day) = civil_from_days(BUILT_AT / 86_400);
+    let features = if FEATURES.is_empty() {
+        String::from("none") // @alloc
+    } else {
+        FEATURES.join(", ")
+    };
+    vec![
+        format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        format!("commit:   {}", GIT_COMMIT.unwrap_or("unknown")),
+        format!("built:    {year:04}-{month:02}-{day:02}"),
+        format!("rustc:    {}", RUSTC_VERSION.unwrap_or("unknown")),
+        format!("features: {features}"),
+    ] // @alloc
+}
+
+/// Renders the dependencies as a table of names, versions, and licenses,
+/// each followed by its authors wrapped to `width` columns.
+pub fn dependency_table(width: usize) -> Vec<String> {
+    const HEADER: [&str; 3] = ["library", "version", "license"];
+    const FIRST: &str = "  by ";
+    const REST: &str = "     ";
+
+    let name_width = DEPENDENCIES
+        .iter()
+        .map(|dep| UnicodeWidthStr::width(dep.name))
+        .fold(UnicodeWidthStr::width(HEADER[0]), cmp::max);
+    let version_width = DEPENDENCIES
+        .iter()
+        .map(|dep| UnicodeWidthStr::width(dep.version))
+        .fold(UnicodeWidthStr::width(HEADER[1]), cmp::max);
+    let row = |name: &str, version: &str, license: &str| {
+        let name_pad = name_width - UnicodeWidthStr::width(name);
+        let version_pad = version_width - UnicodeWidthStr::width(version);
+        format!(
+            "{name}{:name_pad$}  {version}{:version_pad$}  {license}",
+            "", ""
+        )
+    };
+
+    let mut lines = Vec::with_capacity(DEPENDENCIES.len() * 2 + 1); // @alloc
+    lines.push(row(HEADER[0], HEADER[1], HEADER[2]));
+    for dep in DEPENDENCIES {
+        lines.push(row(dep.name, dep.version, dep.license));
+        if dep.authors.is_empty() {
+            continue;
+        }
+
+        // wrap authors, breaking only between them
+        let mut line = String::from(FIRST);
+        let mut empty = true;
+        for (idx, author) in dep.authors.iter().enumerate() {
+            let sep = if idx + 1 == dep.authors.len() {
+                ""
+            } else {
+                ","
+            };
+            let len = UnicodeWidthStr::width(*author) + sep.len();
+            if !empty && UnicodeWidthStr::width(line.as_str()) + 1 + len > width {
+                lines.push(mem::replace(&mut line, String::from(REST)));
+                empty = true;
+            }
+            if !empty {
+                line.push(' ');
+            }
+            line.push_str(author);
+            line.push_str(sep);
+            empty = false;
+        }
+        lines.push(line);
+    }
+    lines
+}
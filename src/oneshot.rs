@@ -0,0 +1,258 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use argh::FromArgs;
+use termcolor::{Color, ColorSpec};
+
+use std::io;
+use std::time::SystemTime;
+
+use crate::clock::Source;
+use crate::config::Config;
+use crate::export::{self, Snapshot};
+use crate::parse::ReadDur;
+use crate::persist::{Loaded, Saved};
+use crate::precision::Precision;
+use crate::shell::Shell;
+use crate::state::{self, DurationFmt, State};
+
+/// Non-interactive commands operating on a persisted stopwatch.
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum Oneshot {
+    Start(Start),
+    Stop(Stop),
+    Show(Show),
+    Reset(Reset),
+    Schedule(Schedule),
+    Daemon(Daemon),
+    Attach(Attach),
+}
+
+/// start the persisted stopwatch
+#[derive(FromArgs)]
+#[argh(subcommand, name = "start")]
+pub struct Start {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    name: String,
+}
+
+/// stop the persisted stopwatch
+#[derive(FromArgs)]
+#[argh(subcommand, name = "stop")]
+pub struct Stop {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    name: String,
+}
+
+/// show the elapsed time of the persisted stopwatch
+#[derive(FromArgs)]
+#[argh(subcommand, name = "show")]
+pub struct Show {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    name: String,
+
+    /// print the state as JSON
+    #[argh(switch)]
+    json: bool,
+}
+
+/// stop and reset the persisted stopwatch
+#[derive(FromArgs)]
+#[argh(subcommand, name = "reset")]
+pub struct Reset {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    name: String,
+}
+
+/// schedule the persisted stopwatch to start after a duration
+#[derive(FromArgs)]
+#[argh(subcommand, name = "schedule")]
+pub struct Schedule {
+    /// how long from now to start, such as "15m"
+    #[argh(positional)]
+    after: String,
+
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    name: String,
+}
+
+/// run the stopwatch without a terminal, for clients to attach to
+#[derive(FromArgs)]
+#[argh(subcommand, name = "daemon")]
+pub struct Daemon {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    pub name: String,
+}
+
+/// attach to the stopwatch run by the daemon
+#[derive(FromArgs)]
+#[argh(subcommand, name = "attach")]
+pub struct Attach {
+    /// stopwatch name
+    #[argh(positional, default = "String::new()")]
+    pub name: String,
+}
+
+impl Oneshot {
+    fn name(&self) -> &str {
+        match self {
+            Self::Start(Start { name })
+            | Self::Stop(Stop { name })
+            | Self::Show(Show { name, .. })
+            | Self::Reset(Reset { name })
+            | Self::Schedule(Schedule { name, .. })
+            | Self::Daemon(Daemon { name })
+            | Self::Attach(Attach { name }) => name,
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub fn run(&self, shell: &mut Shell) -> io::Result<()> {
+        let name = self.name();
+        let path = Saved::path(name)?;
+        let (mut saved, migrated) = match Saved::load(&path, name)? {
+            Some(Loaded { saved, migrated }) => (saved, migrated),
+            None => (Saved::new(name.to_owned()), Vec::new()),
+        };
+        let now = SystemTime::now();
+        // as the shell would show it, leaving warnings about the
+        // configuration to the shell
+        let prec = saved
+            .precision
+            .map_or(Precision::Fixed(State::DEFAULT_PRECISION), |spec| {
+                spec.resolve(Config::load().0.precision_thresholds)
+            });
+        let mut cb = shell.create_cmd_buf();
+
+        if let Self::Show(Show { json: true, .. }) = self {
+            // nothing else is printed, so that the output can be parsed as is
+            let snapshot = Snapshot {
+                name: &saved.name,
+                tags: &[],
+                elapsed: saved.sw.elapsed_at(now),
+                running: saved.sw.is_running(),
+                precision: prec.at(saved.sw.elapsed_at(now)),
+                laps: &[],
+                overtime: None,
+                // the clock the stopwatch keeps time by when it's resumed
+                clock: saved.clock.unwrap_or(Source::Monotonic).resolve().0.name(),
+            };
+            return cb.writeln(format_args!("{}", export::json(&snapshot)));
+        }
+
+        state::report_migrated(&mut cb, &migrated)?;
+        if !matches!(self, Self::Show(_)) {
+            // the time since the last session is accounted for
+            saved.closed = None;
+        }
+
+        match self {
+            Self::Start(_) => {
+                saved.scheduled = None;
+                if saved.sw.is_running() {
+                    cb.info_idle(format_args!("stopwatch already running"))?;
+                } else {
+                    saved.sw.start_at(now);
+                    cb.info_change(format_args!("started stopwatch"))?;
+                }
+            }
+
+            Self::Stop(_) => {
+                if saved.sw.is_stopped() {
+                    cb.info_idle(format_args!("stopwatch already stopped"))?;
+                } else {
+                    let overflow = !saved.sw.checked_stop_at(now);
+                    if overflow {
+                        saved.sw.stop_at(now);
+                    }
+                    cb.info_change(format_args!("stopped stopwatch"))?;
+                    if overflow {
+                        cb.warn(format_args!(
+                            "new elapsed time too large, clamped to maximum"
+                        ))?;
+                    }
+                }
+            }
+
+            Self::Show(_) => {
+                cb.writeln(format_args!(
+                    "{}",
+                    DurationFmt::scaled(saved.sw.elapsed_at(now), prec, cb.visual_cues())
+                ))?;
+                let (state, color) = if saved.sw.is_running() {
+                    ("running", Color::Green)
+                } else {
+                    ("stopped", Color::Yellow)
+                };
+                cb.writeln_color(
+                    ColorSpec::new().set_fg(Some(color)),
+                    format_args!("{state}"),
+                )?;
+                if let Some(missed) = saved.missed_at(now) {
+                    cb.info_idle(format_args!(
+                        "scheduled start passed {} ago",
+                        DurationFmt::new(missed, 0, cb.visual_cues())
+                    ))?;
+                } else if let Some(until) = saved
+                    .scheduled
+                    .and_then(|scheduled| scheduled.duration_since(now).ok())
+                {
+                    cb.info_idle(format_args!(
+                        "scheduled to start in {}",
+                        DurationFmt::new(until, 0, cb.visual_cues())
+                    ))?;
+                }
+            }
+
+            Self::Reset(_) => {
+                let sw_was_running = saved.sw.is_running();
+                saved.sw.reset();
+                saved.scheduled = None;
+                if sw_was_running {
+                    cb.info_change(format_args!("stopped and reset stopwatch"))?;
+                } else {
+                    cb.info_change(format_args!("reset stopwatch"))?;
+                }
+            }
+
+            Self::Daemon(_) | Self::Attach(_) => unreachable!("run by main"),
+
+            Self::Schedule(Schedule { after, .. }) => match ReadDur::parse(after, false) {
+                Some(Ok(ReadDur { dur, is_neg })) => {
+                    assert!(!is_neg);
+                    saved.scheduled = now.checked_add(dur);
+                    cb.info_change(format_args!(
+                        "scheduled stopwatch to start in {}",
+                        DurationFmt::new(dur, 0, cb.visual_cues())
+                    ))?;
+                    if saved.sw.is_running() {
+                        cb.warn(format_args!("stopwatch is already running"))?;
+                    }
+                }
+                Some(Err(err)) => {
+                    err.display(&mut cb)?;
+                    return Ok(());
+                }
+                None => {
+                    cb.error(format_args!("expected a duration"))?;
+                    return Ok(());
+                }
+            },
+        }
+
+        // migrated files are rewritten so that the summary is only shown once
+        if !matches!(self, Self::Show(_)) || !migrated.is_empty() {
+            saved.store(&path)?;
+        }
+
+        Ok(())
+    }
+}
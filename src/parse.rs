@@ -4,24 +4,40 @@
 
 use termcolor::ColorSpec;
 use unicode_segmentation::UnicodeSegmentation;
-use unicode_width::UnicodeWidthStr;
 
 use core::fmt;
 use core::num::{IntErrorKind, ParseIntError};
 use core::time::Duration;
+use std::borrow::Cow;
 use std::io;
 
-use crate::shell::{CmdBuf, ERROR};
+use crate::shell::{CmdBuf, Escaped};
 
+pub(crate) mod iso;
 pub(crate) mod long;
 pub(crate) mod short;
 
+use iso::IsoErrKind;
 use long::LongErrKind;
 use short::ShortErrKind;
 
+// code points of the digit zero in each block of unicode decimal digits
+// (general category Nd). each block is contiguous from zero to nine.
+const DECIMAL_ZEROS: [u32; 68] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450, 0x114D0,
+    0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50, 0x11DA0, 0x11F50, 0x16A60,
+    0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0,
+    0x1E950, 0x1FBF0,
+];
+
 const SEC_PER_MIN: u8 = 60;
 const MIN_PER_HOUR: u8 = 60;
+const HOUR_PER_DAY: u8 = 24;
 const SEC_PER_HOUR: u16 = 3600;
+const SEC_PER_DAY: u32 = 86400;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ReadDur {
@@ -33,6 +49,10 @@ impl ReadDur {
     pub fn parse(s: &str, allow_neg: bool) -> Option<Result<Self, ParseErr<'_>>> {
         if s.is_empty() {
             None
+        } else if iso::looks_iso(s) {
+            Some(Self::parse_as_iso(s, allow_neg))
+        } else if !s.as_bytes().contains(&b':') && short::split_segments(s).len() > 1 {
+            Some(Self::parse_as_compound(s, allow_neg))
         } else {
             let parsed = match Self::parse_as_short(s, allow_neg) {
                 Ok(short_ok) => Ok(short_ok),
@@ -56,6 +76,7 @@ impl ReadDur {
 pub(crate) enum ErrKind<'s> {
     Short(ShortErrKind<'s>),
     Long(LongErrKind),
+    Iso(IsoErrKind<'s>),
     Negative,
 }
 
@@ -64,6 +85,11 @@ impl From<LongErrKind> for ErrKind<'_> {
         Self::Long(long)
     }
 }
+impl<'s> From<IsoErrKind<'s>> for ErrKind<'s> {
+    fn from(iso: IsoErrKind<'s>) -> Self {
+        Self::Iso(iso)
+    }
+}
 impl<'s> From<ShortErrKind<'s>> for ErrKind<'s> {
     fn from(short: ShortErrKind<'s>) -> Self {
         Self::Short(short)
@@ -98,7 +124,7 @@ impl<'s> ParseErr<'s> {
                 }
             }
             // showing int overflow error to user breaks abstraction
-            ErrKind::Negative => (),
+            ErrKind::Iso(_) | ErrKind::Negative => (),
         }
 
         Self {
@@ -108,20 +134,30 @@ impl<'s> ParseErr<'s> {
         }
     }
 
-    // TODO: careful printing user input. may contain escape codes.
+    /// Moves the error from `span.src` into `src`, where `span.src` starts
+    /// `offset` bytes into `src`.
+    pub(crate) fn rebase(mut self, offset: usize, src: &'s str) -> Self {
+        self.span.start += offset;
+        self.span.src = src;
+        self.src = src;
+        self
+    }
+
+    /// Writes the error, along with the input it's in. The input is escaped,
+    /// since it may contain escape sequences.
     pub fn display(&self, cmd: &mut CmdBuf<'_>) -> io::Result<()> {
         fn display_error_red_highlighted(err: &ParseErr, cmd: &mut CmdBuf<'_>) -> io::Result<()> {
             // text before span
-            cmd.write(format_args!("{}", err.span.get_before()))?;
+            cmd.write(format_args!("{}", Escaped(err.span.get_before())))?;
 
             // red span text
             cmd.write_color(
-                ColorSpec::new().set_fg(Some(ERROR)),
-                format_args!("{}", err.span.get()),
+                ColorSpec::new().set_fg(cmd.theme().error),
+                format_args!("{}", Escaped(err.span.get())),
             )?;
 
             // text after span
-            cmd.writeln(format_args!("{}", err.span.get_after()))?;
+            cmd.writeln(format_args!("{}", Escaped(err.span.get_after())))?;
 
             Ok(())
         }
@@ -130,10 +166,9 @@ impl<'s> ParseErr<'s> {
             display_error_red_highlighted(err, cmd)?;
 
             // write caret underline
-            let spaces: usize = UnicodeWidthStr::width(err.span.get_before());
-            let carets: usize = UnicodeWidthStr::width(err.span.get());
+            let (spaces, carets) = err.span.caret_columns();
             cmd.writeln_color(
-                ColorSpec::new().set_fg(Some(ERROR)),
+                ColorSpec::new().set_fg(cmd.theme().error),
                 format_args!("{}{}", " ".repeat(spaces), "^".repeat(carets)),
             )?;
 
@@ -143,8 +178,8 @@ impl<'s> ParseErr<'s> {
         fn display_error_no_visual(err: &ParseErr, cmd: &mut CmdBuf<'_>) -> io::Result<()> {
             // write what the error text is
             cmd.writeln_color(
-                ColorSpec::new().set_fg(Some(ERROR)),
-                format_args!("found error: {}", err.span.get()),
+                ColorSpec::new().set_fg(cmd.theme().error),
+                format_args!("found error: {}", Escaped(err.span.get())),
             )?;
 
             Ok(())
@@ -174,6 +209,7 @@ impl ParseErr<'_> {
         match &self.kind {
             ErrKind::Short(short) => short.has_help_message(),
             ErrKind::Long(long) => long.has_help_message(),
+            ErrKind::Iso(iso) => iso.has_help_message(),
             ErrKind::Negative => true,
         }
     }
@@ -185,12 +221,14 @@ impl fmt::Display for ParseErr<'_> {
             match &self.kind {
                 ErrKind::Short(short) => write!(f, "{short:#}"),
                 ErrKind::Long(long) => write!(f, "{long:#}"),
+                ErrKind::Iso(iso) => write!(f, "{iso:#}"),
                 ErrKind::Negative => write!(f, "only offsets to duration can be negative"),
             }
         } else {
             match &self.kind {
                 ErrKind::Short(short) => write!(f, "{short}"),
                 ErrKind::Long(long) => write!(f, "{long}"),
+                ErrKind::Iso(iso) => write!(f, "{iso}"),
                 ErrKind::Negative => write!(f, "expected positive duration"),
             }
         }
@@ -239,6 +277,35 @@ impl<'s> ByteSpan<'s> {
         &self.src[self.start + self.len..]
     }
 
+    /// Returns the number of terminal columns before the span, and the number
+    /// of columns needed to underline it.
+    ///
+    /// Graphemes are segmented over the whole source text, so that clusters
+    /// such as emoji ZWJ sequences and combining marks are measured as they
+    /// are displayed. A grapheme which only partially overlaps the span is
+    /// underlined in full.
+    pub fn caret_columns(&self) -> (usize, usize) {
+        let end = self.start + self.len;
+        let mut before = 0;
+        let mut carets = 0;
+        for (idx, grapheme) in UnicodeSegmentation::grapheme_indices(self.src, true) {
+            // measured as it's displayed, escaped if need be
+            let width = Escaped(grapheme).width();
+            if idx + grapheme.len() <= self.start {
+                before += width;
+            } else if idx < end {
+                carets += width;
+            } else {
+                break;
+            }
+        }
+        if self.len != 0 {
+            // zero width graphemes still deserve a caret
+            carets = carets.max(1);
+        }
+        (before, carets)
+    }
+
     pub fn trim_whitespace(&mut self) {
         // forward
         {
@@ -270,18 +337,56 @@ pub(crate) enum Unit {
     Second,
     Minute,
     Hour,
+    Day,
 }
 
 impl Unit {
+    /// Parses a unit written as its suffix, such as `m`, or spelled out, such
+    /// as `min` or `minutes`.
     #[inline]
-    pub fn from_grapheme(grapheme: &str) -> Result<Self, &str> {
-        match grapheme {
-            "s" => Ok(Self::Second),
-            "m" => Ok(Self::Minute),
-            "h" => Ok(Self::Hour),
+    pub fn from_suffix(suffix: &str) -> Result<Self, &str> {
+        match suffix {
+            "s" | "sec" | "secs" | "second" | "seconds" => Ok(Self::Second),
+            "m" | "min" | "mins" | "minute" | "minutes" => Ok(Self::Minute),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Ok(Self::Hour),
+            "d" | "day" | "days" => Ok(Self::Day),
             unk => Err(unk),
         }
     }
+
+    /// Suffix which denotes the unit in a duration.
+    pub const fn suffix(self) -> &'static str {
+        match self {
+            Self::Second => "s",
+            Self::Minute => "m",
+            Self::Hour => "h",
+            Self::Day => "d",
+        }
+    }
+
+    /// Unit which an unrecognised one, such as `sec` or `hrs`, was most likely
+    /// meant to be, if any is similar enough.
+    pub fn similar(unk: &str) -> Option<Self> {
+        const UNIT_SUGGEST_SIMILAR_THRESHOLD: f64 = 0.4;
+        const NAMES: [(Unit, &str); 8] = [
+            (Unit::Second, "second"),
+            (Unit::Second, "seconds"),
+            (Unit::Minute, "minute"),
+            (Unit::Minute, "minutes"),
+            (Unit::Hour, "hour"),
+            (Unit::Hour, "hours"),
+            (Unit::Day, "day"),
+            (Unit::Day, "days"),
+        ];
+
+        let unk = unk.to_lowercase(); // @alloc
+        NAMES
+            .iter()
+            .map(|(unit, name)| (strsim::normalized_damerau_levenshtein(&unk, name), *unit))
+            .filter(|(similarity, _)| *similarity >= UNIT_SUGGEST_SIMILAR_THRESHOLD)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, unit)| unit)
+    }
 }
 
 impl fmt::Display for Unit {
@@ -290,6 +395,7 @@ impl fmt::Display for Unit {
             Self::Second => "second",
             Self::Minute => "minute",
             Self::Hour => "hour",
+            Self::Day => "day",
         })
     }
 }
@@ -306,16 +412,79 @@ pub(crate) enum ParseFracErr {
     },
 }
 
+/// Returns the value of `chr` if it's a decimal digit in any script.
+pub(crate) fn decimal_digit(chr: char) -> Option<u8> {
+    let code = u32::from(chr);
+    let zero = match DECIMAL_ZEROS.binary_search(&code) {
+        Ok(idx) => DECIMAL_ZEROS[idx],
+        Err(0) => return None,
+        Err(idx) => DECIMAL_ZEROS[idx - 1],
+    };
+    u8::try_from(code - zero).ok().filter(|digit| *digit < 10)
+}
+
+/// Replaces decimal digits of any script in `s` with their ASCII equivalents,
+/// so that the result can be passed to the standard integer parsers.
+pub(crate) fn normalize_digits(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(
+            s.chars()
+                .map(|chr| match decimal_digit(chr) {
+                    Some(digit) => char::from(b'0' + digit),
+                    None => chr,
+                })
+                .collect(), // @alloc
+        )
+    }
+}
+
+/// Parses the digits after a decimal point as a fraction of `unit_secs`
+/// seconds, such as `5` of an hour being 30 minutes, to the nanosecond.
+pub(crate) fn parse_frac_of(s: &str, unit_secs: u32) -> Result<Duration, ParseFracErr> {
+    // enough to be exact to the nanosecond for units of up to a day
+    const PLACES: u8 = 18;
+
+    let mut num: u64 = 0;
+    let mut places = 0;
+    for (idx, chr) in UnicodeSegmentation::grapheme_indices(s, true) {
+        let mut chars = chr.chars();
+        let digit = match (chars.next().and_then(decimal_digit), chars.next()) {
+            (Some(digit), None) => digit,
+            _ => chr.parse::<u8>().map_err(|err| ParseFracErr::ParseDigit {
+                idx,
+                len: chr.len(),
+                err,
+            })?,
+        };
+        if places < PLACES {
+            // excess digits truncated
+            num = num * 10 + u64::from(digit);
+            places += 1;
+        }
+    }
+    let nanos =
+        u128::from(num) * u128::from(unit_secs) * 1_000_000_000 / 10_u128.pow(places.into());
+    Ok(Duration::from_nanos(
+        u64::try_from(nanos).expect("less than the unit, which fits"),
+    ))
+}
+
 pub(crate) fn parse_frac(s: &str, places: u8) -> Result<u32, ParseFracErr> {
     let mut num: u32 = 0;
     let mut place: u32 = places.into();
     let graphs = UnicodeSegmentation::grapheme_indices(s, true);
     for (idx, chr) in graphs {
-        let digit = chr.parse::<u8>().map_err(|err| ParseFracErr::ParseDigit {
-            idx,
-            len: chr.len(),
-            err,
-        })?;
+        let mut chars = chr.chars();
+        let digit = match (chars.next().and_then(decimal_digit), chars.next()) {
+            (Some(digit), None) => digit,
+            _ => chr.parse::<u8>().map_err(|err| ParseFracErr::ParseDigit {
+                idx,
+                len: chr.len(),
+                err,
+            })?,
+        };
         if place == 0 {
             // excess digits truncated
         } else {
@@ -0,0 +1,228 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt;
+use core::time::Duration;
+
+use super::{ByteSpan, ErrKind, ParseErr, ParseFracErr, ReadDur, SEC_PER_DAY, SEC_PER_HOUR};
+use crate::shell::Escaped;
+
+const SEC_PER_WEEK: u32 = 7 * SEC_PER_DAY;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum IsoErrKind<'s> {
+    Empty,
+    TimeEmpty,
+    ValueMissing,
+    DesignatorMissing,
+    DesignatorUnknown(&'s str),
+    DesignatorOrder,
+    VariableLength(&'s str),
+    FracNotLast,
+    Overflow,
+}
+
+impl IsoErrKind<'_> {
+    pub(crate) fn has_help_message(&self) -> bool {
+        match self {
+            Self::Empty
+            | Self::TimeEmpty
+            | Self::DesignatorMissing
+            | Self::DesignatorUnknown(_)
+            | Self::DesignatorOrder
+            | Self::VariableLength(_)
+            | Self::FracNotLast
+            | Self::Overflow => true,
+
+            Self::ValueMissing => false,
+        }
+    }
+}
+
+impl fmt::Display for IsoErrKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            match self {
+                Self::Empty | Self::TimeEmpty => {
+                    write!(f, "a duration such as 'PT1H30M' has at least one value")
+                }
+                Self::DesignatorMissing | Self::DesignatorUnknown(_) => write!(
+                    f,
+                    "use 'W' for weeks and 'D' for days, then 'T' followed by 'H' for hours, 'M' for minutes, and 'S' for seconds"
+                ),
+                Self::DesignatorOrder => {
+                    write!(f, "values go from the largest unit to the smallest")
+                }
+                Self::VariableLength(_) => {
+                    write!(f, "years and months vary in length, so use days instead")
+                }
+                Self::FracNotLast => write!(f, "only the last value can have a fractional part"),
+                Self::Overflow => write!(f, "this duration is too large to be represented"),
+                Self::ValueMissing => unreachable!(),
+            }
+        } else {
+            match self {
+                Self::Empty => write!(f, "missing values"),
+                Self::TimeEmpty => write!(f, "missing time values after 'T'"),
+                Self::ValueMissing => write!(f, "expected a number"),
+                Self::DesignatorMissing => write!(f, "missing unit designator"),
+                Self::DesignatorUnknown(unk) => {
+                    write!(f, "unrecognised unit designator '{}'", Escaped(unk))
+                }
+                Self::DesignatorOrder => write!(f, "unit designator out of order"),
+                Self::VariableLength(unit) => write!(f, "'{unit}' has no fixed length"),
+                Self::FracNotLast => write!(f, "unexpected decimal point"),
+                Self::Overflow => write!(f, "duration overflow"),
+            }
+        }
+    }
+}
+
+/// Whether `s` should be parsed as an ISO 8601 duration, as it starts with
+/// the "P" designator.
+pub(crate) fn looks_iso(s: &str) -> bool {
+    let s = s.trim_start();
+    let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+    s.starts_with(['P', 'p'])
+}
+
+impl ReadDur {
+    #[allow(clippy::too_many_lines)]
+    pub fn parse_as_iso<'s>(s: &'s str, allow_neg: bool) -> Result<Self, ParseErr<'s>> {
+        // whitespace? + sign? + "P" + (number + designator)* + ("T" + (number + designator)*)? + whitespace?
+        let mut span = ByteSpan::new_all(s);
+        span.trim_whitespace();
+        let src = span.get();
+        let offset = span.start;
+        let err = |start: usize, len: usize, kind: IsoErrKind<'s>| {
+            ParseErr::new(ByteSpan::new(offset + start, len, s), kind)
+        };
+
+        // parse sign
+        let mut rest = src;
+        let mut is_neg = false;
+        if let Some(after) = rest.strip_prefix('-') {
+            if !allow_neg {
+                return Err(ParseErr::new(
+                    ByteSpan::new(offset, 1, s),
+                    ErrKind::Negative,
+                ));
+            }
+            is_neg = true;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('+') {
+            rest = after;
+        }
+        let p_idx = src.len() - rest.len();
+        rest = &rest[1..]; // skip "P", as checked by looks_iso
+
+        let mut dur = Duration::ZERO;
+        let mut in_time = false;
+        let mut time_idx = None;
+        let mut any = false;
+        let mut last_rank = 0;
+        let mut frac_idx = None;
+        let mut idx = src.len() - rest.len();
+        while idx < src.len() {
+            let rest = &src[idx..];
+            let chr = rest.chars().next().unwrap();
+            if chr == 'T' || chr == 't' {
+                if in_time {
+                    return Err(err(idx, 1, IsoErrKind::DesignatorOrder));
+                }
+                in_time = true;
+                time_idx = Some(idx);
+                idx += 1;
+                continue;
+            }
+
+            // number, with optional fractional part
+            let num_len = rest
+                .char_indices()
+                .find(|(_, chr)| super::decimal_digit(*chr).is_none() && *chr != '.' && *chr != ',')
+                .map_or(rest.len(), |(idx, _)| idx);
+            let num = &rest[..num_len];
+            if num.is_empty() {
+                return Err(err(idx, chr.len_utf8(), IsoErrKind::ValueMissing));
+            }
+            let (int, frac) = match num.find(['.', ',']) {
+                Some(dot) => (&num[..dot], Some((idx + dot + 1, &num[dot + 1..]))),
+                None => (num, None),
+            };
+
+            // designator
+            let Some(designator) = rest[num_len..].chars().next() else {
+                return Err(err(idx, num_len, IsoErrKind::DesignatorMissing));
+            };
+            let des_idx = idx + num_len;
+            let des_len = designator.len_utf8();
+            let des_str = &src[des_idx..des_idx + des_len];
+            let (rank, secs) = match (in_time, designator.to_ascii_uppercase()) {
+                (false, 'W') => (1, SEC_PER_WEEK),
+                (false, 'D') => (2, SEC_PER_DAY),
+                (true, 'H') => (3, u32::from(SEC_PER_HOUR)),
+                (true, 'M') => (4, 60),
+                (true, 'S') => (5, 1),
+                (false, 'Y' | 'M') => {
+                    return Err(err(des_idx, des_len, IsoErrKind::VariableLength(des_str)))
+                }
+                _ => {
+                    return Err(err(
+                        des_idx,
+                        des_len,
+                        IsoErrKind::DesignatorUnknown(des_str),
+                    ))
+                }
+            };
+            if rank <= last_rank {
+                return Err(err(des_idx, des_len, IsoErrKind::DesignatorOrder));
+            }
+            if let Some(frac_idx) = frac_idx {
+                return Err(err(frac_idx, 1, IsoErrKind::FracNotLast));
+            }
+            last_rank = rank;
+            any = true;
+
+            // accumulate value
+            let overflow = || err(idx, num_len + des_len, IsoErrKind::Overflow);
+            let ints = if int.is_empty() {
+                0
+            } else {
+                super::normalize_digits(int)
+                    .parse::<u64>()
+                    .map_err(|_| overflow())?
+            };
+            let mut value = Duration::from_secs(ints)
+                .checked_mul(secs)
+                .ok_or_else(overflow)?;
+            if let Some((sub_idx, sub)) = frac {
+                frac_idx = Some(sub_idx - 1);
+                let nanos = super::parse_frac(sub, 9).map_err(|frac_err| match frac_err {
+                    ParseFracErr::ParseDigit { idx, len, err: _ } => {
+                        err(sub_idx + idx, len, IsoErrKind::ValueMissing)
+                    }
+                    ParseFracErr::NumeratorOverflow { .. } => overflow(),
+                })?;
+                value = Duration::from_nanos(nanos.into())
+                    .checked_mul(secs)
+                    .and_then(|sub| value.checked_add(sub))
+                    .ok_or_else(overflow)?;
+            }
+            dur = dur.checked_add(value).ok_or_else(overflow)?;
+
+            idx = des_idx + des_len;
+        }
+
+        if let Some(time_idx) = time_idx {
+            if last_rank < 3 {
+                return Err(err(time_idx, 1, IsoErrKind::TimeEmpty));
+            }
+        }
+        if !any {
+            return Err(err(p_idx, 1, IsoErrKind::Empty));
+        }
+
+        Ok(ReadDur { dur, is_neg })
+    }
+}
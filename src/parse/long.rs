@@ -10,7 +10,8 @@ use core::time::Duration;
 use core::{fmt, ops};
 
 use super::{
-    ByteSpan, ErrKind, ParseErr, ParseFracErr, ReadDur, MIN_PER_HOUR, SEC_PER_HOUR, SEC_PER_MIN,
+    ByteSpan, ErrKind, ParseErr, ParseFracErr, ReadDur, HOUR_PER_DAY, MIN_PER_HOUR, SEC_PER_DAY,
+    SEC_PER_HOUR, SEC_PER_MIN,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -40,23 +41,11 @@ impl fmt::Display for LongErrKind {
         if f.alternate() {
             match self {
                 Self::UnexpectedColon => {
-                    write!(f, "there is no colon before {}", Group::Hours)
+                    write!(f, "there is no colon before {}", Group::Days)
                 }
                 Self::UnexpectedDot(group) => {
                     assert_ne!(*group, Group::SecondsSub);
-                    if *group == Group::SecondsInt {
-                        write!(
-                            f,
-                            "decimal point was already given for {}",
-                            Group::SecondsSub
-                        )
-                    } else {
-                        write!(
-                            f,
-                            "found in {group}, but only {} can have fractional values",
-                            Group::SecondsInt
-                        )
-                    }
+                    write!(f, "decimal point was already given for {group}")
                 }
                 Self::DurationOverflow(_) => {
                     write!(f, "this duration is too large to be represented")
@@ -84,9 +73,12 @@ impl fmt::Display for LongErrKind {
 }
 
 impl ReadDur {
+    #[allow(clippy::too_many_lines)]
     pub fn parse_as_long(s: &str, allow_neg: bool) -> Result<Self, ParseErr<'_>> {
         /* split string into groups of hours, minutes, etc */
         let mut neg_span = None;
+        // fractional parts of the days, hours, and minutes, if given
+        let mut fracs: [Option<ByteSpan>; 3] = [None; 3];
         let (groups, is_neg): (Groups, bool) = {
             // NOTE: the lexer scans IN REVERSE
             let mut lexer = LongLexer::new(s).peekable();
@@ -128,11 +120,21 @@ impl ReadDur {
 
                     (Group::Minutes, LongTokenKind::Colon) => cur = Group::Hours,
 
-                    (Group::Hours, LongTokenKind::Colon) => {
+                    (Group::Hours, LongTokenKind::Colon) => cur = Group::Days,
+
+                    (Group::Days, LongTokenKind::Colon) => {
                         return Err(ParseErr::new(token.span, LongErrKind::UnexpectedColon));
                     }
 
                     (_, LongTokenKind::Data) => groups[cur] = token.span,
+                    (Group::Days | Group::Hours | Group::Minutes, LongTokenKind::Dot)
+                        if fracs[cur as usize].is_none() =>
+                    {
+                        // what was read so far is the fraction, so the whole
+                        // part comes next
+                        fracs[cur as usize] = Some(groups[cur]);
+                        groups[cur] = ByteSpan::new(token.span.start, 0, s);
+                    }
                     (_, LongTokenKind::Dot) => {
                         return Err(ParseErr::new(token.span, LongErrKind::UnexpectedDot(cur)));
                     }
@@ -170,8 +172,38 @@ impl ReadDur {
         /* parse group substrings into an actual duration */
         let mut dur = Duration::ZERO;
 
-        // hours, minutes, seconds (whole)
+        // fractions of days, hours, and minutes
+        for (group, frac) in [Group::Days, Group::Hours, Group::Minutes]
+            .into_iter()
+            .zip(fracs)
+        {
+            let Some(span) = frac else {
+                continue;
+            };
+            let mut span = span;
+            span.trim_whitespace();
+            let part =
+                super::parse_frac_of(span.get(), group.secs()).map_err(
+                    |frac_err| match frac_err {
+                        ParseFracErr::ParseDigit { idx, len, err } => {
+                            let mut span = span;
+                            span.shift_start_right(idx);
+                            span.len = len;
+                            ParseErr::new(span, LongErrKind::Int { group, err })
+                        }
+                        ParseFracErr::NumeratorOverflow { .. } => {
+                            ParseErr::new(span, LongErrKind::DurationOverflow(group))
+                        }
+                    },
+                )?;
+            dur = dur
+                .checked_add(part)
+                .ok_or_else(|| ParseErr::new(span, LongErrKind::DurationOverflow(group)))?;
+        }
+
+        // days, hours, minutes, seconds (whole)
         for (group, sec_per_unit) in [
+            (Group::Days, u64::from(SEC_PER_DAY)),
             (Group::Hours, u64::from(SEC_PER_HOUR)),
             (Group::Minutes, u64::from(SEC_PER_MIN)),
             (Group::SecondsInt, 1),
@@ -181,7 +213,7 @@ impl ReadDur {
             /* NOTE: we're trimming after we get the span, meaning the to_parse
              * doesn't reflect the span. */
             if !to_parse.is_empty() {
-                match to_parse.parse::<u64>() {
+                match super::normalize_digits(to_parse).parse::<u64>() {
                     Ok(units) => {
                         let secs = units.checked_mul(sec_per_unit).ok_or_else(|| {
                             ParseErr::new(span, LongErrKind::DurationOverflow(group))
@@ -321,11 +353,11 @@ pub(crate) struct LongToken<'s> {
 }
 
 #[derive(Debug)]
-struct Groups<'s>([ByteSpan<'s>; 4]);
+struct Groups<'s>([ByteSpan<'s>; 5]);
 
 impl<'s> Groups<'s> {
     pub(crate) fn new(s: &'s str) -> Self {
-        Self([ByteSpan::new(0, 0, s); 4])
+        Self([ByteSpan::new(0, 0, s); 5])
     }
 }
 
@@ -345,6 +377,7 @@ impl ops::IndexMut<Group> for Groups<'_> {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum Group {
+    Days,
     Hours,
     Minutes,
     SecondsInt,
@@ -352,9 +385,20 @@ pub(crate) enum Group {
 }
 
 impl Group {
+    /// Seconds in one of the days, hours, or minutes.
+    const fn secs(self) -> u32 {
+        match self {
+            Self::Days => SEC_PER_DAY,
+            Self::Hours => SEC_PER_HOUR as _,
+            Self::Minutes => SEC_PER_MIN as _,
+            Self::SecondsInt | Self::SecondsSub => 1,
+        }
+    }
+
     pub(crate) const fn max(self) -> u64 {
         match self {
-            Self::Hours => u64::MAX / SEC_PER_HOUR as u64 + 1,
+            Self::Days => u64::MAX / SEC_PER_DAY as u64 + 1,
+            Self::Hours => HOUR_PER_DAY as _,
             Self::Minutes => MIN_PER_HOUR as _,
             Self::SecondsInt => SEC_PER_MIN as _,
             #[allow(clippy::cast_possible_truncation)]
@@ -366,6 +410,7 @@ impl Group {
 impl fmt::Display for Group {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match self {
+            Group::Days => "days",
             Group::Hours => "hours",
             Group::Minutes => "minutes",
             Group::SecondsInt => "seconds",
@@ -8,7 +8,13 @@ use core::fmt;
 use core::num::ParseIntError;
 use core::time::Duration;
 
-use super::{ByteSpan, ErrKind, ParseErr, ParseFracErr, ReadDur, Unit, SEC_PER_HOUR, SEC_PER_MIN};
+use super::{
+    ByteSpan, ErrKind, ParseErr, ParseFracErr, ReadDur, Unit, SEC_PER_DAY, SEC_PER_HOUR,
+    SEC_PER_MIN,
+};
+use crate::shell::Escaped;
+
+const UNITS_HELP: &str = "use 's' for seconds, 'm' for minutes, 'h' for hours, and 'd' for days";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum ShortErrKind<'s> {
@@ -17,6 +23,7 @@ pub(crate) enum ShortErrKind<'s> {
     DurMissing(Unit),
     ParseInt { err: ParseIntError, unit: Unit },
     DurOverflow(Unit),
+    SignNotFirst,
 }
 
 impl ShortErrKind<'_> {
@@ -26,7 +33,8 @@ impl ShortErrKind<'_> {
             | Self::DurMissing(_)
             | Self::ParseInt { .. }
             | Self::UnitUnknown(_)
-            | Self::DurOverflow(_) => true,
+            | Self::DurOverflow(_)
+            | Self::SignNotFirst => true,
         }
     }
 }
@@ -35,21 +43,30 @@ impl fmt::Display for ShortErrKind<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if f.alternate() {
             match self {
-                Self::UnitMissing | Self::UnitUnknown(_) => {
-                    write!(f, "use 's' for seconds, 'm' for minutes, and 'h' for hours")
-                }
+                Self::UnitUnknown(unk) => match Unit::similar(unk) {
+                    Some(unit) => write!(
+                        f,
+                        "for {unit}s, use '{}' rather than '{}'",
+                        unit.suffix(),
+                        Escaped(unk)
+                    ),
+                    None => f.write_str(UNITS_HELP),
+                },
+                Self::UnitMissing => f.write_str(UNITS_HELP),
                 Self::DurMissing(unit) | Self::ParseInt { err: _, unit } => {
                     write!(f, "expected the number of {unit}s")
                 }
                 Self::DurOverflow(_) => write!(f, "this duration is too large to be represented"),
+                Self::SignNotFirst => write!(f, "a sign applies to the whole duration"),
             }
         } else {
             match self {
                 Self::UnitMissing => write!(f, "missing unit"),
-                Self::UnitUnknown(unk) => write!(f, "unrecognised unit '{unk}'"),
+                Self::UnitUnknown(unk) => write!(f, "unrecognised unit '{}'", Escaped(unk)),
                 Self::DurMissing(_) => write!(f, "unit given, but missing value"),
                 Self::ParseInt { err, unit: _ } => write!(f, "{err}"),
                 Self::DurOverflow(unit) => write!(f, "duration overflow while parsing {unit}s"),
+                Self::SignNotFirst => write!(f, "sign must come before the first value"),
             }
         }
     }
@@ -60,15 +77,12 @@ impl ReadDur {
         // whitespace? + number + whitespace? + unit + whitespace?
         let s = s.trim_end();
 
-        let (try_unit_idx, try_unit) = UnicodeSegmentation::grapheme_indices(s, true)
-            .peekable()
-            .last()
-            .ok_or(ParseErr::new(
-                ByteSpan::new_all(s),
-                ShortErrKind::UnitMissing,
-            ))?;
+        let (try_unit_idx, try_unit) = split_unit(s).ok_or(ParseErr::new(
+            ByteSpan::new_all(s),
+            ShortErrKind::UnitMissing,
+        ))?;
 
-        let unit = Unit::from_grapheme(try_unit).map_err(|_| {
+        let unit = Unit::from_suffix(try_unit).map_err(|_| {
             ParseErr::new(
                 ByteSpan::new(try_unit_idx, try_unit.len(), s),
                 ShortErrKind::UnitUnknown(try_unit),
@@ -119,7 +133,7 @@ impl ReadDur {
                 let tmp_sub_start = dot_span.start + dot_span.len;
                 sub_span = Some(ByteSpan::new(
                     tmp_sub_start,
-                    dur_span.len - tmp_sub_start,
+                    dur_span.start + dur_span.len - tmp_sub_start,
                     s,
                 ));
             }
@@ -128,43 +142,129 @@ impl ReadDur {
             int_span.trim_whitespace();
             let mut ints = 0;
             if !int_span.get().is_empty() {
-                ints = int_span
-                    .get()
+                ints = super::normalize_digits(int_span.get())
                     .parse::<u64>()
                     .map_err(|err| ParseErr::new(int_span, ShortErrKind::ParseInt { err, unit }))?;
             }
 
-            // parse subs
-            let mut subs: u32 = 0;
+            // scale value based on unit
+            let unit_secs = match unit {
+                Unit::Second => 1,
+                Unit::Minute => u32::from(SEC_PER_MIN),
+                Unit::Hour => u32::from(SEC_PER_HOUR),
+                Unit::Day => SEC_PER_DAY,
+            };
+            let mut dur = Duration::from_secs(ints)
+                .checked_mul(unit_secs)
+                .ok_or(ParseErr::new(num_span, ShortErrKind::DurOverflow(unit)))?;
+
+            // parse subs, as a fraction of the unit
             if let Some(mut sub_span) = sub_span {
                 sub_span.trim_whitespace();
+                let subs =
+                    super::parse_frac_of(sub_span.get(), unit_secs).map_err(|err| match err {
+                        ParseFracErr::ParseDigit { idx, len, err } => {
+                            let mut span = sub_span;
+                            span.shift_start_right(idx);
+                            span.len = len;
+                            ParseErr::new(span, ShortErrKind::ParseInt { err, unit })
+                        }
+                        ParseFracErr::NumeratorOverflow { idx: _ } => {
+                            ParseErr::new(sub_span, ShortErrKind::DurOverflow(unit))
+                        }
+                    })?;
+                dur = dur
+                    .checked_add(subs)
+                    .ok_or(ParseErr::new(num_span, ShortErrKind::DurOverflow(unit)))?;
+            }
 
-                // TODO: can't specify full precision hours or minutes
-                let places = 9; // u32::MAX digits
-                subs = super::parse_frac(sub_span.get(), places).map_err(|err| match err {
-                    ParseFracErr::ParseDigit { idx, len, err } => {
-                        let mut span = sub_span;
-                        span.shift_start_right(idx);
-                        span.len = len;
-                        ParseErr::new(span, ShortErrKind::ParseInt { err, unit })
-                    }
-                    ParseFracErr::NumeratorOverflow { idx: _ } => {
-                        ParseErr::new(sub_span, ShortErrKind::DurOverflow(unit))
-                    }
-                })?;
+            Ok(ReadDur { dur, is_neg })
+        }
+    }
+
+    pub fn parse_as_compound(s: &str, allow_neg: bool) -> Result<Self, ParseErr<'_>> {
+        // short + (whitespace? + short)*
+        let mut total = Self {
+            dur: Duration::ZERO,
+            is_neg: false,
+        };
+        for (idx, mut span) in split_segments(s).into_iter().enumerate() {
+            span.trim_whitespace();
+            let segment = span.get();
+            if idx != 0 {
+                if let Some(sign) = segment
+                    .chars()
+                    .next()
+                    .filter(|chr| *chr == '+' || *chr == '-')
+                {
+                    return Err(ParseErr::new(
+                        ByteSpan::new(span.start, sign.len_utf8(), s),
+                        ShortErrKind::SignNotFirst,
+                    ));
+                }
+            }
+            let read = Self::parse_as_short(segment, allow_neg)
+                .map_err(|err| err.rebase(span.start, s))?;
+            if idx == 0 {
+                total.is_neg = read.is_neg;
             }
+            let unit = split_unit(segment)
+                .and_then(|(_, unit)| Unit::from_suffix(unit).ok())
+                .unwrap_or(Unit::Second);
+            total.dur = total
+                .dur
+                .checked_add(read.dur)
+                .ok_or_else(|| ParseErr::new(span, ShortErrKind::DurOverflow(unit)))?;
+        }
+        Ok(total)
+    }
+}
 
-            // scale value based on unit
-            let mut dur = Duration::new(ints, subs);
-            dur = dur
-                .checked_mul(match unit {
-                    Unit::Second => 1,
-                    Unit::Minute => u32::from(SEC_PER_MIN),
-                    Unit::Hour => u32::from(SEC_PER_HOUR),
-                })
-                .ok_or(ParseErr::new(num_span, ShortErrKind::DurOverflow(unit)))?;
+/// Whether `grapheme` is part of a value, rather than of its unit.
+fn is_value(grapheme: &str) -> bool {
+    grapheme.chars().all(|chr| {
+        chr.is_whitespace()
+            || chr == '.'
+            || chr == '+'
+            || chr == '-'
+            || super::decimal_digit(chr).is_some()
+    })
+}
 
-            Ok(ReadDur { dur, is_neg })
+/// Returns where the unit at the end of `s` starts, along with the unit,
+/// which may be a word such as `"min"`. Without a unit, the last grapheme is
+/// taken to be one, so that it's reported as unrecognised.
+fn split_unit(s: &str) -> Option<(usize, &str)> {
+    let mut start = None;
+    for (idx, grapheme) in UnicodeSegmentation::grapheme_indices(s, true).rev() {
+        if is_value(grapheme) {
+            start = start.or(Some(idx));
+            break;
+        }
+        start = Some(idx);
+    }
+    start.map(|start| (start, &s[start..]))
+}
+
+/// Splits `s` into segments which each end with a unit, such as `"1h"` and
+/// `" 30m"` in `"1h 30m"`. Trailing text without a unit becomes its own
+/// segment.
+pub(crate) fn split_segments(s: &str) -> Vec<ByteSpan<'_>> {
+    let mut segments = Vec::new(); // @alloc
+    let mut start = 0;
+    let mut graphemes = UnicodeSegmentation::grapheme_indices(s, true).peekable();
+    while let Some((idx, grapheme)) = graphemes.next() {
+        // a unit may be several graphemes long, such as a misspelled one
+        let unit_ends =
+            !is_value(grapheme) && graphemes.peek().map_or(true, |(_, next)| is_value(next));
+        if unit_ends {
+            let end = idx + grapheme.len();
+            segments.push(ByteSpan::new(start, end - start, s));
+            start = end;
         }
     }
+    if !s[start..].trim().is_empty() {
+        segments.push(ByteSpan::new(start, s.len() - start, s));
+    }
+    segments
 }
@@ -0,0 +1,400 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::{Sw, SystemSw};
+
+use core::fmt::{self, Write as _};
+use core::iter;
+use core::time::Duration;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::clock::Source;
+use crate::precision::Spec;
+
+/* history of the format:
+ * 1: elapsed and start
+ * 2: added name
+ * 3: added scheduled
+ * 4: added disconnected
+ * 5: added closed
+ * 6: added clock
+ * 7: added precision
+ * 8: added started
+ * 9: added group */
+const FORMAT_VERSION: u32 = 9;
+
+// file names are kept well below the usual limit of 255 bytes
+const MAX_KEY_LEN: usize = 200;
+
+/// Stopwatch state which outlives the process, anchored to the system clock
+/// so that it remains meaningful after a restart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Saved {
+    pub sw: SystemSw,
+    pub name: String,
+    /// Group whose time the stopwatch counts towards, or empty if none.
+    pub group: String,
+    /// When the stopwatch is scheduled to start.
+    pub scheduled: Option<SystemTime>,
+    /// When the shell lost its terminal, if it did since the stopwatch was
+    /// last resumed.
+    pub disconnected: Option<SystemTime>,
+    /// When the last session quit while the stopwatch was running, if
+    /// nothing has touched it since.
+    pub closed: Option<SystemTime>,
+    /// Clock the stopwatch keeps time by, if one was chosen for it.
+    pub clock: Option<Source>,
+    /// Precision the stopwatch is displayed with, if one was chosen for it.
+    pub precision: Option<Spec>,
+    /// When the stopwatch was last started, as of the last time it was
+    /// stored while running.
+    pub started: Option<SystemTime>,
+}
+
+/// Saved state read from a file, along with a description of each change
+/// made while migrating it from an older format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loaded {
+    pub saved: Saved,
+    pub migrated: Vec<String>,
+}
+
+impl Saved {
+    pub const fn new(name: String) -> Self {
+        Self {
+            sw: SystemSw::new(),
+            name,
+            group: String::new(),
+            scheduled: None,
+            disconnected: None,
+            closed: None,
+            clock: None,
+            precision: None,
+            started: None,
+        }
+    }
+
+    /// When the stopwatch was last started, if known.
+    pub fn last_started(&self) -> Option<SystemTime> {
+        self.sw.start.or(self.started)
+    }
+
+    /// How long ago the scheduled start passed without the stopwatch being
+    /// started, if it has.
+    pub fn missed_at(&self, now: SystemTime) -> Option<Duration> {
+        if self.sw.is_running() {
+            return None;
+        }
+        now.duration_since(self.scheduled?).ok()
+    }
+
+    /// Path to the state file of the stopwatch called `name`.
+    pub fn path(name: &str) -> io::Result<PathBuf> {
+        Self::path_with_key("name-", name)
+    }
+
+    /// Path to the state file of the stopwatch belonging to the directory
+    /// `dir`.
+    pub fn path_for_dir(dir: &Path) -> io::Result<PathBuf> {
+        Self::path_with_key("cwd-", &dir.to_string_lossy())
+    }
+
+    fn path_with_key(prefix: &str, key: &str) -> io::Result<PathBuf> {
+        let mut file = String::from(prefix); // @alloc
+        encode_key(&mut file, key);
+        if file.len() > MAX_KEY_LEN {
+            // keep the start for readability, and a hash to tell keys apart
+            let mut end = MAX_KEY_LEN - 17;
+            while !file.is_char_boundary(end) {
+                end -= 1;
+            }
+            file.truncate(end);
+            _ = write!(file, "-{:016x}", fnv1a(key.as_bytes()));
+        }
+        file.push_str(".state");
+        Ok(state_dir()?.join(file))
+    }
+
+    /// Returns the saved stopwatch anchored to the monotonic clock.
+    pub fn to_sw(&self, now: Instant, sys_now: SystemTime) -> Sw {
+        let mut sw = Sw::with_elapsed(self.sw.elapsed);
+        if let Some(start) = self.sw.start {
+            let age = sys_now.duration_since(start).unwrap_or_default();
+            if let Some(start) = now.checked_sub(age) {
+                sw.start_at(start);
+            } else {
+                // start can't be represented, so count the time as elapsed
+                sw = sw.saturating_add(age);
+                sw.start_at(now);
+            }
+        }
+        sw
+    }
+
+    /// Saves `sw`, anchoring it to the system clock.
+    pub fn set_sw(&mut self, sw: &Sw, now: Instant, sys_now: SystemTime) {
+        self.sw = SystemSw::with_elapsed(sw.elapsed);
+        if let Some(start) = sw.start {
+            let age = now.saturating_duration_since(start);
+            if let Some(start) = sys_now.checked_sub(age) {
+                self.sw.start_at(start);
+            } else {
+                self.sw = self.sw.saturating_add(age);
+                self.sw.start_at(sys_now);
+            }
+        }
+    }
+
+    /// Reads saved state from `path`, or returns [`None`] if there is none.
+    ///
+    /// Files written by older versions of `sw` are migrated to the current
+    /// format, using `name` if they don't record one. Unknown keys are
+    /// dropped rather than rejected.
+    pub fn load(path: &Path, name: &str) -> io::Result<Option<Loaded>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Self::decode(&contents, name).map(Some).map_err(|msg| {
+            io::Error::new(ErrorKind::InvalidData, format!("{}: {msg}", path.display()))
+        })
+    }
+
+    /// Writes state to `path`, creating parent directories as needed.
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // write then rename so a crash never leaves a half written file
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.encode())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+impl Saved {
+    pub fn encode(&self) -> String {
+        let mut out = String::new(); // @alloc
+        _ = writeln!(out, "version {FORMAT_VERSION}");
+        out.push_str("name ");
+        escape(&mut out, &self.name);
+        out.push('\n');
+        if !self.group.is_empty() {
+            out.push_str("group ");
+            escape(&mut out, &self.group);
+            out.push('\n');
+        }
+        _ = writeln!(out, "elapsed {}", Stamp(self.sw.elapsed));
+        if let Some(start) = self.sw.start {
+            let since_epoch = start.duration_since(UNIX_EPOCH).unwrap_or_default();
+            _ = writeln!(out, "start {}", Stamp(since_epoch));
+        }
+        if let Some(scheduled) = self.scheduled {
+            let since_epoch = scheduled.duration_since(UNIX_EPOCH).unwrap_or_default();
+            _ = writeln!(out, "scheduled {}", Stamp(since_epoch));
+        }
+        if let Some(disconnected) = self.disconnected {
+            let since_epoch = disconnected.duration_since(UNIX_EPOCH).unwrap_or_default();
+            _ = writeln!(out, "disconnected {}", Stamp(since_epoch));
+        }
+        if let Some(closed) = self.closed {
+            let since_epoch = closed.duration_since(UNIX_EPOCH).unwrap_or_default();
+            _ = writeln!(out, "closed {}", Stamp(since_epoch));
+        }
+        if let Some(clock) = self.clock {
+            _ = writeln!(out, "clock {}", clock.name());
+        }
+        if let Some(precision) = self.precision {
+            _ = writeln!(out, "precision {precision}");
+        }
+        if let Some(started) = self.last_started() {
+            let since_epoch = started.duration_since(UNIX_EPOCH).unwrap_or_default();
+            _ = writeln!(out, "started {}", Stamp(since_epoch));
+        }
+        out
+    }
+
+    pub fn decode(s: &str, name: &str) -> Result<Loaded, String> {
+        let mut saved = Self::new(String::new());
+        let mut migrated = Vec::new();
+        let mut version = None;
+        let mut has_name = false;
+        for line in s.lines() {
+            let line = line.trim_start();
+            if line.trim_end().is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line.trim_end(), ""));
+            match key {
+                "version" => {
+                    version = Some(
+                        value
+                            .trim()
+                            .parse::<u32>()
+                            .map_err(|err| format!("invalid version ({err})"))?,
+                    );
+                }
+                "name" => {
+                    saved.name = unescape(value);
+                    has_name = true;
+                }
+                "group" => saved.group = unescape(value),
+                "elapsed" => saved.sw.elapsed = Stamp::parse(value.trim())?,
+                "start" => saved.sw.start = Some(UNIX_EPOCH + Stamp::parse(value.trim())?),
+                "scheduled" => saved.scheduled = Some(UNIX_EPOCH + Stamp::parse(value.trim())?),
+                "disconnected" => {
+                    saved.disconnected = Some(UNIX_EPOCH + Stamp::parse(value.trim())?);
+                }
+                "closed" => saved.closed = Some(UNIX_EPOCH + Stamp::parse(value.trim())?),
+                "clock" => saved.clock = Some(value.trim().parse()?),
+                "precision" => saved.precision = Some(value.trim().parse()?),
+                "started" => saved.started = Some(UNIX_EPOCH + Stamp::parse(value.trim())?),
+                unk => migrated.push(format!("dropped unknown key '{unk}'")),
+            }
+        }
+
+        let version = version.ok_or("missing version")?;
+        if version > FORMAT_VERSION {
+            return Err(format!(
+                "written by a newer version of {} (format {version}, expected at most {FORMAT_VERSION})",
+                env!("CARGO_PKG_NAME")
+            ));
+        }
+        if version < 2 && !has_name {
+            saved.name.push_str(name);
+            migrated.push(format!("stopwatch name set to '{name}'"));
+        }
+        if version < FORMAT_VERSION {
+            migrated.insert(
+                0,
+                format!("upgraded from format {version} to {FORMAT_VERSION}"),
+            );
+        }
+
+        Ok(Loaded { saved, migrated })
+    }
+}
+
+/// Every persisted stopwatch, along with the path of its state file, in no
+/// particular order. Files which can't be read are left out.
+pub fn list() -> io::Result<Vec<(PathBuf, Saved)>> {
+    let dir = match fs::read_dir(state_dir()?) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut list = Vec::new(); // @alloc
+    for entry in dir {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "state") {
+            if let Ok(Some(Loaded { saved, .. })) = Saved::load(&path, "") {
+                list.push((path, saved)); // @alloc
+            }
+        }
+    }
+    Ok(list)
+}
+
+/// Directory holding persisted state, following the XDG base directory
+/// specification.
+pub fn state_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Ok(PathBuf::from(dir).join(env!("CARGO_PKG_NAME")))
+    } else if let Some(home) = env::var_os("HOME").filter(|dir| !dir.is_empty()) {
+        Ok(PathBuf::from(home)
+            .join(".local/state")
+            .join(env!("CARGO_PKG_NAME")))
+    } else {
+        Err(io::Error::new(
+            ErrorKind::NotFound,
+            "can't locate state directory (neither XDG_STATE_HOME nor HOME are set)",
+        ))
+    }
+}
+
+/// Percent-encodes `key` so that it's safe to use as part of a file name.
+fn encode_key(out: &mut String, key: &str) {
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            out.push(char::from(byte));
+        } else {
+            _ = write!(out, "%{byte:02X}");
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, which unlike the standard library's hasher is stable
+/// across versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Writes `s` with newlines and backslashes escaped, so it fits on one line.
+fn escape(out: &mut String, s: &str) {
+    for chr in s.chars() {
+        match chr {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(chr),
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len()); // @alloc
+    let mut chars = s.chars();
+    while let Some(chr) = chars.next() {
+        if chr == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(chr);
+        }
+    }
+    out
+}
+
+/// Duration serialized as `seconds.nanoseconds`.
+struct Stamp(Duration);
+
+impl Stamp {
+    fn parse(s: &str) -> Result<Duration, String> {
+        let (secs, frac) = s.split_once('.').unwrap_or((s, "0"));
+        let secs = secs
+            .parse::<u64>()
+            .map_err(|err| format!("invalid seconds '{secs}' ({err})"))?;
+        // the fraction is in seconds, so "1.5" is 1 s and 500000000 ns
+        if !(1..=9).contains(&frac.len()) || !frac.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(format!("invalid fraction of a second '{frac}'"));
+        }
+        let nanos = frac
+            .bytes()
+            .chain(iter::repeat(b'0'))
+            .take(9)
+            .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'));
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:09}", self.0.as_secs(), self.0.subsec_nanos())
+    }
+}
@@ -0,0 +1,84 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use core::time::Duration;
+use std::time::Instant;
+
+use crate::command::Command;
+
+/// Version of the plumbing commands. It's only bumped when they change in a
+/// way which could break programs using them, unlike the wording of the
+/// commands meant for humans, which may change in any release.
+pub const VERSION: u32 = 1;
+
+/// Change made by a plumbing command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Command which the change is recorded as, such as for undo.
+    pub command: Command,
+    /// Name of the change, as used in exported events.
+    pub event: &'static str,
+    pub message: &'static str,
+}
+
+/// Runs the plumbing command `line`, such as `:get elapsed-ns`, on `sw`.
+/// Returns the response, which is a single line, along with the change made
+/// to the stopwatch, if any. An error is returned for anything which isn't a
+/// plumbing command of this version.
+pub fn run(line: &str, sw: &mut Sw, now: Instant) -> Result<(String, Option<Change>), String> {
+    let mut words = line.strip_prefix(':').unwrap_or(line).split_whitespace();
+    let (verb, key, value) = (words.next(), words.next(), words.next());
+    if words.next().is_some() {
+        return Err(format!("too many arguments to '{line}'")); // @alloc
+    }
+    match (verb, key, value) {
+        (Some("version"), None, None) => Ok((VERSION.to_string(), None)), // @alloc
+        (Some("get"), Some("elapsed-ns"), None) => {
+            Ok((sw.elapsed_at(now).as_nanos().to_string(), None)) // @alloc
+        }
+        (Some("get"), Some("running"), None) => Ok((sw.is_running().to_string(), None)), // @alloc
+        (Some("set"), Some("elapsed-ns"), Some(value)) => {
+            let nanos = value
+                .parse::<u64>()
+                .map_err(|err| format!("invalid elapsed-ns '{value}': {err}"))?; // @alloc
+            sw.set_in_place_at(Duration::from_nanos(nanos), now);
+            let change = Change {
+                command: Command::Change,
+                event: "change",
+                message: "changed elapsed time",
+            };
+            Ok(("ok".to_string(), Some(change))) // @alloc
+        }
+        (Some("set"), Some("running"), Some(value)) => {
+            let running = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("expected 'true' or 'false', found '{value}'")), // @alloc
+            };
+            let change = if running == sw.is_running() {
+                None
+            } else if running {
+                sw.start_at(now);
+                Some(Change {
+                    command: Command::Toggle,
+                    event: "start",
+                    message: "started stopwatch",
+                })
+            } else {
+                sw.stop_at(now);
+                Some(Change {
+                    command: Command::Toggle,
+                    event: "stop",
+                    message: "stopped stopwatch",
+                })
+            };
+            Ok(("ok".to_string(), change)) // @alloc
+        }
+        _ => Err(format!(
+            "unknown plumbing command '{line}' (version {VERSION} has :version, :get, and :set)"
+        )), // @alloc
+    }
+}
@@ -0,0 +1,177 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::fmt;
+use core::num::IntErrorKind;
+use core::str::FromStr;
+use core::time::Duration;
+
+use crate::state::State;
+
+/// Number of subsecond digits with which durations are displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    Fixed(u8),
+    /// Scales with the duration being displayed.
+    Auto(AutoPrecision),
+}
+
+impl Precision {
+    /// Number of subsecond digits with which `dur` is displayed.
+    pub const fn at(self, dur: Duration) -> u8 {
+        match self {
+            Self::Fixed(prec) => prec,
+            Self::Auto(auto) => auto.at(dur),
+        }
+    }
+
+    /// How the precision would be given to `--precision`.
+    pub const fn spec(self) -> Spec {
+        match self {
+            Self::Fixed(prec) => Spec::Fixed(prec),
+            Self::Auto(_) => Spec::Auto,
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fixed(prec) => write!(f, "{prec}"),
+            Self::Auto(_) => write!(f, "auto"),
+        }
+    }
+}
+
+/// Precision as given to `--precision`, before it's clamped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spec {
+    Fixed(u8),
+    Auto,
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fixed(prec) => write!(f, "{prec}"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl Spec {
+    /// Precision as it's used, with automatic precision scaling by `auto`.
+    pub fn resolve(self, auto: AutoPrecision) -> Precision {
+        match self {
+            Self::Fixed(prec) => Precision::Fixed(State::clamp_prec(prec).0),
+            Self::Auto => Precision::Auto(auto),
+        }
+    }
+}
+
+impl FromStr for Spec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(Self::Auto);
+        }
+        match crate::parse::normalize_digits(s).parse::<u8>() {
+            Ok(prec) => Ok(Self::Fixed(prec)),
+            // clamped like any other precision that's too large
+            Err(err) if *err.kind() == IntErrorKind::PosOverflow => Ok(Self::Fixed(u8::MAX)),
+            Err(_) => Err(format!(
+                "expected a number of digits or 'auto', found '{s}'"
+            )), // @alloc
+        }
+    }
+}
+
+/// How the last subsecond digit shown is chosen, when the duration has more
+/// digits than the precision shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Drop the digits which aren't shown, so that `4.99` shows as `4.9`.
+    #[default]
+    Truncate,
+    /// Round to the nearest, with halves rounded up.
+    HalfUp,
+    /// Round to the nearest, with halves rounded to an even digit.
+    HalfEven,
+}
+
+impl Rounding {
+    /// Rounds `dur` to `prec` subsecond digits, clamping it to the largest
+    /// duration if it overflows.
+    pub fn apply(self, dur: Duration, prec: u8) -> Duration {
+        let prec = prec.min(crate::MAX_NANOS_CHARS);
+        let unit = 10_u32.pow(u32::from(crate::MAX_NANOS_CHARS - prec));
+        let rem = dur.subsec_nanos() % unit;
+        let down = Duration::new(dur.as_secs(), dur.subsec_nanos() - rem);
+        let up = match self {
+            Self::Truncate => false,
+            Self::HalfUp => rem >= unit - rem,
+            Self::HalfEven => {
+                let odd = down.as_nanos() / u128::from(unit) % 2 == 1;
+                rem > unit - rem || (rem == unit - rem && odd)
+            }
+        };
+        if up {
+            down.checked_add(Duration::from_nanos(unit.into()))
+                .unwrap_or(Duration::MAX)
+        } else {
+            down
+        }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truncate" => Ok(Self::Truncate),
+            "half_up" => Ok(Self::HalfUp),
+            "half_even" => Ok(Self::HalfEven),
+            _ => Err(format!(
+                "expected 'truncate', 'half_up', or 'half_even', found '{s}'"
+            )), // @alloc
+        }
+    }
+}
+
+/// Thresholds at which automatic precision changes. Durations shorter than
+/// `fine_below` are displayed to the millisecond, those at least
+/// `coarse_from` to the second, and those in between with the default
+/// precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoPrecision {
+    pub fine_below: Duration,
+    pub coarse_from: Duration,
+}
+
+impl AutoPrecision {
+    const FINE: u8 = 3;
+    const COARSE: u8 = 0;
+
+    pub const fn at(self, dur: Duration) -> u8 {
+        // `Duration`'s comparisons aren't const
+        if dur.as_nanos() < self.fine_below.as_nanos() {
+            Self::FINE
+        } else if dur.as_nanos() >= self.coarse_from.as_nanos() {
+            Self::COARSE
+        } else {
+            State::DEFAULT_PRECISION
+        }
+    }
+}
+
+impl Default for AutoPrecision {
+    fn default() -> Self {
+        Self {
+            fine_below: Duration::from_secs(60),
+            coarse_from: Duration::from_secs(60 * 60),
+        }
+    }
+}
@@ -3,17 +3,152 @@
 // licensed under GPL-3.0-or-later
 
 use termcolor::{BufferedStandardStream, Color, ColorChoice, ColorSpec, WriteColor};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use core::fmt;
-use std::io::{self, stdin, BufRead, Read, Stdin, Write};
+use core::time::Duration;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, stdin, BufRead, Read, Write};
+use std::process::{self, Stdio};
 use std::time::Instant;
 
 use crate::command::Command;
+use crate::precision::Precision;
+use crate::state::{DisplayStyle, DurationFmt};
+use crate::theme::{Theme, Thresholds};
 
-pub const INFO_CHANGE: Color = Color::Magenta;
-pub const INFO_IDLE: Color = Color::Cyan;
-pub const WARN: Color = Color::Yellow;
-pub const ERROR: Color = Color::Red;
+/// Outcome of reading a line of input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub enum Received {
+    Line,
+    Eof,
+    /// The answer was abandoned with ctrl-c, leaving the input empty.
+    Cancel,
+}
+
+/// Line being edited a keystroke at a time, as read from a terminal in raw
+/// mode.
+#[derive(Debug, Default)]
+pub struct LineEdit {
+    pending: Vec<u8>, // bytes of a partially typed character
+    escape: bool,     // skipping an escape sequence, such as an arrow key
+}
+
+impl LineEdit {
+    /// Applies the key `byte` to `input`, which is kept within `limit` bytes.
+    /// Returns how the line ended, if it did.
+    pub fn key(&mut self, input: &mut String, byte: u8, limit: usize) -> Option<Received> {
+        if self.escape {
+            self.escape = byte == b'[' || !(0x40..=0x7e).contains(&byte);
+            return None;
+        }
+        match byte {
+            b'\r' | b'\n' => return Some(Received::Line),
+            0x04 if input.is_empty() => return Some(Received::Eof), // ctrl-d
+            // ctrl-c and ctrl-\, read instead of signalled
+            0x03 | 0x1c => {
+                input.clear();
+                return Some(Received::Cancel);
+            }
+            0x08 | 0x7f => _ = input.pop(), // backspace
+            0x15 => input.clear(),          // ctrl-u
+            0x1b => self.escape = true,
+            ..=0x1f => (),
+            _ => {
+                self.pending.push(byte); // @alloc
+                match core::str::from_utf8(&self.pending) {
+                    Ok(chr) => {
+                        if input.len() + chr.len() <= limit {
+                            input.push_str(chr);
+                        }
+                        self.pending.clear();
+                    }
+                    Err(err) if err.error_len().is_some() => self.pending.clear(),
+                    Err(_) => (),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Feedback on input which is still being typed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Preview {
+    None,
+    Valid(String),
+    Invalid(String),
+}
+
+/// Text from the user, such as a name or something they typed, written so
+/// that it can't control the terminal. Control characters, which include
+/// those starting escape sequences, and characters which reorder text are
+/// written escaped, such as `\u{1b}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Escaped<'a>(pub &'a str);
+
+impl<'a> Escaped<'a> {
+    fn is_unsafe(chr: char) -> bool {
+        chr.is_control()
+            || matches!(chr, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+    }
+
+    /// Number of columns the text occupies once escaped.
+    pub fn width(self) -> usize {
+        if self.0.chars().any(Self::is_unsafe) {
+            self.to_string().width() // @alloc
+        } else {
+            self.0.width()
+        }
+    }
+
+    /// Shortens the text to at most `max` columns once escaped, ending it
+    /// with an ellipsis if anything was cut.
+    pub const fn clip(self, max: usize) -> Clipped<'a> {
+        Clipped { text: self, max }
+    }
+}
+
+/// Escaped text which is shortened to fit, as made by [`Escaped::clip`].
+pub struct Clipped<'a> {
+    text: Escaped<'a>,
+    max: usize,
+}
+
+impl fmt::Display for Clipped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.text.width() <= self.max {
+            return write!(f, "{}", self.text);
+        }
+        // whole graphemes are kept, leaving a column for the ellipsis
+        let mut width = 0;
+        for grapheme in self.text.0.graphemes(true) {
+            let grapheme = Escaped(grapheme);
+            width += grapheme.width();
+            if width >= self.max {
+                break;
+            }
+            write!(f, "{grapheme}")?;
+        }
+        write!(f, "\u{2026}")
+    }
+}
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chr in self.0.chars() {
+            if Self::is_unsafe(chr) {
+                write!(f, "{}", chr.escape_unicode())?;
+            } else {
+                write!(f, "{chr}")?;
+            }
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum IoKind {
@@ -21,36 +156,87 @@ enum IoKind {
     In,
 }
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct Shell {
-    stdout: BufferedStandardStream,
-    stdin: Stdin,
+    stdout: Box<dyn WriteColor>,
+    choice: ColorChoice,
+    reader: Box<dyn BufRead>,
+    interactive: bool,    // false if reading commands from a script or pipe
+    live_input: bool,     // whether some prompts are read a keystroke at a time
+    reduced_motion: bool, // whether to avoid redrawing output in place
+    single_key: bool,     // whether commands named by a single key run without enter
+    name_width: usize,    // columns which the stopwatch's name may take up in the prompt
+    style: DisplayStyle,  // of the elapsed time when it's displayed
+    theme: Theme,
+    thresholds: Thresholds, // colors of the elapsed time by how long it has been running
+    errored: bool,
     read_limit: u16,
+    chained: String, // commands after the first on a line, separated by ";"
     last_op: Option<IoKind>,
+    repeated: Option<(String, u32)>, // last warning or error, and how many times it was left out since
     pub last_read_time: Option<Instant>, // instant when the last read completed
+    hung_up: bool,                   // whether input ended because the terminal went away
 
     visual_cues: bool,
 
     splash_text_written: bool,
+    title_saved: bool, // whether the terminal's title was saved before writing one
 
     finished: bool,
 }
 
 impl Shell {
+    /// Columns which the stopwatch's name may take up in the prompt, unless
+    /// configured otherwise.
+    const NAME_WIDTH: usize = 24;
+
     pub fn new(choice: ColorChoice, read_limit: u16, visual_cues: bool) -> Self {
-        let stdout = BufferedStandardStream::stdout(choice); // @alloc
+        let mut shell = Self::new_batch(
+            choice,
+            read_limit,
+            visual_cues,
+            Box::new(stdin().lock()), // @alloc
+        );
+        shell.interactive = true;
+        shell
+    }
+
+    /// Creates a shell which reads commands from `reader` without writing
+    /// prompts.
+    pub fn new_batch(
+        choice: ColorChoice,
+        read_limit: u16,
+        visual_cues: bool,
+        reader: Box<dyn BufRead>,
+    ) -> Self {
+        let stdout = Box::new(BufferedStandardStream::stdout(choice)); // @alloc
         Self {
             stdout,
-            stdin: stdin(),
+            choice,
+            reader,
+            interactive: false,
+            live_input: false,
+            reduced_motion: false,
+            single_key: false,
+            name_width: Self::NAME_WIDTH,
+            style: DisplayStyle::default(),
+            theme: Theme::DEFAULT,
+            thresholds: Thresholds::default(),
+            errored: false,
             read_limit,
+            chained: String::new(),
             last_op: None,
+            repeated: None,
             last_read_time: None,
+            hung_up: false,
             visual_cues,
             splash_text_written: false,
+            title_saved: false,
             finished: false,
         }
     }
 
-    pub fn splash_text(&mut self) -> io::Result<()> {
+    pub fn splash_text(&mut self, upgraded_from: Option<&str>) -> io::Result<()> {
         assert!(
             !self.splash_text_written,
             "splash text can only be written once"
@@ -81,10 +267,52 @@ impl Shell {
                 }
             ),
         )?;
+        if let Some(last) = upgraded_from {
+            self.writeln(
+                ColorSpec::new().set_fg(self.theme.info_idle),
+                format_args!(r#"updated from {last}. enter "w" to see what's new."#),
+            )?;
+        }
 
         Ok(())
     }
 
+    /// Connects the shell to another terminal, such as that of a client
+    /// attached to the daemon, and greets it with the splash text.
+    pub fn attach(
+        &mut self,
+        reader: Box<dyn BufRead>,
+        writer: Box<dyn WriteColor>,
+    ) -> io::Result<()> {
+        self.detach();
+        self.reader = reader;
+        self.stdout = writer;
+        self.interactive = true;
+        // the terminal is on the other end, out of reach of stty
+        self.live_input = false;
+        self.single_key = false;
+        self.splash_text_written = false;
+        self.splash_text(None)
+    }
+
+    /// Disconnects the shell from the attached terminal, if any. Output goes
+    /// to standard output again, and there's no more input.
+    pub fn detach(&mut self) {
+        // the other end may be gone already
+        _ = self.flush(None);
+        self.reader = Box::new(io::empty()); // @alloc
+        self.chained.clear();
+        self.stdout = Box::new(BufferedStandardStream::stdout(self.choice)); // @alloc
+        self.interactive = false;
+        self.last_op = None;
+    }
+
+    /// Writes output to standard error instead, leaving standard output to
+    /// the caller.
+    pub fn write_to_stderr(&mut self) {
+        self.stdout = Box::new(BufferedStandardStream::stderr(self.choice)); // @alloc
+    }
+
     pub fn create_cmd_buf(&mut self) -> CmdBuf<'_> {
         CmdBuf::new(self)
     }
@@ -103,16 +331,62 @@ impl Shell {
         Ok(())
     }
 
-    pub fn read(&mut self, input: &mut String) -> io::Result<()> {
+    pub fn read(&mut self, input: &mut String) -> io::Result<Received> {
         let this_op = IoKind::In;
         self.flush(Some(this_op))?;
         input.clear();
-        self.stdin
-            .lock()
-            .take(self.read_limit.into())
-            .read_line(input)?;
+        let bytes = match Read::take(&mut *self.reader, self.read_limit.into()).read_line(input) {
+            Ok(bytes) => bytes,
+            // a terminal which has gone away fails reads rather than ending them
+            Err(err) if self.interactive && is_hangup(&err) => {
+                self.hung_up = true;
+                0
+            }
+            Err(err) => return Err(err),
+        };
         self.last_read_time = Some(Instant::now());
-        Ok(())
+        if bytes == 0 {
+            Ok(Received::Eof)
+        } else {
+            Ok(Received::Line)
+        }
+    }
+
+    /// Sets whether prompts which offer a preview read input a keystroke at
+    /// a time, so that the preview is shown while typing.
+    pub fn set_live_input(&mut self, live: bool) {
+        self.live_input = live;
+    }
+
+    /// Sets whether a key which is the short name of a command runs it when
+    /// pressed at the command prompt, without enter.
+    pub fn set_single_key(&mut self, single_key: bool) {
+        self.single_key = single_key;
+    }
+
+    /// Sets whether to avoid animation and redrawing output in place, which
+    /// takes precedence over live input.
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.reduced_motion = reduced;
+    }
+
+    /// Sets how many columns the stopwatch's name may take up in the prompt,
+    /// beyond which it's cut short.
+    pub fn set_name_width(&mut self, width: u16) {
+        self.name_width = width.into();
+    }
+
+    /// Sets how the elapsed time is written when it's displayed.
+    pub fn set_display_style(&mut self, style: DisplayStyle) {
+        self.style = style;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: Thresholds) {
+        self.thresholds = thresholds;
     }
 
     pub fn input(input: &str) -> &str {
@@ -122,14 +396,43 @@ impl Shell {
     pub fn finish(&mut self) -> io::Result<()> {
         if !self.finished {
             self.finished = true;
+            self.create_cmd_buf().end_repeats()?;
+            if self.title_saved {
+                // restore the title from before the first one was written
+                self.write(&ColorSpec::new(), format_args!("\x1b[23;2t"))?;
+            }
             self.flush(None)?;
         }
         Ok(())
     }
 
+    /// Sets the title of the terminal window to `title`, without any control
+    /// characters. The title from before is saved the first time, so that
+    /// it's restored when finishing.
+    pub fn write_title(&mut self, title: fmt::Arguments) -> io::Result<()> {
+        if !self.interactive {
+            return Ok(());
+        }
+        let title = title.to_string().replace(char::is_control, ""); // @alloc
+        if !self.title_saved {
+            self.title_saved = true;
+            self.write(&ColorSpec::new(), format_args!("\x1b[22;2t"))?;
+        }
+        self.write(&ColorSpec::new(), format_args!("\x1b]2;{title}\x07"))
+    }
+
     pub const fn read_limit(&self) -> u16 {
         self.read_limit
     }
+
+    pub const fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Whether any error has been reported to the user.
+    pub const fn errored(&self) -> bool {
+        self.errored
+    }
 }
 
 impl Shell {
@@ -167,6 +470,8 @@ impl Shell {
 
 impl Drop for Shell {
     fn drop(&mut self) {
+        // also resets the colors, which an error partway through a write may
+        // have left set
         _ = self.finish();
     }
 }
@@ -181,33 +486,93 @@ impl CmdBuf<'_> {
         self.shell.visual_cues
     }
 
+    pub const fn theme(&self) -> Theme {
+        self.shell.theme
+    }
+
+    pub const fn name_width(&self) -> usize {
+        self.shell.name_width
+    }
+
+    pub const fn display_style(&self) -> DisplayStyle {
+        self.shell.style
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.shell.theme = theme;
+    }
+
+    pub fn write_title(&mut self, title: fmt::Arguments) -> io::Result<()> {
+        self.shell.write_title(title)
+    }
+
     pub fn set_visual_cues(&mut self, new: bool) {
         self.shell.visual_cues = new;
     }
 
+    /// Reads a command, prompting with the stopwatch's name and state, and
+    /// `elapsed` if given. The command is returned along with the name it was
+    /// typed as and its argument, or otherwise the unknown input.
     pub fn read_cmd<'a>(
         &mut self,
         input: &'a mut String,
         name: &str,
         is_running: bool,
-    ) -> io::Result<Result<Command, &'a str>> {
-        if self.shell.visual_cues {
-            self.read(
-                input,
-                format_args!("{name} {} ", if is_running { "*" } else { ";" }),
-            )?;
-        } else {
-            self.read(input, format_args!("{name}. "))?;
+        elapsed: Option<DurationFmt>,
+    ) -> io::Result<Result<(Command, &'a str, &'a str), &'a str>> {
+        let state = if is_running { "*" } else { ";" };
+        let chained = !self.shell.chained.is_empty();
+        let guard = Some(())
+            .filter(|()| self.shell.interactive && self.shell.single_key && !chained)
+            .and_then(|()| TerminalGuard::raw(self.shell.stdout.supports_color()));
+        let mut read = |cb: &mut Self, prompt| {
+            if chained {
+                // the next of the chained commands runs as if it were typed
+                input.replace_range(.., &cb.shell.chained);
+                cb.shell.chained.clear();
+                if cb.shell.interactive {
+                    let next = input.split(';').next().unwrap_or_default();
+                    cb.write(format_args!("{prompt}{}\n", Escaped(next.trim())))?;
+                }
+                Ok(Received::Line)
+            } else if guard.is_some() {
+                cb.read_single_key(input, prompt)
+            } else {
+                cb.read(input, prompt)
+            }
+        };
+        let name = Escaped(name).clip(self.shell.name_width);
+        let received = match (self.shell.visual_cues, elapsed) {
+            (true, Some(elapsed)) => read(self, format_args!("{name} {elapsed} {state} "))?,
+            (true, None) => read(self, format_args!("{name} {state} "))?,
+            (false, Some(elapsed)) => read(self, format_args!("{name} ({elapsed}). "))?,
+            (false, None) => read(self, format_args!("{name}. "))?,
+        };
+        drop(guard);
+
+        if received == Received::Eof {
+            return Ok(Ok((self.eof_command(), "", "")));
         }
 
-        if input.is_empty() {
-            // we received EOF, quit
-            return Ok(Ok(Command::QuitAbrupt));
+        // commands chained with ";", as in "r; s", run one after the other
+        if let Some(at) = input.find(';') {
+            let rest =
+                input[at + 1..].trim_start_matches(|chr: char| chr == ';' || chr.is_whitespace());
+            self.shell.chained.replace_range(.., rest.trim_end());
+            input.truncate(at);
         }
 
+        // commands which take an argument are followed by it, as in "lap 156"
         let try_cmd = Shell::input(input);
+        if let Some((word, arg)) = try_cmd.split_once(char::is_whitespace) {
+            if let Ok(cmd) = word.parse::<Command>() {
+                if cmd.takes_argument() {
+                    return Ok(Ok((cmd, word, arg.trim())));
+                }
+            }
+        }
         match try_cmd.parse() {
-            Ok(cmd) => Ok(Ok(cmd)),
+            Ok(cmd) => Ok(Ok((cmd, try_cmd, ""))),
             Err(()) => Ok(Err(try_cmd)),
         }
     }
@@ -230,29 +595,397 @@ impl CmdBuf<'_> {
         self.writeln_color(&ColorSpec::new(), fmt)
     }
 
+    /// Color of the elapsed time once `elapsed` has passed, by the configured
+    /// thresholds.
+    pub fn elapsed_color(&self, elapsed: Duration) -> ColorSpec {
+        ColorSpec::new()
+            .set_fg(self.shell.thresholds.color_at(elapsed))
+            .clone()
+    }
+
+    /// Writes the elapsed time on its own line, colored by how long it has
+    /// been running.
+    pub fn writeln_elapsed(&mut self, elapsed: Duration, prec: Precision) -> io::Result<()> {
+        let color = self.elapsed_color(elapsed);
+        let visual_cues = self.visual_cues();
+        self.writeln_color(
+            &color,
+            format_args!(
+                "{}",
+                DurationFmt::scaled(elapsed, prec, visual_cues).styled(self.shell.style)
+            ),
+        )
+    }
+
     pub fn info_change(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
-        self.writeln_color(ColorSpec::new().set_fg(Some(INFO_CHANGE)), fmt)
+        // warnings given again after a change aren't repeats
+        self.end_repeats()?;
+        self.writeln_color(ColorSpec::new().set_fg(self.shell.theme.info_change), fmt)
+    }
+
+    /// Writes a heading, such as that of a group of commands in help.
+    pub fn heading(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
+        self.writeln_color(
+            ColorSpec::new()
+                .set_fg(self.shell.theme.prompt)
+                .set_bold(true),
+            fmt,
+        )
     }
 
     pub fn info_idle(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
-        self.writeln_color(ColorSpec::new().set_fg(Some(INFO_IDLE)), fmt)
+        self.writeln_color(ColorSpec::new().set_fg(self.shell.theme.info_idle), fmt)
     }
 
+    /// Writes a warning, unless it's a repeat of the last one.
     pub fn warn(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
-        self.writeln_color(ColorSpec::new().set_fg(Some(WARN)), fmt)
+        let color = self.shell.theme.warn;
+        self.writeln_once(color, fmt.to_string()) // @alloc
     }
 
-    pub fn error(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
+    /// Writes `msg` in `color`, unless it's the same as the last warning or
+    /// error and nothing has changed since. Repeats are only counted, and
+    /// the count is written once they end, as in "elapsed time overflowing
+    /// (repeated 3 more times)".
+    fn writeln_once(&mut self, color: Option<Color>, msg: String) -> io::Result<()> {
+        if let Some((last, count)) = &mut self.shell.repeated {
+            if *last == msg {
+                *count += 1;
+                return Ok(());
+            }
+        }
+        self.end_repeats()?;
+        self.writeln_color(ColorSpec::new().set_fg(color), format_args!("{msg}"))?;
+        self.shell.repeated = Some((msg, 0));
+        Ok(())
+    }
+
+    /// Writes how many times the last warning or error was repeated, if it
+    /// was, and forgets it so that it's written in full next time.
+    fn end_repeats(&mut self) -> io::Result<()> {
+        match self.shell.repeated.take() {
+            Some((msg, count)) if count > 0 => self.writeln_color(
+                ColorSpec::new().set_dimmed(true),
+                format_args!(
+                    "{msg} (repeated {count} more {})",
+                    if count == 1 { "time" } else { "times" }
+                ),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes a highlighted warning, ringing the terminal bell if a human is
+    /// watching.
+    pub fn alert(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
         self.writeln_color(
-            ColorSpec::new().set_fg(Some(ERROR)),
-            format_args!("error: {fmt}"),
-        )
+            ColorSpec::new()
+                .set_fg(self.shell.theme.warn)
+                .set_bold(true)
+                .set_intense(true),
+            fmt,
+        )?;
+        if self.shell.interactive {
+            self.write(format_args!("\x07"))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an error, unless it's a repeat of the last one. Either way, the
+    /// shell has errored.
+    pub fn error(&mut self, fmt: fmt::Arguments) -> io::Result<()> {
+        self.shell.errored = true;
+        let color = self.shell.theme.error;
+        self.writeln_once(color, format!("error: {fmt}")) // @alloc
+    }
+
+    /// Makes everything written so far visible, such as before waiting.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.shell.flush(None)
+    }
+
+    /// Writes `lines`, pausing for the user whenever the terminal fills up.
+    pub fn writeln_paged(&mut self, lines: &[String]) -> io::Result<()> {
+        self.writeln_paged_color(lines, |_| ColorSpec::new())
+    }
+
+    /// Like [`Self::writeln_paged`], but each line is written in the color
+    /// which `color` gives for its index.
+    pub fn writeln_paged_color(
+        &mut self,
+        lines: &[String],
+        color: impl Fn(usize) -> ColorSpec,
+    ) -> io::Result<()> {
+        let page = match terminal_size() {
+            // leave room for the pager prompt and the first line of output
+            Some((_, rows)) if self.shell.interactive && rows > 2 => usize::from(rows - 2),
+            _ => usize::MAX,
+        };
+        let mut answer = String::new(); // @alloc
+        for (idx, line) in lines.iter().enumerate() {
+            if idx != 0 && idx % page == 0 {
+                let received = self.read(
+                    &mut answer,
+                    format_args!("-- more (enter to continue, q to stop) -- "),
+                )?;
+                if received == Received::Eof || Shell::input(&answer).eq_ignore_ascii_case("q") {
+                    break;
+                }
+            }
+            self.writeln_color(&color(idx), format_args!("{line}"))?;
+        }
+        Ok(())
     }
 
-    pub fn read(&mut self, input: &mut String, prompt: fmt::Arguments) -> io::Result<()> {
-        self.write(prompt)?;
+    pub fn read(&mut self, input: &mut String, prompt: fmt::Arguments) -> io::Result<Received> {
+        if self.shell.interactive {
+            self.write(prompt)?;
+        }
         self.shell.read(input)
     }
+
+    /// Reads the answer to a question asked partway through a command, such
+    /// as the new elapsed time. The prompt is indented and colored to set it
+    /// apart from the command prompt, and followed by `hint`, such as the
+    /// value being edited.
+    pub fn read_sub(
+        &mut self,
+        input: &mut String,
+        prompt: fmt::Arguments,
+        hint: Option<fmt::Arguments>,
+    ) -> io::Result<Received> {
+        self.read_sub_default(input, prompt, hint, None, None)
+    }
+
+    /// Like [`Self::read_sub`], but an empty answer accepts `default`, which
+    /// is shown after the hint. If live input is enabled, `preview` is shown
+    /// after the input as it's typed.
+    pub fn read_sub_default(
+        &mut self,
+        input: &mut String,
+        prompt: fmt::Arguments,
+        hint: Option<fmt::Arguments>,
+        default: Option<&str>,
+        preview: Option<&dyn Fn(&str) -> Preview>,
+    ) -> io::Result<Received> {
+        let raw = preview
+            .filter(|_| {
+                self.shell.interactive && self.shell.live_input && !self.shell.reduced_motion
+            })
+            .and_then(|preview| {
+                let guard = TerminalGuard::raw(self.shell.stdout.supports_color())?;
+                Some((preview, guard))
+            });
+        let received = if let Some((preview, guard)) = raw {
+            let received = self.read_live(input, prompt, hint, default, preview)?;
+            // what was written in raw mode goes out before the guard resets
+            // the colors
+            self.shell.flush(None)?;
+            drop(guard);
+            received
+        } else {
+            if self.shell.interactive {
+                self.write_sub_prompt(prompt, hint, default)?;
+            }
+            self.shell.read(input)?
+        };
+        if let Some(default) = default {
+            if received == Received::Line && Shell::input(input).is_empty() {
+                input.replace_range(.., default);
+            }
+        }
+        Ok(received)
+    }
+
+    fn write_sub_prompt(
+        &mut self,
+        prompt: fmt::Arguments,
+        hint: Option<fmt::Arguments>,
+        default: Option<&str>,
+    ) -> io::Result<()> {
+        self.write_color(
+            ColorSpec::new().set_fg(self.shell.theme.prompt),
+            format_args!("  {prompt}"),
+        )?;
+        if let Some(hint) = hint {
+            self.write_color(ColorSpec::new().set_dimmed(true), format_args!("[{hint}] "))?;
+        }
+        if let Some(default) = default {
+            self.write_color(
+                ColorSpec::new().set_dimmed(true),
+                format_args!("[default {}] ", Escaped(default)),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a line a keystroke at a time, redrawing it along with a preview
+    /// of the input after each one. The terminal must be in raw mode.
+    fn read_live(
+        &mut self,
+        input: &mut String,
+        prompt: fmt::Arguments,
+        hint: Option<fmt::Arguments>,
+        default: Option<&str>,
+        preview: &dyn Fn(&str) -> Preview,
+    ) -> io::Result<Received> {
+        let mut redraw = |cb: &mut Self, input: &str| {
+            cb.write(format_args!("\r\x1b[K"))?;
+            cb.write_sub_prompt(prompt, hint, default)?;
+            cb.write(format_args!("{}", Escaped(input)))?;
+            let (color, text) = match preview(input) {
+                Preview::None => (None, String::new()), // @alloc
+                Preview::Valid(text) => (cb.shell.theme.info_idle, text),
+                Preview::Invalid(text) => (cb.shell.theme.error, text),
+            };
+            if !text.is_empty() {
+                // keep the cursor at the end of the input
+                cb.write(format_args!("\x1b7"))?;
+                cb.write_color(
+                    ColorSpec::new().set_fg(color).set_dimmed(true),
+                    format_args!("  {text}"),
+                )?;
+                cb.write(format_args!("\x1b8"))?;
+            }
+            Ok(())
+        };
+        let received = self.read_keys(input, &mut redraw, &|_| false)?;
+
+        // leave the line as it would look without live input
+        self.write(format_args!("\r\x1b[K"))?;
+        self.write_sub_prompt(prompt, hint, default)?;
+        self.write(format_args!("{}\n", Escaped(input)))?;
+        Ok(received)
+    }
+
+    /// Reads a command a keystroke at a time after `prompt`. A key which is
+    /// the short name of a command is taken as soon as it's pressed. The
+    /// terminal must be in raw mode.
+    fn read_single_key(
+        &mut self,
+        input: &mut String,
+        prompt: fmt::Arguments,
+    ) -> io::Result<Received> {
+        let mut redraw = |cb: &mut Self, input: &str| {
+            cb.write(format_args!("\r\x1b[K{prompt}{}", Escaped(input)))
+        };
+        let received = loop {
+            let received = self.read_keys(input, &mut redraw, &|input| {
+                Command::from_key(input).is_some()
+            })?;
+            // a cancelled command is dropped, and the prompt asks again
+            if received != Received::Cancel {
+                break received;
+            }
+        };
+        self.write(format_args!("\r\x1b[K{prompt}{}\n", Escaped(input)))?;
+        self.shell.flush(None)?;
+        Ok(received)
+    }
+
+    /// Reads a line a keystroke at a time, calling `redraw` with the input so
+    /// far before each one. The line also ends once `complete` returns true
+    /// for the input, without waiting for enter. The terminal must be in raw
+    /// mode.
+    fn read_keys(
+        &mut self,
+        input: &mut String,
+        redraw: &mut dyn FnMut(&mut Self, &str) -> io::Result<()>,
+        complete: &dyn Fn(&str) -> bool,
+    ) -> io::Result<Received> {
+        input.clear();
+        let mut edit = LineEdit::default();
+        let received = loop {
+            redraw(self, input)?;
+            self.shell.flush(Some(IoKind::In))?;
+            let buf = match self.shell.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                // as with whole lines, a terminal which has gone away fails reads
+                Err(err) if is_hangup(&err) => {
+                    self.shell.hung_up = true;
+                    &[]
+                }
+                Err(err) => return Err(err),
+            };
+            if buf.is_empty() {
+                break Received::Eof;
+            }
+            let mut done = None;
+            let mut used = 0;
+            for byte in buf.iter().copied() {
+                used += 1;
+                done = edit.key(input, byte, usize::from(self.shell.read_limit));
+                if done.is_none() && complete(input) {
+                    done = Some(Received::Line);
+                }
+                if done.is_some() {
+                    break;
+                }
+            }
+            self.shell.reader.consume(used);
+            if let Some(received) = done {
+                break received;
+            }
+        };
+        self.shell.last_read_time = Some(Instant::now());
+        Ok(received)
+    }
+
+    /// Calls `tick` until it returns true, checking whether a key was pressed
+    /// after each call. Returns false if one was, which discards it. Only a
+    /// human typing at a terminal can interrupt, as otherwise the input is
+    /// the commands to run next.
+    pub fn until_key(
+        &mut self,
+        tick: &mut dyn FnMut(&mut Self) -> io::Result<bool>,
+    ) -> io::Result<bool> {
+        let guard = Some(())
+            .filter(|()| self.shell.interactive)
+            .and_then(|()| TerminalGuard::polled(self.shell.stdout.supports_color()));
+        let finished = loop {
+            if tick(self)? {
+                break true;
+            }
+            if guard.is_none() {
+                continue;
+            }
+            let pressed = match self.shell.reader.fill_buf() {
+                Ok(buf) => buf.len(),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => 0,
+                Err(err) if is_hangup(&err) => {
+                    self.shell.hung_up = true;
+                    break false;
+                }
+                Err(err) => return Err(err),
+            };
+            if pressed != 0 {
+                self.shell.reader.consume(pressed);
+                break false;
+            }
+        };
+        // what was written in polled mode goes out before the guard resets
+        // the colors
+        self.shell.flush(None)?;
+        drop(guard);
+        Ok(finished)
+    }
+
+    /// The command to run once input has ended. Quitting is only abrupt if a
+    /// human is typing.
+    pub const fn eof_command(&self) -> Command {
+        if self.shell.interactive {
+            Command::QuitAbrupt
+        } else {
+            Command::Quit
+        }
+    }
+
+    /// Whether input ended because the terminal went away, rather than
+    /// because it was ended on purpose, such as with ctrl-d.
+    pub const fn hung_up(&self) -> bool {
+        self.shell.hung_up
+    }
 }
 
 impl<'shell> CmdBuf<'shell> {
@@ -276,3 +1009,109 @@ impl<'shell> CmdBuf<'shell> {
         Ok(())
     }
 }
+
+/// Change to the terminal's mode, which is undone when dropped, along with
+/// any colors left set. That happens on every way out of the code using it,
+/// including errors and panics, so the terminal isn't left without echo.
+///
+/// Signals sent from elsewhere, such as SIGTERM or SIGHUP, still end the
+/// process without restoring the terminal. Catching them would take unsafe
+/// code, which the crate forbids, or a dependency just for that. The
+/// alternate screen is never used, so there's no screen to switch back.
+struct TerminalGuard {
+    /// Settings from before the change, as understood by `stty`.
+    saved: String,
+    /// The terminal, written to directly rather than through the shell's
+    /// buffered output, if colors may have been set.
+    tty: Option<File>,
+}
+
+impl TerminalGuard {
+    /// Reads input a keystroke at a time without echoing it. Keys which send
+    /// signals, such as ctrl-c, are read as input too, since a signal would
+    /// end the process without restoring the terminal.
+    fn raw(colors: bool) -> Option<Self> {
+        Self::keys(colors, "1")
+    }
+
+    /// Like [`Self::raw`], except that reading returns at once, with nothing
+    /// if no key was pressed.
+    fn polled(colors: bool) -> Option<Self> {
+        Self::keys(colors, "0")
+    }
+
+    /// Reads input a keystroke at a time, waiting for at least `min` bytes.
+    fn keys(colors: bool, min: &str) -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        let guard = Self {
+            saved: saved.trim().to_owned(),
+            tty: colors
+                .then(|| OpenOptions::new().write(true).open("/dev/tty").ok())
+                .flatten(),
+        };
+        // if this fails partway, the guard restores what did change
+        stty(&["-icanon", "-echo", "-isig", "min", min, "time", "0"])?;
+        Some(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Some(tty) = &mut self.tty {
+            _ = tty.write_all(b"\x1b[0m");
+        }
+        _ = stty(&[&self.saved]);
+    }
+}
+
+/// Whether a failed read means that the terminal on the other end is gone.
+fn is_hangup(err: &io::Error) -> bool {
+    // reading from a terminal which was hung up fails with EIO
+    const EIO: i32 = 5;
+
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    ) || (cfg!(unix) && err.raw_os_error() == Some(EIO))
+}
+
+/// Runs `stty` on the terminal connected to stdin, returning its output if
+/// it succeeded.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = process::Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Returns the size of the terminal as columns and rows, if it can be
+/// determined.
+pub fn terminal_size() -> Option<(u16, u16)> {
+    fn from_env(var: &str) -> Option<u16> {
+        env::var(var)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+            .filter(|len| *len != 0)
+    }
+
+    if let (Some(cols), Some(rows)) = (from_env("COLUMNS"), from_env("LINES")) {
+        return Some((cols, rows));
+    }
+
+    // ask the terminal connected to stdin
+    let size = stty(&["size"])?;
+    let (rows, cols) = size.trim().split_once(' ')?;
+    let rows = from_env("LINES").or_else(|| rows.parse().ok())?;
+    let cols = from_env("COLUMNS").or_else(|| cols.parse().ok())?;
+    Some((cols, rows))
+}
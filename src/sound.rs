@@ -0,0 +1,91 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use std::io;
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+use std::thread;
+
+/// Something that happened to the stopwatch which can have a sound or hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Start,
+    Stop,
+    Lap,
+    Expiry,
+    Alarm,
+    Reset,
+}
+
+impl Event {
+    pub const ALL: [Self; 6] = [
+        Self::Start,
+        Self::Stop,
+        Self::Lap,
+        Self::Expiry,
+        Self::Alarm,
+        Self::Reset,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Lap => "lap",
+            Self::Expiry => "expiry",
+            Self::Alarm => "alarm",
+            Self::Reset => "reset",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+/// Sound files played by an external player as events happen.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sounds {
+    /// Command which plays the file given as its last argument, such as
+    /// `"paplay"`.
+    pub player: Option<String>,
+    files: [Option<PathBuf>; Event::ALL.len()],
+    pub muted: bool,
+}
+
+impl Sounds {
+    pub fn set(&mut self, event: Event, file: PathBuf) {
+        self.files[event as usize] = Some(file);
+    }
+
+    /// Whether any event has a sound.
+    pub fn any(&self) -> bool {
+        self.player.is_some() && self.files.iter().any(Option::is_some)
+    }
+
+    /// Starts playing the sound of `event`, if it has one and sounds aren't
+    /// muted. The sound plays in the background.
+    pub fn play(&self, event: Event) -> io::Result<()> {
+        let (Some(player), Some(file)) = (&self.player, &self.files[event as usize]) else {
+            return Ok(());
+        };
+        if self.muted {
+            return Ok(());
+        }
+        let mut words = player.split_whitespace();
+        let Some(program) = words.next() else {
+            return Ok(());
+        };
+        let mut child = process::Command::new(program)
+            .args(words)
+            .arg(file)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // reap the player once it's done, without holding up the shell
+        thread::spawn(move || child.wait()); // @alloc
+        Ok(())
+    }
+}
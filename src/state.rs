@@ -3,161 +3,2145 @@
 // licensed under GPL-3.0-or-later
 
 use libsw_core::Sw;
-use termcolor::{Color, ColorSpec};
+use termcolor::{Color, ColorSpec, WriteColor};
 use unicode_width::UnicodeWidthStr;
 
+use core::cell::OnceCell;
+use core::fmt::Write as _;
 use core::num::IntErrorKind;
 use core::time::Duration;
 use core::{cmp, fmt, mem};
-use std::io;
-use std::time::Instant;
-
-use crate::command::Command;
-use crate::parse::ReadDur;
-use crate::shell::Shell;
-
-struct Crate {
-    name: &'static str,
-    license: &'static str,
-    owners: &'static [&'static str],
-}
-
-// NOTE: volatile, copypasted data
-const DEPENDENCIES: [Crate; 6] = [
-    Crate {
-        name: "argh",
-        license: "BSD-3-Clause",
-        owners: &[
-            "Taylor Cramer <cramertj@google.com>",
-            "Benjamin Brittain <bwb@google.com>",
-            "Erick Tryzelaar <etryzelaar@google.com>",
-        ],
-    },
-    Crate {
-        name: "libsw-core",
-        license: "MIT OR Apache-2.0",
-        owners: &["Ula Shipman <ula.hello@mailbox.org>"],
-    },
-    Crate {
-        name: "strsim",
-        license: "MIT",
-        owners: &["Danny Guo <danny@dannyguo.com>"],
-    },
-    Crate {
-        name: "termcolor",
-        license: "Unlicense OR MIT",
-        owners: &["Andrew Gallant <jamslam@gmail.com>"],
-    },
-    Crate {
-        name: "unicode-segmentation",
-        license: "MIT/Apache-2.0",
-        owners: &[
-            "kwantam <kwantam@gmail.com>",
-            "Manish Goregaokar <manishsmail@gmail.com>",
-        ],
-    },
-    Crate {
-        name: "unicode-width",
-        license: "MIT/Apache-2.0",
-        owners: &[
-            "kwantam <kwantam@gmail.com>",
-            "Manish Goregaokar <manishsmail@gmail.com>",
-        ],
-    },
-];
-
-impl fmt::Display for Crate {
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use crate::alarm::Alarms;
+use crate::big;
+use crate::changelog;
+use crate::chess::Chess;
+use crate::clock::{Clock, Jump, Source, WallAnchor};
+use crate::command::{Category, Command};
+use crate::config::Config;
+use crate::control::Listener;
+use crate::countdown::{Countdown, Timer};
+use crate::daytime::{self, TimeOfDay};
+use crate::escalate::Escalator;
+use crate::events::{Event as LogEvent, Events};
+use crate::export;
+use crate::hook::Hooks;
+use crate::laps::{self, Announce, Lap, Laps, Prediction, Target};
+use crate::locale::{Locale, Unit};
+use crate::marks::{Mark, Marks};
+use crate::meta;
+use crate::parse::{self, ReadDur};
+use crate::persist::{self, Loaded, Saved};
+use crate::plumbing;
+use crate::precision::{AutoPrecision, Precision, Rounding, Spec};
+use crate::shell::{self, CmdBuf, Escaped, Preview, Received, Shell};
+use crate::sound::{Event, Sounds};
+use crate::status::{Broadcast, Subscriber};
+use crate::theme::Theme;
+use crate::usage::Usage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Passback {
+    Quit,
+    /// The terminal went away while a human was typing, such as when it was
+    /// closed.
+    Disconnected,
+}
+
+/// Stopwatch states preceding each mutating command, most recent last.
+struct History(VecDeque<(Command, Sw, Laps, Option<Chess>)>);
+
+impl History {
+    const LIMIT: usize = 64;
+
+    fn new() -> Self {
+        Self(VecDeque::with_capacity(Self::LIMIT)) // @alloc
+    }
+
+    fn push(&mut self, command: Command, sw: Sw, laps: &Laps, chess: Option<Chess>) {
+        if self.0.len() == Self::LIMIT {
+            self.0.pop_front();
+        }
+        self.0.push_back((command, sw, laps.clone(), chess)); // @alloc
+    }
+
+    fn pop(&mut self) -> Option<(Command, Sw, Laps, Option<Chess>)> {
+        self.0.pop_back()
+    }
+}
+
+/// Recent answers to the prompts of each command, most recent first. They
+/// can be recalled as `!1`, `!2`, and so on, and the most recent is accepted
+/// by pressing enter if enabled.
+struct Answers {
+    defaults: bool,
+    recent: HashMap<Command, VecDeque<String>>,
+}
+
+impl Answers {
+    const LIMIT: usize = 3;
+
+    fn new(defaults: bool) -> Self {
+        Self {
+            defaults,
+            recent: HashMap::new(),
+        }
+    }
+
+    fn default_for(&self, command: Command) -> Option<&str> {
+        self.recent
+            .get(&command)
+            .and_then(VecDeque::front)
+            .filter(|_| self.defaults)
+            .map(String::as_str)
+    }
+
+    /// Recent answers to the prompt of `command`, for use as a hint, such as
+    /// `", !1 -5m, !2 30s"`.
+    fn hint(&self, command: Command) -> RecentFmt<'_> {
+        RecentFmt(self.recent.get(&command))
+    }
+
+    fn remember(&mut self, command: Command, answer: &str) {
+        let recent = self.recent.entry(command).or_default();
+        if let Some(idx) = recent.iter().position(|recent| recent == answer) {
+            recent.remove(idx);
+        } else if recent.len() == Self::LIMIT {
+            recent.pop_back();
+        }
+        recent.push_front(answer.to_owned()); // @alloc
+    }
+
+    /// Replaces a reference such as `!1` in `input` with the answer it
+    /// refers to. Other input is left as is.
+    fn recall(&self, command: Command, input: &mut String) -> Result<(), String> {
+        let Some(number) = Shell::input(input).strip_prefix('!') else {
+            return Ok(());
+        };
+        let answer = parse::normalize_digits(number)
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|idx| self.recent.get(&command)?.get(idx))
+            .ok_or_else(|| format!("no recent answer numbered '{}'", Escaped(number)))?; // @alloc
+        input.replace_range(.., answer);
+        Ok(())
+    }
+}
+
+struct RecentFmt<'a>(Option<&'a VecDeque<String>>);
+
+impl fmt::Display for RecentFmt<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Self {
-            name,
-            owners,
-            license,
-        } = self;
-        write!(f, "'{name}' by ")?;
-        for owner in *owners {
-            write!(f, "{owner}, ")?;
-        }
-        write!(f, "licensed under '{license}'")?;
+        for (idx, answer) in self.0.into_iter().flatten().enumerate() {
+            write!(f, ", !{} {}", idx + 1, Escaped(answer))?;
+        }
         Ok(())
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Passback {
-    Quit,
-}
+/// When things happened this session, on the wall clock.
+struct WallTimes {
+    launched: SystemTime,
+    started: Option<SystemTime>, // when the stopwatch was last started
+    stopped: Option<SystemTime>, // when the stopwatch was last stopped
+}
+
+/// A stopwatch as listed by the summary.
+struct SummaryRow {
+    name: String,
+    group: String,
+    current: bool, // whether it's this session's stopwatch
+    running: bool,
+    elapsed: Duration,
+    started: Option<SystemTime>,
+    prec: Option<Precision>, // its own, if it differs from the session's
+}
+
+impl SummaryRow {
+    /// Lists `current`, this session's stopwatch, followed by every other
+    /// persisted stopwatch in order of name. If this session's stopwatch is
+    /// persisted, it's stored at `path`. Saved automatic precision scales by
+    /// `auto`.
+    fn list(
+        current: Self,
+        path: Option<&Path>,
+        (now, sys_now): (Instant, SystemTime),
+        auto: AutoPrecision,
+    ) -> io::Result<Vec<Self>> {
+        let mut rows = vec![current]; // @alloc
+        let others = persist::list()?
+            .into_iter()
+            .filter(|(other, _)| Some(other.as_path()) != path)
+            .map(|(_, saved)| {
+                let sw = saved.to_sw(now, sys_now);
+                Self {
+                    started: saved.last_started(),
+                    prec: saved.precision.map(|spec| spec.resolve(auto)),
+                    name: saved.name,
+                    group: saved.group,
+                    current: false,
+                    running: sw.is_running(),
+                    elapsed: sw.elapsed_at(now),
+                }
+            });
+        rows.extend(others);
+        rows[1..].sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+}
+
+/// State file which the stopwatch is resumed from and stored to.
+struct Persisted {
+    path: PathBuf,
+    saved: Saved,
+    stored: Sw, // stopwatch as of the last store
+}
+
+impl Persisted {
+    /// Saves `prec` as the stopwatch's own precision, so that it's used
+    /// whenever the stopwatch is resumed.
+    fn remember_precision(&mut self, prec: Precision) -> io::Result<()> {
+        let spec = Some(prec.spec());
+        if self.saved.precision == spec {
+            return Ok(());
+        }
+        self.saved.precision = spec;
+        self.saved.store(&self.path)
+    }
+}
+
+pub struct State<'shell> {
+    sw: Sw,
+    laps: Laps,
+    marks: Marks,
+    alarms: Alarms,
+    timer: Timer,
+    history: History,
+    answers: Answers,
+    events: Events,
+    chess: Option<Chess>, // the other side's stopwatch, in chess clock mode
+    persisted: Option<Persisted>,
+    broadcast: Option<Broadcast>, // feeds the elapsed time to status files and such
+    control: Option<Listener>,
+    escalator: Option<Escalator>, // rings while alarms go unacknowledged
+    since_stop: Sw,
+    opened: Instant, // when the session began, for the summary on quitting
+    wall: WallTimes,
+    source: Source,
+    clock: Box<dyn Clock>,        // where the time is read from
+    anchor: WallAnchor,           // when the wall clock was last compared with the monotonic clock
+    frozen: Option<Duration>,     // elapsed time shown by Display instead of the live value
+    reset_from: Option<Duration>, // elapsed time before the last reset, for Unreset
+    banked: Duration,             // elapsed time discarded by resets this session, for Total
+    name: String,
+    group: String,     // group whose time the stopwatch counts towards, if not empty
+    tags: Vec<String>, // set by templates, for other programs to sort sessions by
+    input: String,
+    arg: String, // argument given on the same line as the command
+    recent: VecDeque<(Option<Command>, bool)>, // commands for crash reports, and whether they had an argument
+    seen: HashMap<String, (Duration, usize)>, // elapsed time and number of events when each client detached
+    client: String, // terminal of the attached client, by which it's told apart from others
+    usage: Option<Usage>, // commands run this session, if they're recorded
+    typed: String,  // name the last command was typed as, if usage is recorded
+    prec: Precision,
+    upgraded_from: Option<String>,
+    config: Config,
+    shell: &'shell mut Shell,
+}
+
+impl<'shell> State<'shell> {
+    pub const DEFAULT_PRECISION: u8 = 2;
+    const MAX_PRECISION: u8 = crate::MAX_NANOS_CHARS;
+    const COMMAND_SUGGEST_SIMILAR_THRESHOLD: f64 = 0.4;
+    const COMMAND_SUGGEST_COUNT: usize = 3;
+    const RECENT_LEN: usize = 16;
+
+    pub fn new(
+        shell: &'shell mut Shell,
+        name: String,
+        upgraded_from: Option<String>,
+        config: Config,
+        clock: Box<dyn Clock>,
+    ) -> Self {
+        let input = String::with_capacity(shell.read_limit().into()); // @alloc
+        let anchor = WallAnchor::new(clock.now(), clock.sys_now());
+        let mut laps = Laps::new();
+        laps.target = config.lap_target;
+        Self {
+            sw: Sw::new(),
+            laps,
+            marks: Marks::new(),
+            alarms: Alarms::new(),
+            timer: Timer::new(),
+            history: History::new(),
+            answers: Answers::new(config.prompt_defaults),
+            events: Events::new(),
+            chess: None,
+            persisted: None,
+            broadcast: None,
+            control: None,
+            escalator: None,
+            since_stop: Sw::new_started_at(clock.now()),
+            opened: clock.now(),
+            wall: WallTimes {
+                launched: clock.sys_now(),
+                started: None,
+                stopped: None,
+            },
+            source: Source::Monotonic,
+            clock,
+            anchor,
+            frozen: None,
+            reset_from: None,
+            banked: Duration::ZERO,
+            name,
+            group: String::new(),
+            tags: Vec::new(),
+            input,
+            arg: String::new(),
+            recent: VecDeque::with_capacity(Self::RECENT_LEN), // @alloc
+            seen: HashMap::new(),
+            client: String::new(),
+            usage: config.record_usage.then(Usage::default),
+            typed: String::new(),
+            prec: if config.auto_precision {
+                Precision::Auto(config.precision_thresholds)
+            } else {
+                Precision::Fixed(Self::DEFAULT_PRECISION)
+            },
+            upgraded_from,
+            config,
+            shell,
+        }
+    }
+
+    /// If the persisted stopwatch of the same name missed its scheduled
+    /// start, offers to start with the missed time already elapsed. Either
+    /// way, the schedule is cleared so that it's only offered once.
+    pub fn offer_backfill(&mut self) -> io::Result<()> {
+        if !self.shell.is_interactive() {
+            return Ok(());
+        }
+        let mut cb = self.shell.create_cmd_buf();
+        let (path, mut saved) = if let Some(persisted) = &self.persisted {
+            (persisted.path.clone(), persisted.saved.clone()) // @alloc
+        } else {
+            // the session works without the state file, so failing to read
+            // it only means there's no schedule to offer
+            let found = Saved::path(&self.name).and_then(|path| {
+                let loaded = Saved::load(&path, &self.name)?;
+                Ok(loaded.map(|Loaded { saved, .. }| (path, saved)))
+            });
+            match found {
+                Ok(Some(found)) => found,
+                Ok(None) => return Ok(()),
+                Err(err) => {
+                    return cb.warn(format_args!("can't check for a scheduled start: {err}"));
+                }
+            }
+        };
+        let Some(missed) = saved.missed_at(self.clock.sys_now()) else {
+            return Ok(());
+        };
+
+        cb.info_idle(format_args!(
+            "scheduled start passed {} ago",
+            DurationFmt::new(missed, 0, cb.visual_cues())
+        ))?;
+        let received = cb.read(
+            &mut self.input,
+            format_args!("start with the missed time elapsed? [y/N] "),
+        )?;
+        if received == Received::Line && Shell::input(&self.input).eq_ignore_ascii_case("y") {
+            let now = self.clock.now();
+            self.sw.set(missed);
+            self.sw.start_at(now);
+            cb.info_change(format_args!("started stopwatch"))?;
+        } else {
+            cb.info_idle(format_args!("stopwatch not started"))?;
+        }
+
+        saved.scheduled = None;
+        if let Some(persisted) = &mut self.persisted {
+            persisted.saved.scheduled = None;
+            self.store(true)
+        } else if let Err(err) = saved.store(&path) {
+            self.shell
+                .create_cmd_buf()
+                .warn(format_args!("can't clear the scheduled start: {err}"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resumes the stopwatch persisted at `path`, which is stored to as it
+    /// changes, along with its precision if it has its own. If the stopwatch
+    /// has no name, it's named after the saved stopwatch, or otherwise
+    /// `default_name`.
+    pub fn resume(&mut self, path: PathBuf, default_name: &str) -> io::Result<()> {
+        let now = self.clock.now();
+        let sys_now = self.clock.sys_now();
+        let interactive = self.shell.is_interactive();
+        let mut cb = self.shell.create_cmd_buf();
+        let saved = match Saved::load(&path, &self.name)? {
+            Some(Loaded {
+                mut saved,
+                migrated,
+            }) => {
+                report_migrated(&mut cb, &migrated)?;
+                report_disconnected(&mut cb, &mut saved, sys_now, self.prec)?;
+                self.sw = saved.to_sw(now, sys_now);
+                if self.name.is_empty() {
+                    self.name.push_str(&saved.name);
+                }
+                self.group.clone_from(&saved.group);
+                if let Some(closed) = saved.closed.take().filter(|_| self.sw.is_running()) {
+                    if interactive {
+                        let event = Self::rejoin(
+                            &mut cb,
+                            &mut self.input,
+                            &mut self.sw,
+                            &saved,
+                            closed,
+                            (now, sys_now),
+                        )?;
+                        let elapsed = self.sw.elapsed_at(now);
+                        let running = self.sw.is_running();
+                        self.events
+                            .push(LogEvent::new(event, elapsed, running, sys_now));
+                    }
+                }
+                cb.info_idle(format_args!(
+                    "resumed stopwatch at {} ({})",
+                    DurationFmt::scaled(self.sw.elapsed_at(now), self.prec, cb.visual_cues()),
+                    if self.sw.is_running() {
+                        "running"
+                    } else {
+                        "stopped"
+                    }
+                ))?;
+                saved
+            }
+            None => Saved::new(String::new()),
+        };
+        if self.name.is_empty() {
+            self.name.push_str(default_name);
+        }
+        if let Some(spec) = saved.precision {
+            self.apply_precision(spec)?;
+        }
+        self.persisted = Some(Persisted {
+            path,
+            saved,
+            stored: self.sw,
+        });
+        // files migrated from older versions are rewritten right away
+        self.store(true)
+    }
+
+    /// Asks what to do about the time since the last session quit at
+    /// `closed`, leaving the stopwatch running, while it was still running.
+    /// Returns the name of the event recording the choice.
+    fn rejoin(
+        cb: &mut CmdBuf<'_>,
+        input: &mut String,
+        sw: &mut Sw,
+        saved: &Saved,
+        closed: SystemTime,
+        (now, sys_now): (Instant, SystemTime),
+    ) -> io::Result<&'static str> {
+        let gap = sys_now.duration_since(closed).unwrap_or_default();
+        cb.info_idle(format_args!(
+            "last session quit {} ago with the stopwatch running",
+            DurationFmt::new(gap, 0, cb.visual_cues())
+        ))?;
+        let received = cb.read(
+            input,
+            format_args!(
+                "keep counting (k), pause at quit (p), or discard the time since (d)? [K/p/d] "
+            ),
+        )?;
+        // as if the session had just quit
+        let at_close = saved.to_sw(now, closed);
+        let answer = Shell::input(input);
+        Ok(
+            if received == Received::Line && answer.eq_ignore_ascii_case("p") {
+                *sw = at_close;
+                sw.stop_at(now);
+                cb.info_change(format_args!("paused stopwatch as of last session"))?;
+                "rejoin-pause"
+            } else if received == Received::Line && answer.eq_ignore_ascii_case("d") {
+                *sw = at_close;
+                cb.info_change(format_args!("discarded time since last session"))?;
+                "rejoin-discard"
+            } else {
+                cb.info_idle(format_args!("kept time since last session"))?;
+                "rejoin-keep"
+            },
+        )
+    }
+
+    /// Saves the stopwatch as the session quits. If it's left running, this
+    /// is noted so that the next session can ask about the time in between.
+    pub fn close(&mut self) -> io::Result<()> {
+        if let Some(usage) = &self.usage {
+            usage.save()?;
+        }
+        let Some(persisted) = &mut self.persisted else {
+            return Ok(());
+        };
+        // a stopwatch left running by a script is meant to keep running, as
+        // with the subcommands, so nothing needs accounting for next time
+        persisted.saved.closed =
+            (self.sw.is_running() && self.shell.is_interactive()).then(|| self.clock.sys_now());
+        self.store(true)
+    }
+
+    /// Restores the stopwatch of the same name if the shell lost its terminal
+    /// last time, so that the time isn't lost.
+    pub fn recover_disconnected(&mut self) -> io::Result<()> {
+        if self.persisted.is_some() || !self.shell.is_interactive() {
+            // either already reported when resuming, or nobody to tell
+            return Ok(());
+        }
+        // problems with the file are reported when offering a backfill
+        let Ok(path) = Saved::path(&self.name) else {
+            return Ok(());
+        };
+        let Ok(Some(Loaded { mut saved, .. })) = Saved::load(&path, &self.name) else {
+            return Ok(());
+        };
+        if saved.disconnected.is_none() {
+            return Ok(());
+        }
+        let now = self.clock.now();
+        let sys_now = self.clock.sys_now();
+        let mut cb = self.shell.create_cmd_buf();
+        report_disconnected(&mut cb, &mut saved, sys_now, self.prec)?;
+        self.sw = saved.to_sw(now, sys_now);
+        cb.info_idle(format_args!(
+            "restored stopwatch at {}",
+            DurationFmt::scaled(self.sw.elapsed_at(now), self.prec, cb.visual_cues())
+        ))?;
+        saved.store(&path)
+    }
+
+    /// Sets the precision, as given on the command line. A persisted
+    /// stopwatch remembers it.
+    pub fn set_precision(&mut self, spec: Spec) -> io::Result<()> {
+        self.apply_precision(spec)?;
+        if let Some(persisted) = &mut self.persisted {
+            persisted.remember_precision(self.prec)?;
+        }
+        Ok(())
+    }
+
+    fn apply_precision(&mut self, spec: Spec) -> io::Result<()> {
+        match spec {
+            Spec::Auto => self.prec = Precision::Auto(self.config.precision_thresholds),
+            Spec::Fixed(spec) => {
+                let (new_prec, clamped) = Self::clamp_prec(spec);
+                self.prec = Precision::Fixed(new_prec);
+                if clamped {
+                    let mut cb = self.shell.create_cmd_buf();
+                    cb.warn(format_args!("precision clamped to {new_prec}"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the elapsed time, as given on the command line.
+    pub fn set_elapsed(&mut self, elapsed: Duration) -> io::Result<()> {
+        self.sw.set(elapsed);
+        let event = LogEvent::new(
+            "change",
+            elapsed,
+            self.sw.is_running(),
+            self.clock.sys_now(),
+        );
+        self.events.push(event);
+        let mut cb = self.shell.create_cmd_buf();
+        cb.info_change(format_args!(
+            "set elapsed time to {}",
+            DurationFmt::scaled(elapsed, self.prec, cb.visual_cues())
+        ))
+    }
+
+    /// Writes a table of `rows`, with the columns lined up by their width on
+    /// the terminal.
+    fn summary(
+        cb: &mut CmdBuf<'_>,
+        rows: &[SummaryRow],
+        prec: Precision,
+        sys_now: SystemTime,
+    ) -> io::Result<()> {
+        const HEADER: [&str; 5] = ["name", "group", "state", "elapsed", "last started"];
+        let (visual_cues, theme) = (cb.visual_cues(), cb.theme());
+        let clip = |text: &str| Escaped(text).clip(cb.name_width()).to_string(); // @alloc
+        let cells: Vec<[String; 5]> = rows
+            .iter()
+            .map(|row| {
+                let name = if row.name.is_empty() {
+                    String::from("(unnamed)") // @alloc
+                } else {
+                    clip(&row.name)
+                };
+                let state = if row.running { "running" } else { "stopped" };
+                let prec = row.prec.unwrap_or(prec);
+                let elapsed = DurationFmt::scaled(row.elapsed, prec, visual_cues).to_string();
+                let started = row.started.map_or_else(
+                    || String::from("-"), // @alloc
+                    |at| {
+                        let ago = sys_now.duration_since(at).unwrap_or_default();
+                        format!("{} ago", DurationFmt::new(ago, 0, visual_cues))
+                        // @alloc
+                    },
+                );
+                [name, clip(&row.group), state.to_string(), elapsed, started] // @alloc
+            })
+            .collect(); // @alloc
+        let mut widths = HEADER.map(UnicodeWidthStr::width);
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = cmp::max(*width, cell.width());
+            }
+        }
+        // the columns are two apart, after a marker for this session's
+        // stopwatch, and only the state is colored
+        let write_row = |cb: &mut CmdBuf<'_>, marker, row: [&str; 5], color| {
+            let pad = |idx: usize| widths[idx].saturating_sub(row[idx].width());
+            let [name, group, state, elapsed, started] = row;
+            cb.write(format_args!(
+                "{marker} {name}{0:1$}  {group}{0:2$}  ",
+                "",
+                pad(0),
+                pad(1)
+            ))?;
+            cb.write_color(
+                ColorSpec::new().set_fg(color),
+                format_args!("{state}{:1$}", "", pad(2)),
+            )?;
+            cb.writeln(format_args!("  {:1$}{elapsed}  {started}", "", pad(3)))
+        };
+        write_row(cb, ' ', HEADER, None)?;
+        for (row, cells) in rows.iter().zip(&cells) {
+            let marker = if row.current { '>' } else { ' ' };
+            let color = if row.running {
+                theme.running
+            } else {
+                theme.stopped
+            };
+            let [name, group, state, elapsed, started] = cells;
+            write_row(cb, marker, [name, group, state, elapsed, started], color)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the total elapsed time of the stopwatches in each group, in
+    /// order of name, followed by those in none. A total is shown with the
+    /// precision which all of its stopwatches have, or otherwise `prec`.
+    fn groups(cb: &mut CmdBuf<'_>, rows: &[SummaryRow], prec: Precision) -> io::Result<()> {
+        // number of stopwatches, how many of them are running, their total,
+        // and their precision
+        let mut groups: BTreeMap<&str, (usize, usize, Duration, Option<Precision>)> =
+            BTreeMap::new();
+        for row in rows {
+            let row_prec = row.prec.unwrap_or(prec);
+            let (count, running, total, shared) = groups.entry(&row.group).or_default(); // @alloc
+            *shared = if *count == 0 || *shared == Some(row_prec) {
+                Some(row_prec)
+            } else {
+                None
+            };
+            *count += 1;
+            *running += usize::from(row.running);
+            *total = total.saturating_add(row.elapsed);
+        }
+        if groups.keys().all(|group| group.is_empty()) {
+            return cb.info_idle(format_args!("no stopwatches are in groups"));
+        }
+        let ungrouped = groups.remove("");
+        let visual_cues = cb.visual_cues();
+        let named: Vec<(String, _)> = groups
+            .into_iter()
+            .map(|(group, stats)| (Escaped(group).clip(cb.name_width()).to_string(), stats))
+            .chain(ungrouped.map(|stats| (String::from("(ungrouped)"), stats))) // @alloc
+            .collect(); // @alloc
+
+        // the totals line up after the names
+        let width = named.iter().map(|(group, _)| group.width()).max();
+        let lines: Vec<String> = named
+            .iter()
+            .map(|(group, (count, running, total, shared))| {
+                let pad = width.unwrap_or_default().saturating_sub(group.width());
+                let stopwatches = if *count == 1 {
+                    "stopwatch"
+                } else {
+                    "stopwatches"
+                };
+                format!(
+                    "{group}: {:pad$}{} over {count} {stopwatches}, {running} running",
+                    "",
+                    DurationFmt::scaled(*total, shared.unwrap_or(prec), visual_cues)
+                ) // @alloc
+            })
+            .collect(); // @alloc
+        cb.writeln_paged(&lines)
+    }
+
+    /// Writes the predicted finish, along with how long remains until it and,
+    /// while running, when it will be on the wall clock.
+    fn predict(
+        cb: &mut CmdBuf<'_>,
+        prediction: Prediction,
+        sw: &Sw,
+        prec: Precision,
+        (now, sys_now): (Instant, SystemTime),
+    ) -> io::Result<()> {
+        let visual_cues = cb.visual_cues();
+        cb.info_idle(format_args!(
+            "{}",
+            PredictionFmt::new(prediction, prec, visual_cues)
+        ))?;
+        let remaining = (prediction.finish)
+            .checked_sub(sw.elapsed_at(now))
+            .filter(|remaining| !remaining.is_zero());
+        let Some(remaining) = remaining else {
+            return cb.info_idle(format_args!("the projected finish has passed"));
+        };
+        let left = DurationFmt::scaled(remaining, prec, visual_cues);
+        match sys_now.checked_add(remaining).filter(|_| sw.is_running()) {
+            Some(at) => cb.info_idle(format_args!(
+                "{left} remaining, finishing around {}",
+                TimeOfDay::at(at, export::local_offset())
+            )),
+            None => cb.info_idle(format_args!("{left} remaining")),
+        }
+    }
+
+    /// Writes when the session began and the stopwatch last started and
+    /// stopped, on the wall clock, along with the elapsed time and the clock
+    /// `source` it's kept by.
+    fn info(
+        cb: &mut CmdBuf<'_>,
+        wall: &WallTimes,
+        sw: &Sw,
+        since_stop: &Sw,
+        source: Source,
+        prec: Precision,
+        (now, sys_now): (Instant, SystemTime),
+    ) -> io::Result<()> {
+        let offset = export::local_offset();
+        let visual_cues = cb.visual_cues();
+        let at = |time: SystemTime| {
+            let ago = sys_now.duration_since(time).unwrap_or_default();
+            (
+                TimeOfDay::at(time, offset),
+                DurationFmt::new(ago, 0, visual_cues),
+            )
+        };
+
+        let (time, ago) = at(wall.launched);
+        cb.info_idle(format_args!("launched at {time}, {ago} ago"))?;
+        if let Some((time, ago)) = wall.started.map(at) {
+            cb.info_idle(format_args!("last started at {time}, {ago} ago"))?;
+        } else if sw.is_running() {
+            cb.info_idle(format_args!("started before this session"))?;
+        } else {
+            cb.info_idle(format_args!("not started this session"))?;
+        }
+        if let Some((time, ago)) = wall.stopped.map(at) {
+            cb.info_idle(format_args!("last stopped at {time}, {ago} ago"))?;
+        } else {
+            cb.info_idle(format_args!("not stopped this session"))?;
+        }
+        cb.info_idle(format_args!(
+            "elapsed time {} ({})",
+            DurationFmt::scaled(sw.elapsed_at(now), prec, visual_cues),
+            if sw.is_running() {
+                "running"
+            } else {
+                "stopped"
+            }
+        ))?;
+        if sw.is_stopped() {
+            cb.info_idle(format_args!(
+                "stopped for {}",
+                DurationFmt::scaled(since_stop.elapsed_at(now), prec, visual_cues)
+            ))?;
+        }
+        cb.info_idle(format_args!("keeping time by the {} clock", source.name()))
+    }
+
+    /// Starts the stopwatch, as when the shell opens with `--start`.
+    pub fn start(&mut self) -> io::Result<()> {
+        let now = self.clock.now();
+        let mut cb = self.shell.create_cmd_buf();
+        if self.sw.is_running() {
+            return cb.info_idle(format_args!("stopwatch already running"));
+        }
+        self.sw.start_at(now);
+        self.since_stop.reset();
+        let sys_now = self.clock.sys_now();
+        self.wall.started = Some(sys_now);
+        let event = LogEvent::new("start", self.sw.elapsed_at(now), true, sys_now);
+        self.events.push(event);
+        cb.info_change(format_args!("started stopwatch"))?;
+        play(&mut cb, &self.config.sounds, Event::Start)?;
+        hook(&mut cb, &self.config.hooks, &event, &self.name)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.sw.elapsed_at(self.clock.now())
+    }
+
+    /// Elapsed time along with the time discarded by resets this session.
+    #[cfg(test)]
+    pub fn total(&self) -> Duration {
+        self.banked.saturating_add(self.elapsed())
+    }
+
+    #[cfg(test)]
+    pub const fn events(&self) -> &Events {
+        &self.events
+    }
+
+    pub const fn precision(&self) -> Precision {
+        self.prec
+    }
+
+    /// Stops the stopwatch at the moment input ended and saves it with a
+    /// marker, so that the next launch can report what happened.
+    pub fn disconnect(&mut self) -> io::Result<()> {
+        let now = self.clock.now();
+        let at = self.shell.last_read_time.unwrap_or(now);
+        let sys_now = self.clock.sys_now();
+        let sys_at = sys_now
+            .checked_sub(now.saturating_duration_since(at))
+            .unwrap_or(sys_now);
+        if self.sw.is_running() {
+            self.sw.stop_at(at);
+        }
+
+        if self.persisted.is_none() {
+            let path = Saved::path(&self.name)?;
+            let saved = match Saved::load(&path, &self.name) {
+                Ok(Some(Loaded { saved, .. })) => saved,
+                // a broken file is no reason to lose the stopwatch
+                Ok(None) | Err(_) => Saved::new(String::new()),
+            };
+            self.persisted = Some(Persisted {
+                path,
+                saved,
+                stored: self.sw,
+            });
+        }
+        if let Some(persisted) = &mut self.persisted {
+            persisted.saved.disconnected = Some(sys_at);
+        }
+        self.store(true)?;
+        self.update_status();
+
+        // the terminal is most likely gone, so this is only a courtesy
+        let mut cb = self.shell.create_cmd_buf();
+        _ = cb.info_change(format_args!("stopped and saved stopwatch for next time"));
+        Ok(())
+    }
+
+    /// Keeps time by the clock `source` says, warning if it had to fall back
+    /// to the wall clock. A persisted stopwatch remembers the choice, and
+    /// keeps its clock from last time if `source` is [`None`].
+    pub fn set_clock(&mut self, source: Option<Source>) -> io::Result<()> {
+        let source = match &mut self.persisted {
+            Some(persisted) if source.is_some() && persisted.saved.clock != source => {
+                persisted.saved.clock = source;
+                persisted.saved.store(&persisted.path)?;
+                source
+            }
+            Some(persisted) if source.is_none() => persisted.saved.clock,
+            _ => source,
+        };
+        let (source, fell_back) = source.unwrap_or(Source::Monotonic).resolve();
+        self.source = source;
+        self.anchor = WallAnchor::new(self.clock.now(), self.clock.sys_now());
+        let mut cb = self.shell.create_cmd_buf();
+        if fell_back {
+            cb.warn(format_args!(
+                "the monotonic clock stands still while suspended here, so timing by the wall clock"
+            ))?;
+        } else if source == Source::Wall {
+            cb.info_idle(format_args!("timing by the wall clock"))?;
+        }
+        Ok(())
+    }
+
+    /// Starts keeping the elapsed time written to `path` in the background.
+    pub fn write_status_to(&mut self, path: PathBuf) {
+        self.broadcast().subscribe(Subscriber::File(path));
+    }
+
+    /// Broadcast of the elapsed time, which is started once something
+    /// subscribes to it.
+    pub fn broadcast(&mut self) -> &Broadcast {
+        let sw = self.sw;
+        self.broadcast.get_or_insert_with(|| Broadcast::spawn(sw))
+    }
+
+    fn update_status(&self) {
+        if let Some(broadcast) = &self.broadcast {
+            broadcast.update(self.sw);
+        }
+    }
+
+    /// Connects to the terminal of a client attached to the daemon. If the
+    /// `client` attached before, what changed since it detached is
+    /// summarized.
+    pub fn attach(
+        &mut self,
+        reader: Box<dyn BufRead>,
+        writer: Box<dyn WriteColor>,
+        client: &str,
+    ) -> io::Result<()> {
+        self.shell.attach(reader, writer)?;
+        self.client.replace_range(.., client);
+        let mut cb = self.shell.create_cmd_buf();
+        cb.info_idle(format_args!(
+            "attached to stopwatch at {} ({})",
+            DurationFmt::scaled(
+                self.sw.elapsed_at(self.clock.now()),
+                self.prec,
+                cb.visual_cues()
+            ),
+            if self.sw.is_running() {
+                "running"
+            } else {
+                "stopped"
+            }
+        ))?;
+
+        // other clients may have changed the stopwatch since this one looked
+        let Some(&(elapsed, seen)) = self.seen.get(client) else {
+            return Ok(());
+        };
+        let now = self.sw.elapsed_at(self.clock.now());
+        let tally = self.events.tally(seen);
+        if tally.is_empty() {
+            return Ok(());
+        }
+        cb.info_idle(format_args!(
+            "since you detached, elapsed time went from {} to {}",
+            DurationFmt::scaled(elapsed, self.prec, cb.visual_cues()),
+            DurationFmt::scaled(now, self.prec, cb.visual_cues())
+        ))?;
+        let tally = tally
+            .iter()
+            .map(|(name, count)| format!("{name} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", "); // @alloc
+        cb.info_idle(format_args!("meanwhile: {tally}"))
+    }
+
+    /// Disconnects from the attached client, leaving the stopwatch as it is.
+    pub fn detach(&mut self) {
+        self.shell.detach();
+        if !self.client.is_empty() {
+            let seen = (self.sw.elapsed_at(self.clock.now()), self.events.len());
+            self.seen.insert(mem::take(&mut self.client), seen); // @alloc
+        }
+    }
+
+    /// Starts accepting commands from other programs on a socket next to the
+    /// state file.
+    pub fn listen(&mut self) -> io::Result<()> {
+        let path = match &self.persisted {
+            Some(persisted) => persisted.path.with_extension("sock"),
+            None => Saved::path(&self.name)?.with_extension("sock"),
+        };
+        let listener = Listener::bind(path, self.config.hooks.clone(), self.name.clone())?; // @alloc
+        let mut cb = self.shell.create_cmd_buf();
+        cb.info_idle(format_args!(
+            "listening for commands on {}",
+            listener.path().display()
+        ))?;
+        self.control = Some(listener);
+        Ok(())
+    }
+
+    /// Writes the events of the session to `path`, such as when quitting.
+    pub fn export_to(&mut self, path: &Path) -> io::Result<()> {
+        let mut cb = self.shell.create_cmd_buf();
+        Self::export(&mut cb, path, &self.events, &self.name, &self.tags)
+    }
+
+    /// Shows the elapsed time and whether the stopwatch is running, along
+    /// with the countdown, or both sides in chess clock mode.
+    fn display(
+        cb: &mut CmdBuf<'_>,
+        sw: &Sw,
+        chess: Option<&Chess>,
+        frozen: Option<Duration>,
+        timer: &Timer,
+        prec: Precision,
+        now: Instant,
+    ) -> io::Result<()> {
+        if let Some(chess) = chess {
+            let visual_cues = cb.visual_cues();
+            let elapsed = chess
+                .sides(sw)
+                .map(|sw| DurationFmt::scaled(sw.elapsed_at(now), prec, visual_cues).to_string()); // @alloc
+            let width = elapsed.iter().map(String::len).fold(6, cmp::max);
+            cb.writeln(format_args!("{:width$}   side 2", "side 1"))?;
+            cb.writeln(format_args!("{:width$}   {}", elapsed[0], elapsed[1]))?;
+            for (idx, sw) in chess.sides(sw).into_iter().enumerate() {
+                let (state, color) = if sw.is_running() {
+                    ("running", Color::Green)
+                } else if usize::from(chess.side_to_move()) == idx + 1 {
+                    ("to move", Color::Yellow)
+                } else {
+                    ("waiting", Color::Yellow)
+                };
+                let color = ColorSpec::new().set_fg(Some(color)).clone();
+                if idx == 0 {
+                    cb.write_color(&color, format_args!("{state:width$}"))?;
+                    cb.write(format_args!("   "))?;
+                } else {
+                    cb.writeln_color(&color, format_args!("{state}"))?;
+                }
+            }
+        } else {
+            cb.writeln_elapsed(frozen.unwrap_or_else(|| sw.elapsed_at(now)), prec)?;
+            let (state, color) = if sw.is_running() {
+                ("running", Color::Green)
+            } else {
+                ("stopped", Color::Yellow)
+            };
+            cb.writeln_color(
+                ColorSpec::new().set_fg(Some(color)),
+                format_args!("{state}"),
+            )?;
+            if let Some(overtime) = timer.overtime_at(now) {
+                // overtime stands out like an error, since it's past due
+                cb.writeln_color(
+                    ColorSpec::new().set_fg(Some(Color::Red)),
+                    format_args!(
+                        "countdown: +{} overtime",
+                        DurationFmt::scaled(overtime, prec, cb.visual_cues())
+                    ),
+                )?;
+            } else if let Some(countdown) = timer.countdown() {
+                let permille = countdown.permille_at(now);
+                cb.info_idle(format_args!(
+                    "countdown: {} left{}",
+                    DurationFmt::scaled(countdown.remaining_at(now), prec, cb.visual_cues()),
+                    if countdown.is_running() {
+                        ""
+                    } else {
+                        " (paused)"
+                    }
+                ))?;
+                if cb.visual_cues() {
+                    Self::progress_bar(cb, permille, countdown.is_running())?;
+                } else {
+                    cb.info_idle(format_args!("countdown {}% done", permille / 10))?;
+                }
+            }
+            if frozen.is_some() {
+                cb.info_idle(format_args!(
+                    r#"display frozen (enter "{}" to resume)"#,
+                    Command::Unfreeze.short_name_display()
+                ))?;
+            }
+            if sw.checked_elapsed_at(now).is_none() {
+                cb.error(format_args!(
+                    r#"elapsed time overflowing (enter "{}" to recover)"#,
+                    Command::Recover.short_name_display()
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws a bar as wide as the terminal allows, filled `permille`
+    /// thousandths of the way, followed by the percentage.
+    fn progress_bar(cb: &mut CmdBuf<'_>, permille: u16, running: bool) -> io::Result<()> {
+        const MAX_WIDTH: usize = 40;
+        // room for " 100%"
+        let cols = shell::terminal_size().map_or(80, |(cols, _)| usize::from(cols));
+        let width = cols.saturating_sub(5).min(MAX_WIDTH);
+        let filled = width * usize::from(permille) / 1000;
+        // like the state in the display, running is green
+        let color = if running { Color::Green } else { Color::Yellow };
+        cb.write_color(
+            ColorSpec::new().set_fg(Some(color)),
+            format_args!("{}", "\u{2588}".repeat(filled)), // @alloc
+        )?;
+        cb.write_color(
+            ColorSpec::new().set_dimmed(true),
+            format_args!("{}", "\u{2591}".repeat(width - filled)), // @alloc
+        )?;
+        cb.writeln(format_args!(" {}%", permille / 10))
+    }
+
+    fn export(
+        cb: &mut CmdBuf<'_>,
+        path: &Path,
+        events: &Events,
+        name: &str,
+        tags: &[String],
+    ) -> io::Result<()> {
+        match export::write(path, events.as_slice(), name, tags) {
+            Ok(check) => cb.info_change(format_args!(
+                "exported {} events to {}, verified by {}",
+                events.as_slice().len(),
+                path.display(),
+                check.display()
+            )),
+            Err(err) => cb.error(format_args!(
+                "failed to export to {}: {err}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Records the command which just ran as an event, if it changed the
+    /// stopwatch or laps from `before` to `after`.
+    fn log_change(
+        events: &mut Events,
+        command: Command,
+        before: (Sw, usize, Option<Chess>),
+        after: (Sw, &Laps, Option<Chess>),
+        (now, sys_now): (Instant, SystemTime),
+    ) -> Option<LogEvent> {
+        let (sw, lap_count, chess) = before;
+        let (new_sw, laps, new_chess) = after;
+        if (sw, lap_count, chess) == (new_sw, laps.as_slice().len(), new_chess) {
+            return None;
+        }
+        let elapsed = new_sw.elapsed_at(now);
+        let new = |name, elapsed| LogEvent::new(name, elapsed, new_sw.is_running(), sys_now);
+        let event = match command {
+            Command::Toggle if new_chess.is_some() && sw.is_running() => new("pass", elapsed),
+            Command::Toggle if new_sw.is_running() => new("start", elapsed),
+            Command::Toggle => new("stop", elapsed),
+            // the overflowing time is kept, since it's gone otherwise
+            Command::Recover => LogEvent {
+                duration: Some(sw.elapsed_at(now)),
+                ..new("recover", elapsed)
+            },
+            Command::Lap => {
+                let lap = laps.as_slice().last()?;
+                LogEvent {
+                    lap: Some(laps.as_slice().len()),
+                    duration: Some(lap.time),
+                    value: lap.value,
+                    ..new("lap", lap.split)
+                }
+            }
+            _ => new(command.long_name(), elapsed),
+        };
+        events.push(event);
+        Some(event)
+    }
+
+    /// Describes the session for a crash report. Anything typed is left out,
+    /// so that the report can be shared as is.
+    pub fn describe(&self, out: &mut String) {
+        let now = self.clock.now();
+        _ = writeln!(
+            out,
+            "elapsed: {:?} ({})",
+            self.sw.elapsed_at(now),
+            if self.sw.is_running() {
+                "running"
+            } else {
+                "stopped"
+            }
+        );
+        _ = writeln!(out, "precision: {}", self.prec);
+        _ = writeln!(out, "laps: {}", self.laps.as_slice().len());
+        _ = writeln!(out, "alarms: {}", self.alarms.as_slice().len());
+        _ = writeln!(out, "chess: {}", self.chess.is_some());
+        _ = writeln!(out, "persisted: {}", self.persisted.is_some());
+        _ = writeln!(out, "events: {}", self.events.as_slice().len());
+        out.push_str("recent commands, oldest first:");
+        for (command, had_arg) in &self.recent {
+            match command {
+                Some(command) => _ = write!(out, " {}", command.long_name()),
+                None => out.push_str(" (unknown)"),
+            }
+            if *had_arg {
+                out.push_str(" (argument)");
+            }
+            out.push(',');
+        }
+        if !self.recent.is_empty() {
+            out.pop();
+        }
+        out.push('\n');
+    }
+
+    /// Writes the stopwatch to its state file, if it's persisted. Unless
+    /// `force` is set, nothing is written if nothing changed.
+    pub fn store(&mut self, force: bool) -> io::Result<()> {
+        let Some(persisted) = &mut self.persisted else {
+            return Ok(());
+        };
+        let changed = persisted.stored.elapsed != self.sw.elapsed
+            || persisted.stored.start != self.sw.start
+            || persisted.saved.name != self.name
+            || persisted.saved.group != self.group;
+        if force || changed {
+            persisted
+                .saved
+                .set_sw(&self.sw, self.clock.now(), self.clock.sys_now());
+            persisted.saved.name.replace_range(.., &self.name);
+            persisted.saved.group.replace_range(.., &self.group);
+            persisted.saved.store(&persisted.path)?;
+            persisted.stored = self.sw;
+        }
+        Ok(())
+    }
+
+    pub fn clamp_prec(spec: u8) -> (u8, bool) {
+        let new = cmp::min(Self::MAX_PRECISION, spec);
+        let clamped = spec != new;
+        (new, clamped)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub fn update(&mut self) -> io::Result<Option<Passback>> {
+        let mut passback = None;
+        let interactive = self.shell.is_interactive();
+        let mut cb = self.shell.create_cmd_buf();
+        alert_due(
+            &mut cb,
+            &mut self.events,
+            &mut self.alarms,
+            &mut self.timer,
+            &self.sw,
+            &self.name,
+            &self.config,
+            self.prec,
+            (self.clock.now(), self.clock.sys_now()),
+        )?;
+        if interactive {
+            // escalation carries on across prompts, until the alarms which
+            // are due change
+            let mut due = self.alarms.due(&self.sw);
+            due.extend(self.timer.due());
+            let first = due.iter().min().copied();
+            if self.escalator.as_ref().map(Escalator::first) != first {
+                if let Some(escalator) = self.escalator.take() {
+                    escalator.stop();
+                }
+                self.escalator = Escalator::spawn(
+                    &due,
+                    self.config.escalation.clone(),
+                    self.config.sounds.clone(),
+                );
+            }
+        }
+        if let Some(control) = &self.control {
+            control.publish(self.sw, self.chess, self.prec, self.broadcast.as_ref());
+        }
+        if self.config.title {
+            let elapsed = self.sw.elapsed_at(self.clock.now());
+            let name = if self.name.is_empty() {
+                env!("CARGO_PKG_NAME")
+            } else {
+                &self.name
+            };
+            cb.write_title(format_args!(
+                "{} \u{2014} {} \u{2014} {}",
+                Escaped(name),
+                DurationFmt::scaled(elapsed, self.prec, cb.visual_cues()),
+                if self.sw.is_running() {
+                    "running"
+                } else {
+                    "stopped"
+                }
+            ))?;
+        }
+        let elapsed = (self.config.prompt_elapsed).then(|| {
+            let elapsed = self.sw.elapsed_at(self.clock.now());
+            DurationFmt::scaled(elapsed, self.prec, cb.visual_cues()).styled(cb.display_style())
+        });
+        let result = cb
+            .read_cmd(&mut self.input, &self.name, self.sw.is_running(), elapsed)?
+            .map(|(command, typed, arg)| {
+                self.arg.replace_range(.., arg);
+                if self.usage.is_some() {
+                    self.typed.replace_range(.., typed);
+                }
+                command
+            });
+        let jump = self.anchor.check(self.clock.now(), self.clock.sys_now());
+        // the system was suspended while waiting, or the clock was set
+        match jump.filter(|_| self.sw.is_running()) {
+            Some(Jump::Ahead(gap)) if self.source == Source::Wall => {
+                if let Some(sw) = self.sw.checked_add(gap) {
+                    self.sw = sw;
+                    cb.info_change(format_args!(
+                        "added {} which passed while the system was suspended",
+                        DurationFmt::scaled(gap, self.prec, cb.visual_cues())
+                    ))?;
+                } else {
+                    cb.warn(format_args!(
+                        "time which passed while suspended would overflow, ignoring it"
+                    ))?;
+                }
+            }
+            Some(Jump::Ahead(gap)) => cb.warn(format_args!(
+                "the wall clock jumped ahead by {}, which isn't counted (pass --clock wall to count time suspended)",
+                DurationFmt::scaled(gap, self.prec, cb.visual_cues())
+            ))?,
+            Some(Jump::Back(gap)) => cb.warn(format_args!(
+                "the wall clock jumped back by {}, which doesn't change the elapsed time",
+                DurationFmt::scaled(gap, self.prec, cb.visual_cues())
+            ))?,
+            None => (),
+        }
+        if self.escalator.is_some() {
+            // alarms which went off while waiting are announced before the
+            // command runs
+            alert_due(
+                &mut cb,
+                &mut self.events,
+                &mut self.alarms,
+                &mut self.timer,
+                &self.sw,
+                &self.name,
+                &self.config,
+                self.prec,
+                (self.clock.now(), self.clock.sys_now()),
+            )?;
+        }
+        for remote in self
+            .control
+            .as_ref()
+            .map(Listener::reclaim)
+            .unwrap_or_default()
+        {
+            let (sw, chess) = remote.before;
+            self.history.push(remote.command, sw, &self.laps, chess);
+            (self.sw, self.chess) = remote.after;
+            if remote.command == Command::Reset {
+                self.laps.clear();
+                let elapsed = sw.elapsed_at(self.clock.now());
+                self.banked = self.banked.saturating_add(elapsed);
+                self.reset_from = Some(elapsed).filter(|_| chess.is_none());
+            }
+            let (at, elapsed) = remote.at;
+            let event = LogEvent::new(remote.event, elapsed, self.sw.is_running(), at);
+            self.events.push(event);
+            cb.info_change(format_args!("{} remotely", remote.message))?;
+            if let Some(err) = remote.hook_failed {
+                cb.warn(format_args!("{err}"))?;
+            }
+        }
+        let before = (self.sw, self.laps.as_slice().len(), self.chess);
+        let mut executed = result.as_ref().ok().copied();
+        let ran = executed;
+        // plumbing commands also change the stopwatch, but answer with a single line
+        let sticky = self.config.sticky_display
+            && matches!(
+                executed,
+                Some(Command::Toggle | Command::Change | Command::Offset | Command::Reset)
+            );
+        if self.recent.len() == Self::RECENT_LEN {
+            self.recent.pop_front();
+        }
+        self.recent
+            .push_back((executed, executed.is_some() && !self.arg.is_empty()));
+        match result {
+            Ok(command) => match command {
+                Command::Help => Self::help(&mut cb)?,
+
+                Command::Display => Self::display(
+                    &mut cb,
+                    &self.sw,
+                    self.chess.as_ref(),
+                    self.frozen,
+                    &self.timer,
+                    self.prec,
+                    self.clock.now(),
+                )?,
+
+                Command::Big => {
+                    let elapsed = self
+                        .frozen
+                        .unwrap_or_else(|| self.sw.elapsed_at(self.clock.now()));
+                    let text = DurationFmt::scaled(elapsed, self.prec, true)
+                        .styled(cb.display_style())
+                        .to_string(); // @alloc
+                    let (cols, rows) = shell::terminal_size().unwrap_or((80, 24));
+                    let scale = big::fit(&text, cols, rows);
+                    if let Some(scale) = scale.filter(|_| cb.visual_cues()) {
+                        let color = cb.elapsed_color(elapsed);
+                        for row in big::render(&text, scale) {
+                            cb.writeln_color(&color, format_args!("{row}"))?;
+                        }
+                        let (state, color) = if self.sw.is_running() {
+                            ("running", Color::Green)
+                        } else {
+                            ("stopped", Color::Yellow)
+                        };
+                        cb.writeln_color(
+                            ColorSpec::new().set_fg(Some(color)),
+                            format_args!("{state}"),
+                        )?;
+                    } else {
+                        if cb.visual_cues() {
+                            cb.info_idle(format_args!("terminal is too small for big digits"))?;
+                        } else {
+                            cb.info_idle(format_args!("big digits need visual cues"))?;
+                        }
+                        Self::display(
+                            &mut cb,
+                            &self.sw,
+                            self.chess.as_ref(),
+                            self.frozen,
+                            &self.timer,
+                            self.prec,
+                            self.clock.now(),
+                        )?;
+                    }
+                }
+
+                Command::Freeze => {
+                    let elapsed = self.sw.elapsed_at(self.clock.now());
+                    if self.frozen.replace(elapsed).is_some() {
+                        cb.info_change(format_args!("refroze display"))?;
+                    } else {
+                        cb.info_change(format_args!("froze display"))?;
+                    }
+                    cb.info_idle(format_args!(
+                        "{} shown until unfrozen",
+                        DurationFmt::scaled(elapsed, self.prec, cb.visual_cues())
+                    ))?;
+                }
+
+                Command::Unfreeze => {
+                    if self.frozen.take().is_some() {
+                        cb.info_change(format_args!("unfroze display"))?;
+                    } else {
+                        cb.info_idle(format_args!("display not frozen"))?;
+                    }
+                }
+
+                Command::Total => {
+                    let current = self.sw.elapsed_at(self.clock.now());
+                    let total = self.banked.saturating_add(current);
+                    let dur = |dur| DurationFmt::scaled(dur, self.prec, cb.visual_cues());
+                    if self.banked.is_zero() {
+                        cb.info_idle(format_args!(
+                            "total {}, the same as the elapsed time",
+                            dur(total)
+                        ))?;
+                    } else {
+                        cb.info_idle(format_args!(
+                            "total {} ({} from before resets, plus {} now)",
+                            dur(total),
+                            dur(self.banked),
+                            dur(current)
+                        ))?;
+                    }
+                }
+
+                Command::Idle => {
+                    if self.sw.is_running() {
+                        cb.info_idle(format_args!("stopwatch is running"))?;
+                    } else {
+                        cb.info_idle(format_args!(
+                            "stopped for {}",
+                            DurationFmt::scaled(
+                                self.since_stop.elapsed_at(self.clock.now()),
+                                self.prec,
+                                cb.visual_cues()
+                            )
+                        ))?;
+                    }
+                }
+
+                Command::Lap => {
+                    let now = self.clock.now();
+                    let (value, label) = laps::parse_arg(&self.arg);
+                    self.history.push(command, self.sw, &self.laps, self.chess);
+                    self.laps.record(self.sw.elapsed_at(now), value, label);
+                    play(&mut cb, &self.config.sounds, Event::Lap)?;
+                    let count = self.laps.as_slice().len();
+                    let lap = &self.laps.as_slice()[count - 1];
+                    let label_width = lap.label.as_deref().map_or(0, |l| Escaped(l).width());
+                    cb.info_change(format_args!(
+                        "{}",
+                        LapFmt::new(count, lap, label_width, self.prec, cb.visual_cues())
+                    ))?;
+                    if self.laps.should_announce() {
+                        if let Some(average) = self.laps.average() {
+                            cb.info_idle(format_args!(
+                                "average lap {}",
+                                DurationFmt::scaled(average, self.prec, cb.visual_cues())
+                            ))?;
+                        }
+                        if let Some(prediction) =
+                            self.laps.target.and_then(|t| self.laps.predict(t))
+                        {
+                            cb.info_idle(format_args!(
+                                "{}",
+                                PredictionFmt::new(prediction, self.prec, cb.visual_cues())
+                            ))?;
+                        }
+                    }
+                }
+
+                Command::Laps => {
+                    if self.laps.as_slice().is_empty() {
+                        cb.info_idle(format_args!("no laps recorded"))?;
+                    } else {
+                        let visual_cues = cb.visual_cues();
+                        let laps = self.laps.as_slice();
+                        // labels are padded so that the times line up
+                        let label_width = laps
+                            .iter()
+                            .filter_map(|lap| Some(Escaped(lap.label.as_deref()?).width()))
+                            .max()
+                            .unwrap_or(0);
+                        // the fastest and slowest laps are only told apart if
+                        // their times differ
+                        let stats = self.laps.stats();
+                        let extremes = stats
+                            .filter(|stats| stats.min < stats.max)
+                            .map(|stats| (stats.fastest, stats.slowest));
+                        let lines: Vec<String> = laps
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, lap)| {
+                                let lap =
+                                    LapFmt::new(idx + 1, lap, label_width, self.prec, visual_cues);
+                                match extremes {
+                                    // without visual cues, colors are spelled out
+                                    Some((fastest, _)) if idx == fastest && !visual_cues => {
+                                        format!("{lap}, fastest")
+                                    }
+                                    Some((_, slowest)) if idx == slowest && !visual_cues => {
+                                        format!("{lap}, slowest")
+                                    }
+                                    _ => lap.to_string(),
+                                }
+                            })
+                            .collect(); // @alloc
+                        let theme = cb.theme();
+                        cb.writeln_paged_color(&lines, |idx| {
+                            let fg = match extremes {
+                                Some((fastest, _)) if idx == fastest => theme.fastest,
+                                Some((_, slowest)) if idx == slowest => theme.slowest,
+                                _ => None,
+                            };
+                            ColorSpec::new().set_fg(fg.filter(|_| visual_cues)).clone()
+                        })?;
+                        if let Some(stats) = stats.filter(|_| laps.len() > 1) {
+                            let dur = |dur| DurationFmt::scaled(dur, self.prec, visual_cues);
+                            cb.info_idle(format_args!(
+                                "min {}, max {}, mean {}, median {}",
+                                dur(stats.min),
+                                dur(stats.max),
+                                dur(stats.mean),
+                                dur(stats.median)
+                            ))?;
+                        }
+                    }
+                }
 
-pub struct State<'shell> {
-    sw: Sw,
-    since_stop: Sw,
-    name: String,
-    input: String,
-    prec: u8,
-    shell: &'shell mut Shell,
-}
+                Command::Label | Command::Unlap => {
+                    if self.arg.is_empty() {
+                        let prompt = if command == Command::Label {
+                            format_args!("lap number and label? ")
+                        } else {
+                            format_args!("lap number? ")
+                        };
+                        if cb.read_sub(&mut self.input, prompt, None)? == Received::Eof {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    let (number, label) = self.arg.split_once(' ').unwrap_or((&self.arg, ""));
+                    let label = Some(label.trim()).filter(|label| !label.is_empty());
+                    match laps::parse_number(number, self.laps.as_slice().len()) {
+                        Ok(number) => {
+                            self.history.push(command, self.sw, &self.laps, self.chess);
+                            if command == Command::Label {
+                                self.laps.relabel(number, label);
+                                if let Some(label) = label {
+                                    cb.info_change(format_args!(
+                                        "labeled lap {number} '{}'",
+                                        Escaped(label)
+                                    ))?;
+                                } else {
+                                    cb.info_change(format_args!("cleared label of lap {number}"))?;
+                                }
+                            } else {
+                                self.laps.remove(number);
+                                cb.info_change(format_args!("deleted lap {number}"))?;
+                            }
+                        }
+                        Err(err) => cb.error(format_args!("{err}"))?,
+                    }
+                }
 
-impl<'shell> State<'shell> {
-    const DEFAULT_PRECISION: u8 = 2;
-    const MAX_PRECISION: u8 = crate::MAX_NANOS_CHARS;
-    const COMMAND_SUGGEST_SIMILAR_THRESHOLD: f64 = 0.4;
+                Command::Mark => {
+                    if self.arg.is_empty() {
+                        if cb.read_sub(&mut self.input, format_args!("mark name? "), None)?
+                            == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if self.arg.is_empty() {
+                        cb.info_idle(format_args!("no mark set"))?;
+                    } else {
+                        let at = self.sw.elapsed_at(self.clock.now());
+                        let old = self.marks.set(&self.arg, at);
+                        let at = DurationFmt::scaled(at, self.prec, cb.visual_cues());
+                        let name = Escaped(&self.arg);
+                        if old.is_some() {
+                            cb.info_change(format_args!("moved mark '{name}' to {at}"))?;
+                        } else {
+                            cb.info_change(format_args!("marked '{name}' at {at}"))?;
+                        }
+                    }
+                }
 
-    pub fn new(shell: &'shell mut Shell, name: String) -> Self {
-        let input = String::with_capacity(shell.read_limit().into()); // @alloc
-        Self {
-            sw: Sw::new(),
-            since_stop: Sw::new_started(),
-            name,
-            input,
-            prec: Self::DEFAULT_PRECISION,
-            shell,
-        }
-    }
+                Command::Marks => {
+                    if self.marks.as_slice().is_empty() {
+                        cb.info_idle(format_args!("no marks set"))?;
+                    } else {
+                        let now = self.sw.elapsed_at(self.clock.now());
+                        let visual_cues = cb.visual_cues();
+                        let marks = self.marks.as_slice();
+                        let lines: Vec<String> = marks
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, mark)| {
+                                let previous = idx.checked_sub(1).map(|idx| &marks[idx]);
+                                MarkFmt {
+                                    mark,
+                                    previous,
+                                    now,
+                                    prec: self.prec,
+                                    visual_cues,
+                                }
+                                .to_string()
+                            })
+                            .collect(); // @alloc
+                        cb.writeln_paged(&lines)?;
+                    }
+                }
 
-    pub fn clamp_prec(spec: u8) -> (u8, bool) {
-        let new = cmp::min(Self::MAX_PRECISION, spec);
-        let clamped = spec != new;
-        (new, clamped)
-    }
+                Command::Announce => {
+                    if cb.read_sub(
+                        &mut self.input,
+                        format_args!("announce every how many laps? "),
+                        Some(format_args!("now {}", self.laps.announce.every)),
+                    )? == Received::Eof
+                    {
+                        let command = cb.eof_command();
+                        return Self::quit(&mut cb, &self.sw, self.clock.now(), self.prec, command);
+                    }
+                    let every = match Shell::input(&self.input) {
+                        "" => Ok(0),
+                        every => parse::normalize_digits(every).parse::<u32>(),
+                    };
+                    match every {
+                        Ok(0) => {
+                            self.laps.announce = Announce::default();
+                            cb.info_change(format_args!("disabled lap announcements"))?;
+                        }
+                        Ok(every) => {
+                            if cb.read_sub(
+                                &mut self.input,
+                                format_args!("finish after how many laps, or how long? "),
+                                None,
+                            )? == Received::Eof
+                            {
+                                let command = cb.eof_command();
+                                return Self::quit(
+                                    &mut cb,
+                                    &self.sw,
+                                    self.clock.now(),
+                                    self.prec,
+                                    command,
+                                );
+                            }
+                            let target = match Shell::input(&self.input) {
+                                "" | "0" => Ok(None),
+                                target => target.parse::<Target>().map(Some),
+                            };
+                            match target {
+                                Ok(target) => {
+                                    self.laps.announce = Announce { every };
+                                    self.laps.target = target;
+                                    cb.info_change(format_args!("announcing every {every} laps"))?;
+                                }
+                                Err(err) => cb.error(format_args!("{err}"))?,
+                            }
+                        }
+                        Err(err) => cb.error(format_args!("{err}"))?,
+                    }
+                }
 
-    pub fn update(&mut self) -> io::Result<Option<Passback>> {
-        let mut passback = None;
-        let mut cb = self.shell.create_cmd_buf();
-        let result = cb.read_cmd(&mut self.input, &self.name, self.sw.is_running())?;
-        match result {
-            Ok(command) => match command {
-                Command::Help => {
-                    for help_cmd in Command::iter() {
+                Command::Predict => {
+                    let target = match self.arg.as_str() {
+                        "" => Ok(self.laps.target),
+                        arg => arg.parse::<Target>().map(Some),
+                    };
+                    match target {
+                        Ok(Some(target)) => {
+                            // a target given here is kept for next time
+                            self.laps.target = Some(target);
+                            if let Some(prediction) = self.laps.predict(target) {
+                                let (now, sys_now) = (self.clock.now(), self.clock.sys_now());
+                                Self::predict(
+                                    &mut cb,
+                                    prediction,
+                                    &self.sw,
+                                    self.prec,
+                                    (now, sys_now),
+                                )?;
+                            } else {
+                                cb.info_idle(format_args!("no laps to predict from"))?;
+                            }
+                        }
+                        Ok(None) => cb.info_idle(format_args!(
+                            "no lap target set (expected a number of laps or a duration)"
+                        ))?,
+                        Err(err) => cb.error(format_args!("{err}"))?,
+                    }
+                }
+
+                Command::Alarm => {
+                    if self.arg.is_empty() {
+                        let (prec, visual_cues) = (self.prec, cb.visual_cues());
+                        if cb.read_sub_default(
+                            &mut self.input,
+                            format_args!("alarm at? "),
+                            None,
+                            None,
+                            Some(&|input| preview_dur(input, false, prec, visual_cues)),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if let Some(try_read_dur) = ReadDur::parse(&self.arg, false) {
+                        match try_read_dur {
+                            Ok(ReadDur { dur, is_neg }) => {
+                                assert!(!is_neg);
+                                let at = DurationFmt::scaled(dur, self.prec, cb.visual_cues());
+                                if self.alarms.add(dur) {
+                                    cb.info_change(format_args!("set alarm for {at}"))?;
+                                } else {
+                                    cb.info_idle(format_args!("alarm already set for {at}"))?;
+                                }
+                            }
+                            Err(err) => err.display(&mut cb)?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no alarm set"))?;
+                    }
+                }
+
+                Command::Alarms => {
+                    if self.alarms.is_empty() {
+                        cb.info_idle(format_args!("no alarms set"))?;
+                    }
+                    for (idx, alarm) in self.alarms.as_slice().iter().enumerate() {
                         cb.writeln(format_args!(
-                            "{} or {}. {}.",
-                            help_cmd.long_name(),
-                            help_cmd.short_name_display(),
-                            help_cmd.description()
+                            "alarm {}: {}{}",
+                            idx + 1,
+                            DurationFmt::scaled(alarm.at, self.prec, cb.visual_cues()),
+                            if alarm.is_pending() {
+                                " (passed, unacknowledged)"
+                            } else if alarm.is_passed() {
+                                " (passed)"
+                            } else {
+                                ""
+                            }
                         ))?;
+                        if let Some(acked_after) = alarm.acked_after {
+                            cb.info_idle(format_args!(
+                                "last acknowledged after {}",
+                                DurationFmt::scaled(acked_after, self.prec, cb.visual_cues())
+                            ))?;
+                        }
+                    }
+                    let acks = self.alarms.acks();
+                    if let Ok(count) = u32::try_from(acks.len()) {
+                        if count != 0 {
+                            let total: Duration = acks.iter().map(|ack| ack.after).sum();
+                            let slowest = acks.iter().map(|ack| ack.after).max().unwrap();
+                            cb.info_idle(format_args!(
+                                "{count} acknowledged this session, after {} on average and {} at most",
+                                DurationFmt::scaled(total / count, self.prec, cb.visual_cues()),
+                                DurationFmt::scaled(slowest, self.prec, cb.visual_cues())
+                            ))?;
+                        }
                     }
                 }
 
-                Command::Display => {
-                    let now = Instant::now();
-                    cb.writeln(format_args!(
-                        "{}",
-                        DurationFmt::new(self.sw.elapsed_at(now), self.prec, cb.visual_cues())
-                    ))?;
-                    let (state, color) = if self.sw.is_running() {
-                        ("running", Color::Green)
+                Command::Ack => {
+                    let now = self.clock.now();
+                    let countdown_after = self.timer.acknowledge(now);
+                    if let Some(after) = countdown_after {
+                        self.events.push(LogEvent {
+                            duration: Some(after),
+                            ..LogEvent::new(
+                                "overtime",
+                                self.sw.elapsed_at(now),
+                                self.sw.is_running(),
+                                self.clock.sys_now(),
+                            )
+                        });
+                        cb.info_change(format_args!(
+                            "acknowledged countdown after {} overtime",
+                            DurationFmt::scaled(after, self.prec, cb.visual_cues())
+                        ))?;
+                    }
+                    let acked = self.alarms.acknowledge(now);
+                    if acked.is_empty() && countdown_after.is_none() {
+                        cb.info_idle(format_args!("nothing to acknowledge"))?;
+                    }
+                    for ack in acked {
+                        self.events.push(LogEvent {
+                            duration: Some(ack.after),
+                            ..LogEvent::new(
+                                "ack",
+                                self.sw.elapsed_at(now),
+                                self.sw.is_running(),
+                                self.clock.sys_now(),
+                            )
+                        });
+                        cb.info_change(format_args!(
+                            "acknowledged alarm at {} after {}",
+                            DurationFmt::scaled(ack.at, self.prec, cb.visual_cues()),
+                            DurationFmt::scaled(ack.after, self.prec, cb.visual_cues())
+                        ))?;
+                    }
+                }
+
+                Command::Unalarm => {
+                    if self.arg.is_empty() {
+                        if cb.read_sub(
+                            &mut self.input,
+                            format_args!("remove which alarm? "),
+                            None,
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if self.arg.is_empty() {
+                        cb.info_idle(format_args!("no alarm removed"))?;
+                    } else if let Some(alarm) = parse::normalize_digits(&self.arg)
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|number| self.alarms.remove(number.checked_sub(1)?))
+                    {
+                        cb.info_change(format_args!(
+                            "removed alarm for {}",
+                            DurationFmt::scaled(alarm.at, self.prec, cb.visual_cues())
+                        ))?;
                     } else {
-                        ("stopped", Color::Yellow)
-                    };
-                    cb.writeln_color(
-                        ColorSpec::new().set_fg(Some(color)),
-                        format_args!("{state}"),
-                    )?;
-                    if self.sw.checked_elapsed_at(now).is_none() {
-                        cb.error(format_args!("elapsed time overflowing"))?;
+                        cb.error(format_args!(
+                            r#"no alarm numbered '{}' (enter "{}" to list them)"#,
+                            self.arg,
+                            Command::Alarms.short_name_display()
+                        ))?;
+                    }
+                }
+
+                Command::Sequence => {
+                    if self.arg.is_empty() {
+                        let (prec, visual_cues) = (self.prec, cb.visual_cues());
+                        if cb.read_sub_default(
+                            &mut self.input,
+                            format_args!("count down from? "),
+                            None,
+                            self.answers.default_for(command),
+                            Some(&|input| preview_dur(input, false, prec, visual_cues)),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if let Some(try_read_dur) = ReadDur::parse(&self.arg, false) {
+                        match try_read_dur {
+                            Ok(ReadDur { dur, is_neg }) => {
+                                assert!(!is_neg);
+                                self.answers.remember(command, &self.arg);
+                                let countdown = Countdown::new_started_at(dur, self.clock.now());
+                                if let Some(deadline) = countdown.deadline() {
+                                    if Self::run_sequence(&mut cb, &countdown, &*self.clock)? {
+                                        self.history.push(command, self.sw, &self.laps, self.chess);
+                                        self.sw.reset();
+                                        self.laps.clear();
+                                        self.sw.start_at(deadline);
+                                        cb.info_change(format_args!("go! started stopwatch"))?;
+                                        play(&mut cb, &self.config.sounds, Event::Expiry)?;
+                                    } else {
+                                        cb.info_idle(format_args!(
+                                            "sequence cancelled, stopwatch unchanged"
+                                        ))?;
+                                    }
+                                } else {
+                                    let dur = DurationFmt::scaled(dur, self.prec, cb.visual_cues());
+                                    cb.error(format_args!(
+                                        "can't count down from {dur}, it would end too far in the future"
+                                    ))?;
+                                }
+                            }
+                            Err(err) => err.display(&mut cb)?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no sequence started"))?;
+                    }
+                }
+
+                Command::Countdown => {
+                    // asked of `date` only if a time of day is typed
+                    let offset = OnceCell::new();
+                    if self.arg.is_empty() {
+                        let (prec, visual_cues, clock) =
+                            (self.prec, cb.visual_cues(), &*self.clock);
+                        if cb.read_sub_default(
+                            &mut self.input,
+                            format_args!("count down from? "),
+                            None,
+                            self.answers.default_for(command),
+                            Some(&|input| {
+                                if let Some(until) = daytime::strip(input, "until") {
+                                    let offset = *offset.get_or_init(export::local_offset);
+                                    preview_time_of_day(
+                                        until,
+                                        "until",
+                                        offset,
+                                        clock,
+                                        prec,
+                                        visual_cues,
+                                    )
+                                } else {
+                                    preview_dur(input, false, prec, visual_cues)
+                                }
+                            }),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if let Some(until) = daytime::strip(&self.arg, "until") {
+                        if let Some(time) = TimeOfDay::parse(until) {
+                            let offset = *offset.get_or_init(export::local_offset);
+                            let dur =
+                                time.until(TimeOfDay::local_now(self.clock.sys_now(), offset));
+                            self.answers.remember(command, &self.arg);
+                            let target = DurationFmt::scaled(dur, self.prec, cb.visual_cues());
+                            Self::start_countdown(
+                                &mut cb,
+                                &mut self.timer,
+                                dur,
+                                self.clock.now(),
+                                format_args!("{target}, until {time}"),
+                            )?;
+                        } else {
+                            cb.error(format_args!(
+                                "invalid time of day '{until}' (expected a time such as 17:30)"
+                            ))?;
+                        }
+                    } else if let Some(try_read_dur) = ReadDur::parse(&self.arg, false) {
+                        match try_read_dur {
+                            Ok(ReadDur { dur, is_neg }) => {
+                                assert!(!is_neg);
+                                self.answers.remember(command, &self.arg);
+                                let target = DurationFmt::scaled(dur, self.prec, cb.visual_cues());
+                                Self::start_countdown(
+                                    &mut cb,
+                                    &mut self.timer,
+                                    dur,
+                                    self.clock.now(),
+                                    format_args!("{target}"),
+                                )?;
+                            }
+                            Err(err) => err.display(&mut cb)?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no countdown started"))?;
+                    }
+                }
+
+                Command::Pause | Command::Resume => {
+                    let now = self.clock.now();
+                    if let Some(countdown) = self.timer.countdown_mut() {
+                        let left = DurationFmt::scaled(
+                            countdown.remaining_at(now),
+                            self.prec,
+                            cb.visual_cues(),
+                        );
+                        if command == Command::Pause {
+                            if countdown.pause_at(now) {
+                                cb.info_change(format_args!("paused countdown with {left} left"))?;
+                            } else {
+                                cb.info_idle(format_args!("countdown already paused"))?;
+                            }
+                        } else if countdown.resume_at(now) {
+                            cb.info_change(format_args!("resumed countdown with {left} left"))?;
+                        } else {
+                            cb.info_idle(format_args!("countdown already running"))?;
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no countdown to {}", command.long_name()))?;
+                    }
+                }
+
+                Command::Extend => {
+                    if self.timer.countdown().is_none() {
+                        cb.info_idle(format_args!("no countdown to extend"))?;
+                    } else {
+                        if self.arg.is_empty() {
+                            let left = self
+                                .timer
+                                .countdown()
+                                .unwrap()
+                                .remaining_at(self.clock.now());
+                            let (prec, visual_cues) = (self.prec, cb.visual_cues());
+                            if cb.read_sub_default(
+                                &mut self.input,
+                                format_args!("extend by? "),
+                                Some(format_args!(
+                                    "now {} left",
+                                    DurationFmt::scaled(left, prec, visual_cues)
+                                )),
+                                self.answers.default_for(command),
+                                Some(&|input| preview_dur(input, false, prec, visual_cues)),
+                            )? == Received::Eof
+                            {
+                                let command = cb.eof_command();
+                                return Self::quit(
+                                    &mut cb,
+                                    &self.sw,
+                                    self.clock.now(),
+                                    self.prec,
+                                    command,
+                                );
+                            }
+                            self.arg.replace_range(.., Shell::input(&self.input));
+                        }
+                        match ReadDur::parse(&self.arg, false) {
+                            Some(Ok(ReadDur { dur, is_neg })) => {
+                                assert!(!is_neg);
+                                self.answers.remember(command, &self.arg);
+                                let countdown = self.timer.countdown_mut().unwrap();
+                                if countdown.extend(dur) {
+                                    cb.info_change(format_args!(
+                                        "extended countdown by {}",
+                                        DurationFmt::scaled(dur, self.prec, cb.visual_cues())
+                                    ))?;
+                                    cb.info_idle(format_args!(
+                                        "{} left",
+                                        DurationFmt::scaled(
+                                            countdown.remaining_at(self.clock.now()),
+                                            self.prec,
+                                            cb.visual_cues()
+                                        )
+                                    ))?;
+                                } else {
+                                    cb.error(format_args!("countdown would be too long"))?;
+                                }
+                            }
+                            Some(Err(err)) => err.display(&mut cb)?,
+                            None => cb.info_idle(format_args!("countdown unchanged"))?,
+                        }
                     }
                 }
 
+                Command::Chess => {
+                    self.history.push(command, self.sw, &self.laps, self.chess);
+                    if self.chess.take().is_some() {
+                        cb.info_change(format_args!("left chess clock mode"))?;
+                        cb.info_idle(format_args!("kept stopwatch of the side to move"))?;
+                    } else {
+                        self.chess = Some(Chess::new());
+                        cb.info_change(format_args!("entered chess clock mode"))?;
+                        cb.info_idle(format_args!(
+                            r#"current stopwatch belongs to side 1 (enter "{}" to pass the turn)"#,
+                            Command::Toggle.short_name_display()
+                        ))?;
+                    }
+                }
+
+                Command::Toggle if self.sw.is_running() && self.chess.is_some() => {
+                    let chess = self.chess.as_mut().unwrap();
+                    self.history
+                        .push(command, self.sw, &self.laps, Some(*chess));
+                    chess.pass_at(&mut self.sw, self.clock.now());
+                    cb.info_change(format_args!("passed turn to side {}", chess.side_to_move()))?;
+                    play(&mut cb, &self.config.sounds, Event::Start)?;
+                }
+
                 Command::Toggle => {
-                    let now = Instant::now();
+                    let now = self.clock.now();
+                    self.history.push(command, self.sw, &self.laps, self.chess);
                     let sw_overflow = !self.sw.checked_toggle_at(now);
                     if sw_overflow {
                         self.sw.stop_at(now);
@@ -165,9 +2149,10 @@ impl<'shell> State<'shell> {
                     if self.sw.is_running() {
                         assert!(!sw_overflow);
                         cb.info_change(format_args!("started stopwatch"))?;
+                        play(&mut cb, &self.config.sounds, Event::Start)?;
                         cb.info_idle(format_args!(
                             "{} since stopped",
-                            DurationFmt::new(
+                            DurationFmt::scaled(
                                 self.since_stop.elapsed_at(now),
                                 self.prec,
                                 cb.visual_cues()
@@ -175,6 +2160,7 @@ impl<'shell> State<'shell> {
                         ))?;
                     } else {
                         cb.info_change(format_args!("stopped stopwatch"))?;
+                        play(&mut cb, &self.config.sounds, Event::Stop)?;
                         if sw_overflow {
                             cb.warn(format_args!(
                                 "new elapsed time too large, clamped to maximum"
@@ -185,66 +2171,467 @@ impl<'shell> State<'shell> {
 
                 Command::Reset => {
                     let sw_was_running = self.sw.is_running();
+                    let elapsed = self.sw.elapsed_at(self.clock.now());
+                    self.history.push(command, self.sw, &self.laps, self.chess);
                     self.sw.reset();
-                    if sw_was_running {
-                        cb.info_change(format_args!("stopped and reset stopwatch"))?;
+                    self.laps.clear();
+                    self.reset_from = None;
+                    self.banked = self.banked.saturating_add(elapsed);
+                    let was = DurationFmt::scaled(elapsed, self.prec, cb.visual_cues());
+                    if let Some(chess) = &mut self.chess {
+                        *chess = Chess::new();
+                        cb.info_change(format_args!("reset stopwatches of both sides"))?;
+                    } else if sw_was_running {
+                        self.reset_from = Some(elapsed);
+                        cb.info_change(format_args!("stopped and reset stopwatch (was {was})"))?;
                     } else {
-                        cb.info_change(format_args!("reset stopwatch"))?;
+                        self.reset_from = Some(elapsed);
+                        cb.info_change(format_args!("reset stopwatch (was {was})"))?;
+                    }
+                    play(&mut cb, &self.config.sounds, Event::Reset)?;
+                }
+
+                Command::New => {
+                    let templates = &self.config.templates;
+                    if templates.as_slice().is_empty() {
+                        cb.info_idle(format_args!(
+                            "no templates are configured (see the configuration section of the manual)"
+                        ))?;
+                    } else {
+                        if self.arg.is_empty() {
+                            let keys = templates
+                                .as_slice()
+                                .iter()
+                                .map(|template| template.key.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", "); // @alloc
+                            if cb.read_sub(
+                                &mut self.input,
+                                format_args!("template? "),
+                                Some(format_args!("{keys}")),
+                            )? == Received::Eof
+                            {
+                                let command = cb.eof_command();
+                                return Self::quit(
+                                    &mut cb,
+                                    &self.sw,
+                                    self.clock.now(),
+                                    self.prec,
+                                    command,
+                                );
+                            }
+                            self.arg.replace_range(.., Shell::input(&self.input));
+                        }
+                        if self.arg.is_empty() {
+                            cb.info_idle(format_args!("no session started"))?;
+                        } else if let Some(template) = templates.get(&self.arg) {
+                            let now = self.clock.now();
+                            self.history.push(command, self.sw, &self.laps, self.chess);
+                            self.banked = self.banked.saturating_add(self.sw.elapsed_at(now));
+                            self.sw.reset();
+                            self.sw.start_at(now);
+                            self.laps.clear();
+                            if self.chess.is_some() {
+                                self.chess = Some(Chess::new());
+                            }
+                            self.name.replace_range(.., template.name());
+                            self.tags.clone_from(&template.tags);
+                            self.alarms = Alarms::new();
+                            for at in &template.alarms {
+                                self.alarms.add(*at);
+                            }
+                            cb.info_change(format_args!(
+                                "started {} from template",
+                                template.name()
+                            ))?;
+                            if let Some(target) = template.target {
+                                self.timer.start_at(target, now);
+                                cb.info_change(format_args!(
+                                    "started countdown from {}",
+                                    DurationFmt::scaled(target, self.prec, cb.visual_cues())
+                                ))?;
+                            }
+                            if !template.alarms.is_empty() {
+                                cb.info_idle(format_args!(
+                                    r#"set {} alarm{} (enter "{}" to list them)"#,
+                                    template.alarms.len(),
+                                    if template.alarms.len() == 1 { "" } else { "s" },
+                                    Command::Alarms.short_name_display()
+                                ))?;
+                            }
+                            play(&mut cb, &self.config.sounds, Event::Start)?;
+                        } else {
+                            cb.error(format_args!("no template named '{}'", self.arg))?;
+                        }
                     }
                 }
 
                 Command::Change => {
-                    cb.read(&mut self.input, format_args!("new elapsed? "))?;
-                    if let Some(try_read_dur) = ReadDur::parse(Shell::input(&self.input), false) {
+                    let elapsed = self.sw.elapsed_at(self.clock.now());
+                    let (prec, visual_cues, clock) = (self.prec, cb.visual_cues(), &*self.clock);
+                    // asked of `date` only if a time of day is typed
+                    let offset = OnceCell::new();
+                    if cb.read_sub_default(
+                        &mut self.input,
+                        format_args!("new elapsed? "),
+                        Some(format_args!(
+                            "now {}{}",
+                            DurationFmt::scaled(elapsed, prec, visual_cues),
+                            self.answers.hint(command)
+                        )),
+                        self.answers.default_for(command),
+                        // recalled answers were valid when they were given
+                        Some(&|input| {
+                            if input.starts_with('!') {
+                                Preview::None
+                            } else if let Some(since) = daytime::strip(input, "since") {
+                                let offset = *offset.get_or_init(export::local_offset);
+                                preview_time_of_day(
+                                    since,
+                                    "since",
+                                    offset,
+                                    clock,
+                                    prec,
+                                    visual_cues,
+                                )
+                            } else {
+                                preview_dur(input, false, prec, visual_cues)
+                            }
+                        }),
+                    )? == Received::Eof
+                    {
+                        let command = cb.eof_command();
+                        return Self::quit(&mut cb, &self.sw, self.clock.now(), self.prec, command);
+                    }
+                    if let Err(err) = self.answers.recall(command, &mut self.input) {
+                        cb.error(format_args!("{err}"))?;
+                    } else if let Some(since) = daytime::strip(Shell::input(&self.input), "since") {
+                        if let Some(time) = TimeOfDay::parse(since) {
+                            let offset = *offset.get_or_init(export::local_offset);
+                            let dur =
+                                time.since(TimeOfDay::local_now(self.clock.sys_now(), offset));
+                            self.answers.remember(command, Shell::input(&self.input));
+                            self.history.push(command, self.sw, &self.laps, self.chess);
+                            self.sw.set(dur);
+                            cb.info_change(format_args!(
+                                "updated elapsed time to {}, since {time}",
+                                DurationFmt::scaled(dur, self.prec, cb.visual_cues())
+                            ))?;
+                        } else {
+                            cb.error(format_args!(
+                                "invalid time of day '{since}' (expected a time such as 09:15)"
+                            ))?;
+                        }
+                    } else if let Some(try_read_dur) =
+                        ReadDur::parse(Shell::input(&self.input), false)
+                    {
                         match try_read_dur {
                             Ok(ReadDur { dur, is_neg }) => {
                                 assert!(!is_neg);
+                                self.answers.remember(command, Shell::input(&self.input));
+                                self.history.push(command, self.sw, &self.laps, self.chess);
                                 self.sw.set(dur);
                                 cb.info_change(format_args!("updated elapsed time"))?;
                             }
-                            Err(err) => err.display(&mut cb)?,
+                            Err(err) => err.display(&mut cb)?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!("elapsed time unchanged"))?;
+                    }
+                }
+
+                Command::Offset => {
+                    let elapsed = self.sw.elapsed_at(self.clock.now());
+                    let (prec, visual_cues) = (self.prec, cb.visual_cues());
+                    if cb.read_sub_default(
+                        &mut self.input,
+                        format_args!("offset by? "),
+                        Some(format_args!(
+                            "now {}{}",
+                            DurationFmt::scaled(elapsed, prec, visual_cues),
+                            self.answers.hint(command)
+                        )),
+                        self.answers.default_for(command),
+                        // recalled answers were valid when they were given
+                        Some(&|input| {
+                            if input.starts_with('!') {
+                                Preview::None
+                            } else {
+                                preview_dur(input, true, prec, visual_cues)
+                            }
+                        }),
+                    )? == Received::Eof
+                    {
+                        let command = cb.eof_command();
+                        return Self::quit(&mut cb, &self.sw, self.clock.now(), self.prec, command);
+                    }
+                    if let Err(err) = self.answers.recall(command, &mut self.input) {
+                        cb.error(format_args!("{err}"))?;
+                    } else if let Some(try_read_dur) =
+                        ReadDur::parse(Shell::input(&self.input), true)
+                    {
+                        match try_read_dur {
+                            Ok(ReadDur { dur, is_neg }) => {
+                                self.answers.remember(command, Shell::input(&self.input));
+                                if is_neg {
+                                    let now = self.clock.now();
+                                    // time already below the floor isn't taken away
+                                    let floor = self.config.offset_floor;
+                                    let above = self.sw.elapsed_at(now).saturating_sub(floor);
+                                    let floor_fmt =
+                                        DurationFmt::scaled(floor, self.prec, visual_cues);
+                                    if above.is_zero() {
+                                        if floor.is_zero() {
+                                            cb.info_idle(format_args!(
+                                                "nothing subtracted, elapsed time already zero"
+                                            ))?;
+                                        } else {
+                                            cb.info_idle(format_args!(
+                                                "nothing subtracted, elapsed time already at or below floor of {floor_fmt}"
+                                            ))?;
+                                        }
+                                    } else {
+                                        self.history.push(command, self.sw, &self.laps, self.chess);
+                                        self.sw = self.sw.saturating_sub_at(dur.min(above), now);
+                                        cb.info_change(format_args!(
+                                            "subtracted from elapsed time"
+                                        ))?;
+                                        if dur > above && floor.is_zero() {
+                                            cb.warn(format_args!("elapsed time clamped to zero"))?;
+                                        } else if dur > above {
+                                            cb.warn(format_args!(
+                                                "elapsed time clamped to floor of {floor_fmt}"
+                                            ))?;
+                                        }
+                                    }
+                                } else {
+                                    self.history.push(command, self.sw, &self.laps, self.chess);
+                                    // a running stopwatch may overflow by the
+                                    // time since it started, not only by what
+                                    // it had before
+                                    let overflow = self
+                                        .sw
+                                        .checked_elapsed_at(self.clock.now())
+                                        .and_then(|elapsed| elapsed.checked_add(dur))
+                                        .is_none();
+                                    self.sw = self.sw.saturating_add(dur);
+                                    cb.info_change(format_args!("added to elapsed time"))?;
+                                    if overflow {
+                                        cb.warn(format_args!(
+                                            "new elapsed time too large, clamped to maximum"
+                                        ))?;
+                                    }
+                                }
+                            }
+                            Err(err) => err.display(&mut cb)?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no offset applied"))?;
+                    }
+                }
+
+                Command::Unreset => {
+                    if let Some(reset_from) = self.reset_from.take() {
+                        self.history.push(command, self.sw, &self.laps, self.chess);
+                        self.banked = self.banked.saturating_sub(reset_from);
+                        // time since the reset is kept, as if it never happened
+                        let overflow = self.sw.checked_add(reset_from).is_none();
+                        self.sw = self.sw.saturating_add(reset_from);
+                        cb.info_change(format_args!(
+                            "restored {} from before reset",
+                            DurationFmt::scaled(reset_from, self.prec, cb.visual_cues())
+                        ))?;
+                        if overflow {
+                            cb.warn(format_args!(
+                                "new elapsed time too large, clamped to maximum"
+                            ))?;
+                        }
+                    } else {
+                        cb.info_idle(format_args!("no reset to undo"))?;
+                    }
+                }
+
+                Command::Undo => {
+                    if let Some((undone, sw, laps, chess)) = self.history.pop() {
+                        let now = self.clock.now();
+                        // the total stays the same, since time taken out of
+                        // the stopwatch is banked and the other way around
+                        let (restored, current) = (sw.elapsed_at(now), self.sw.elapsed_at(now));
+                        match undone {
+                            // the time is back already
+                            Command::Reset | Command::New => {
+                                if undone == Command::Reset {
+                                    self.reset_from = None;
+                                }
+                                let unbanked = restored.saturating_sub(current);
+                                self.banked = self.banked.saturating_sub(unbanked);
+                            }
+                            Command::Unreset => {
+                                let rebanked = current.saturating_sub(restored);
+                                self.reset_from = Some(rebanked);
+                                self.banked = self.banked.saturating_add(rebanked);
+                            }
+                            _ => (),
                         }
+                        self.sw = sw;
+                        self.laps = laps;
+                        self.chess = chess;
+                        cb.info_change(format_args!("undid {}", undone.long_name()))?;
                     } else {
-                        cb.info_idle(format_args!("elapsed time unchanged"))?;
+                        cb.info_idle(format_args!("nothing to undo"))?;
                     }
                 }
 
-                Command::Offset => {
-                    cb.read(&mut self.input, format_args!("offset by? "))?;
-                    if let Some(try_read_dur) = ReadDur::parse(Shell::input(&self.input), true) {
-                        match try_read_dur {
-                            Ok(ReadDur { dur, is_neg }) => {
-                                if is_neg {
-                                    let now = Instant::now();
-                                    let underflow = dur > self.sw.elapsed_at(now);
-                                    self.sw = self.sw.saturating_sub_at(dur, now);
-                                    cb.info_change(format_args!("subtracted from elapsed time"))?;
-                                    if underflow {
-                                        cb.warn(format_args!("elapsed time clamped to zero"))?;
-                                    }
+                Command::Recover => {
+                    let was = self.sw.elapsed_at(self.clock.now());
+                    let (prec, visual_cues) = (self.prec, cb.visual_cues());
+                    // a stopwatch clamped to the maximum is stuck there too
+                    if was == Duration::MAX {
+                        if cb.read_sub(
+                            &mut self.input,
+                            format_args!("recover how? "),
+                            Some(format_args!("set, subtract, or reset")),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        let how = Shell::input(&self.input).to_lowercase(); // @alloc
+                        let is_set = match how.as_str() {
+                            "set" => Some(true),
+                            "subtract" => Some(false),
+                            _ => None,
+                        };
+                        if how.is_empty() {
+                            cb.info_idle(format_args!("elapsed time unchanged"))?;
+                        } else if how == "reset" {
+                            self.history.push(command, self.sw, &self.laps, self.chess);
+                            self.sw.reset();
+                            // restoring the time would overflow again
+                            self.reset_from = None;
+                            cb.info_change(format_args!(
+                                "recovered from {}; stopped and reset stopwatch",
+                                DurationFmt::scaled(was, prec, visual_cues)
+                            ))?;
+                        } else if let Some(is_set) = is_set {
+                            if cb.read_sub_default(
+                                &mut self.input,
+                                if is_set {
+                                    format_args!("new elapsed? ")
                                 } else {
-                                    /* TODO: not aware of anchor, so its
-                                     * possible to add to an overflowing
-                                     * stopwatch without the warning */
-                                    let overflow = self.sw.checked_add(dur).is_none();
-                                    self.sw = self.sw.saturating_add(dur);
-                                    cb.info_change(format_args!("added to elapsed time"))?;
-                                    if overflow {
-                                        cb.warn(format_args!(
-                                            "new elapsed time too large, clamped to maximum"
-                                        ))?;
+                                    format_args!("subtract how much? ")
+                                },
+                                None,
+                                None,
+                                Some(&|input| preview_dur(input, false, prec, visual_cues)),
+                            )? == Received::Eof
+                            {
+                                let command = cb.eof_command();
+                                return Self::quit(
+                                    &mut cb,
+                                    &self.sw,
+                                    self.clock.now(),
+                                    self.prec,
+                                    command,
+                                );
+                            }
+                            match ReadDur::parse(Shell::input(&self.input), false) {
+                                Some(Ok(ReadDur { dur, is_neg })) => {
+                                    assert!(!is_neg);
+                                    self.history.push(command, self.sw, &self.laps, self.chess);
+                                    if is_set {
+                                        self.sw.set(dur);
+                                    } else {
+                                        self.sw = self.sw.saturating_sub_at(dur, self.clock.now());
+                                    }
+                                    cb.info_change(format_args!(
+                                        "recovered from {}; elapsed time now {}",
+                                        DurationFmt::scaled(was, prec, visual_cues),
+                                        DurationFmt::scaled(
+                                            self.sw.elapsed_at(self.clock.now()),
+                                            prec,
+                                            visual_cues
+                                        )
+                                    ))?;
+                                    if self.sw.elapsed_at(self.clock.now()) == Duration::MAX {
+                                        cb.warn(format_args!("elapsed time still overflowing"))?;
                                     }
                                 }
+                                Some(Err(err)) => err.display(&mut cb)?,
+                                None => cb.info_idle(format_args!("elapsed time unchanged"))?,
                             }
-                            Err(err) => err.display(&mut cb)?,
+                        } else {
+                            cb.error(format_args!(
+                                "expected 'set', 'subtract', or 'reset', found '{how}'"
+                            ))?;
                         }
                     } else {
-                        cb.info_idle(format_args!("no offset applied"))?;
+                        cb.info_idle(format_args!("elapsed time isn't overflowing"))?;
+                    }
+                }
+
+                Command::Export => {
+                    if self.arg.is_empty() {
+                        if cb.read_sub(&mut self.input, format_args!("export to? "), None)?
+                            == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if self.arg.is_empty() {
+                        cb.info_idle(format_args!("nothing exported"))?;
+                    } else {
+                        Self::export(
+                            &mut cb,
+                            Path::new(&self.arg),
+                            &self.events,
+                            &self.name,
+                            &self.tags,
+                        )?;
                     }
                 }
 
+                Command::Json => {
+                    let now = self.clock.now();
+                    let elapsed = self.frozen.unwrap_or_else(|| self.sw.elapsed_at(now));
+                    let snapshot = export::Snapshot {
+                        name: &self.name,
+                        tags: &self.tags,
+                        elapsed,
+                        running: self.sw.is_running(),
+                        precision: self.prec.at(elapsed),
+                        laps: self.laps.as_slice(),
+                        overtime: self.timer.overtime_at(now),
+                        clock: self.source.name(),
+                    };
+                    cb.writeln(format_args!("{}", export::json(&snapshot)))?;
+                }
+
                 Command::Name => {
-                    cb.read(&mut self.input, format_args!("new name? "))?;
+                    if cb.read_sub(
+                        &mut self.input,
+                        format_args!("new name? "),
+                        (!self.name.is_empty())
+                            .then_some(format_args!("now {}", Escaped(&self.name))),
+                    )? == Received::Eof
+                    {
+                        let command = cb.eof_command();
+                        return Self::quit(&mut cb, &self.sw, self.clock.now(), self.prec, command);
+                    }
                     let new_name = Shell::input(&self.input);
                     if new_name == self.name {
                         cb.info_idle(format_args!("name unchanged"))?;
@@ -254,37 +2641,123 @@ impl<'shell> State<'shell> {
                         } else {
                             cb.info_change(format_args!("set name"))?;
                         }
+                        if Escaped(new_name).width() > cb.name_width() {
+                            cb.info_idle(format_args!(
+                                "note: the prompt shows only the first {} columns of it",
+                                cb.name_width()
+                            ))?;
+                        }
                         self.name.replace_range(.., new_name);
                     }
                 }
 
+                Command::Summary | Command::Groups => {
+                    let (now, sys_now) = (self.clock.now(), self.clock.sys_now());
+                    let started = self
+                        .wall
+                        .started
+                        .or_else(|| (self.persisted.as_ref()).and_then(|p| p.saved.last_started()));
+                    let current = SummaryRow {
+                        name: self.name.clone(),   // @alloc
+                        group: self.group.clone(), // @alloc
+                        current: true,
+                        running: self.sw.is_running(),
+                        elapsed: self.sw.elapsed_at(now),
+                        started,
+                        prec: None,
+                    };
+                    let path = self.persisted.as_ref().map(|p| p.path.as_path());
+                    let auto = self.config.precision_thresholds;
+                    match SummaryRow::list(current, path, (now, sys_now), auto) {
+                        Ok(rows) if command == Command::Summary => {
+                            Self::summary(&mut cb, &rows, self.prec, sys_now)?;
+                        }
+                        Ok(rows) => Self::groups(&mut cb, &rows, self.prec)?,
+                        Err(err) => cb.error(format_args!("can't list stopwatches: {err}"))?,
+                    }
+                }
+
+                Command::Group => {
+                    if self.arg.is_empty() {
+                        if cb.read_sub(
+                            &mut self.input,
+                            format_args!("group? "),
+                            (!self.group.is_empty())
+                                .then_some(format_args!("now {}", Escaped(&self.group))),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    if self.arg == self.group {
+                        cb.info_idle(format_args!("group unchanged"))?;
+                    } else if self.arg.is_empty() {
+                        cb.info_change(format_args!("removed from group"))?;
+                    } else {
+                        cb.info_change(format_args!("moved to group '{}'", Escaped(&self.arg)))?;
+                    }
+                    self.group.replace_range(.., &self.arg);
+                }
+
                 Command::Precision => {
-                    cb.read(&mut self.input, format_args!("new precision? "))?;
+                    let prec_was = self.prec;
+                    if cb.read_sub(
+                        &mut self.input,
+                        format_args!("new precision? "),
+                        Some(format_args!("now {}", self.prec)),
+                    )? == Received::Eof
+                    {
+                        let command = cb.eof_command();
+                        return Self::quit(&mut cb, &self.sw, self.clock.now(), self.prec, command);
+                    }
                     let try_prec = Shell::input(&self.input);
-                    let parsed = match try_prec.parse::<u8>() {
-                        Ok(prec) => Ok(Some(prec)),
-                        Err(err) => match err.kind() {
-                            IntErrorKind::PosOverflow => Ok(Some(u8::MAX)), // clamp overflow for better error ux
-                            IntErrorKind::Empty => Ok(None),
-                            _ => Err(err),
-                        },
-                    };
-                    match parsed {
-                        Ok(spec) => {
-                            let (new_prec, clamped) =
-                                Self::clamp_prec(spec.unwrap_or(Self::DEFAULT_PRECISION));
-                            let old_prec = mem::replace(&mut self.prec, new_prec);
-                            if clamped {
-                                cb.warn(format_args!("precision clamped to {new_prec}"))?;
-                            } else if old_prec == new_prec {
-                                cb.info_idle(format_args!("precision unchanged"))?;
-                            } else if spec.is_none() {
-                                cb.info_change(format_args!("reset precision to {new_prec}"))?;
-                            } else {
-                                cb.info_change(format_args!("updated precision"))?;
+                    if try_prec == "auto" {
+                        let new_prec = Precision::Auto(self.config.precision_thresholds);
+                        if mem::replace(&mut self.prec, new_prec) == new_prec {
+                            cb.info_idle(format_args!("precision unchanged"))?;
+                        } else {
+                            cb.info_change(format_args!("precision now scales with durations"))?;
+                        }
+                    } else {
+                        let parsed = match parse::normalize_digits(try_prec).parse::<u8>() {
+                            Ok(prec) => Ok(Some(prec)),
+                            Err(err) => match err.kind() {
+                                IntErrorKind::PosOverflow => Ok(Some(u8::MAX)), // clamp overflow for better error ux
+                                IntErrorKind::Empty => Ok(None),
+                                _ => Err(err),
+                            },
+                        };
+                        match parsed {
+                            Ok(spec) => {
+                                let (new_prec, clamped) =
+                                    Self::clamp_prec(spec.unwrap_or(Self::DEFAULT_PRECISION));
+                                let old_prec =
+                                    mem::replace(&mut self.prec, Precision::Fixed(new_prec));
+                                if clamped {
+                                    cb.warn(format_args!("precision clamped to {new_prec}"))?;
+                                } else if old_prec == Precision::Fixed(new_prec) {
+                                    cb.info_idle(format_args!("precision unchanged"))?;
+                                } else if spec.is_none() {
+                                    cb.info_change(format_args!("reset precision to {new_prec}"))?;
+                                } else {
+                                    cb.info_change(format_args!("updated precision"))?;
+                                }
                             }
+                            Err(err) => cb.error(format_args!("{err}"))?,
+                        }
+                    }
+                    if self.prec != prec_was {
+                        if let Some(persisted) = &mut self.persisted {
+                            persisted.remember_precision(self.prec)?;
                         }
-                        Err(err) => cb.error(format_args!("{err}"))?,
                     }
                 }
 
@@ -300,6 +2773,54 @@ impl<'shell> State<'shell> {
                     ))?;
                 }
 
+                Command::Theme => {
+                    if self.arg.is_empty() {
+                        let names = Theme::PRESETS.map(|(name, _)| name).join(", "); // @alloc
+                        if cb.read_sub(
+                            &mut self.input,
+                            format_args!("theme? "),
+                            Some(format_args!("config, {names}")),
+                        )? == Received::Eof
+                        {
+                            let command = cb.eof_command();
+                            return Self::quit(
+                                &mut cb,
+                                &self.sw,
+                                self.clock.now(),
+                                self.prec,
+                                command,
+                            );
+                        }
+                        self.arg.replace_range(.., Shell::input(&self.input));
+                    }
+                    // the configured theme may differ from every preset
+                    let theme = if self.arg == "config" {
+                        Some(self.config.theme)
+                    } else {
+                        Theme::preset(&self.arg)
+                    };
+                    if self.arg.is_empty() {
+                        cb.info_idle(format_args!("theme unchanged"))?;
+                    } else if let Some(theme) = theme {
+                        cb.set_theme(theme);
+                        cb.info_change(format_args!("switched to {} theme", self.arg))?;
+                    } else {
+                        cb.error(format_args!("no theme named '{}'", self.arg))?;
+                    }
+                }
+
+                Command::Mute => {
+                    let sounds = &mut self.config.sounds;
+                    sounds.muted = !sounds.muted;
+                    cb.info_change(format_args!(
+                        "sounds {}",
+                        if sounds.muted { "muted" } else { "unmuted" }
+                    ))?;
+                    if !sounds.any() {
+                        cb.info_idle(format_args!("no sounds are configured"))?;
+                    }
+                }
+
                 Command::License => {
                     cb.writeln(format_args!(
                         "copyright (C) 2022-2023 {}",
@@ -311,40 +2832,124 @@ impl<'shell> State<'shell> {
                         "{} uses the following libraries:",
                         env!("CARGO_PKG_NAME")
                     ))?;
-                    for dep in DEPENDENCIES {
-                        cb.writeln(format_args!("{dep}"))?;
+                    let width = shell::terminal_size().map_or(80, |(cols, _)| cols);
+                    cb.writeln_paged(&meta::dependency_table(width.into()))?;
+                }
+
+                Command::Version => {
+                    for line in meta::version_lines() {
+                        cb.info_idle(format_args!("{line}"))?;
                     }
                 }
 
-                Command::Quit | Command::QuitAbrupt => {
-                    let now = Instant::now();
+                Command::Info => Self::info(
+                    &mut cb,
+                    &self.wall,
+                    &self.sw,
+                    &self.since_stop,
+                    self.source,
+                    self.prec,
+                    (self.clock.now(), self.clock.sys_now()),
+                )?,
+
+                Command::Insights => {
+                    if let Some(usage) = &self.usage {
+                        match Usage::load() {
+                            Ok(mut total) => {
+                                total.merge(usage);
+                                let lines = total.insights();
+                                if lines.is_empty() {
+                                    cb.info_idle(format_args!("no usage recorded yet"))?;
+                                }
+                                for line in lines {
+                                    cb.info_idle(format_args!("{line}"))?;
+                                }
+                            }
+                            Err(err) => cb.error(format_args!("failed to read usage: {err}"))?,
+                        }
+                    } else {
+                        cb.info_idle(format_args!(
+                            "usage isn't recorded (see the configuration section of the manual)"
+                        ))?;
+                    }
+                }
+
+                Command::WhatsNew => {
+                    let releases = changelog::releases();
+                    let mut lines = Vec::new(); // @alloc
+                    if let Some(since) = &self.upgraded_from {
+                        lines.push(format!("changes since {since}:"));
+                    } else {
+                        lines.push(String::from("latest changes:"));
+                    }
+                    let mut shown = 0;
+                    for release in &releases {
+                        let newer = match &self.upgraded_from {
+                            Some(since) => release.is_newer_than(since),
+                            None => shown == 0,
+                        };
+                        if !newer || release.notable_sections().next().is_none() {
+                            continue;
+                        }
+                        shown += 1;
+                        lines.push(String::new());
+                        match release.date {
+                            Some(date) => lines.push(format!("{} ({date})", release.version)),
+                            None => lines.push(release.version.to_lowercase()),
+                        }
+                        for section in release.notable_sections() {
+                            lines.push(format!("{}:", section.heading.to_lowercase()));
+                            lines.extend(section.lines.iter().map(|line| String::from(*line)));
+                        }
+                    }
+                    if shown == 0 {
+                        cb.info_idle(format_args!("nothing new"))?;
+                    } else {
+                        cb.writeln_paged(&lines)?;
+                    }
+                }
 
-                    /* quit message comes from foot terminal
-                     * (https://codeberg.org/dnkl/foot) */
-                    cb.info_change(format_args!("goodbye"))?;
+                Command::Quit | Command::QuitAbrupt => {
                     assert!(
                         passback.is_none(),
                         "State::update is not called after Passback::Quit"
                     );
-
-                    if let Command::QuitAbrupt = command {
-                        // print how much is elapsed in case of accidental C-d
-                        cb.info_change(format_args!(
-                            "(clock reads {})",
-                            DurationFmt::new(self.sw.elapsed_at(now), self.prec, cb.visual_cues())
-                        ))?;
+                    let now = self.clock.now();
+                    if interactive && command == Command::Quit {
+                        let session = now.saturating_duration_since(self.opened);
+                        Self::summarize(&mut cb, &self.sw, &self.events, session, now, self.prec)?;
                     }
-
-                    passback = Some(Passback::Quit);
+                    passback = Self::quit(&mut cb, &self.sw, now, self.prec, command)?;
                 }
             },
 
+            Err(line) if line.starts_with(':') => {
+                let before = (self.sw, self.chess);
+                match plumbing::run(line, &mut self.sw, self.clock.now()) {
+                    Ok((response, change)) => {
+                        if let Some(change) = change {
+                            let (sw, chess) = before;
+                            self.history.push(change.command, sw, &self.laps, chess);
+                            executed = Some(change.command);
+                        }
+                        cb.writeln(format_args!("{response}"))?;
+                    }
+                    Err(err) => cb.error(format_args!("{err}"))?,
+                }
+            }
+
             Err(unk) => {
                 cb.error(format_args!(r#"unknown command (try "h" for help)"#))?;
 
-                // try to find similarly named command and present it to the user
+                // try to find similarly named commands and present them to the user
                 if UnicodeWidthStr::width(unk) > 1 {
-                    let (similarity, similar_cmd) = Command::iter()
+                    let threshold = self
+                        .config
+                        .suggest_threshold
+                        .map_or(Self::COMMAND_SUGGEST_SIMILAR_THRESHOLD, |percent| {
+                            f64::from(percent) / 100.0
+                        });
+                    let mut similar = Command::iter()
                         .iter()
                         .map(|cmd| {
                             (
@@ -352,25 +2957,84 @@ impl<'shell> State<'shell> {
                                 cmd,
                             )
                         })
-                        .reduce(|(mut most_similar, mut closest_cmd), (similarity, cmd)| {
-                            if similarity > most_similar {
-                                most_similar = similarity;
-                                closest_cmd = cmd;
-                            }
-                            (most_similar, closest_cmd)
-                        })
-                        .expect("there is at least 1 command");
+                        .filter(|(similarity, _)| *similarity >= threshold)
+                        .collect::<Vec<_>>(); // @alloc
 
-                    if similarity >= Self::COMMAND_SUGGEST_SIMILAR_THRESHOLD {
-                        cb.info_idle(format_args!(
-                            "note: the '{}' command has a similar name",
-                            similar_cmd.long_name()
-                        ))?;
+                    // most similar first, keeping the listed order among ties
+                    similar.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+                    similar.truncate(Self::COMMAND_SUGGEST_COUNT);
+
+                    match similar.as_slice() {
+                        [] => (),
+                        [(_, cmd)] => cb.info_idle(format_args!(
+                            "note: the '{}' command has a similar name ({}, {})",
+                            cmd.long_name(),
+                            cmd.short_name_display(),
+                            cmd.description()
+                        ))?,
+                        _ => {
+                            cb.info_idle(format_args!("note: these commands have similar names:"))?;
+                            for (_, cmd) in similar {
+                                cb.info_idle(format_args!(
+                                    "  {} ({}, {})",
+                                    cmd.long_name(),
+                                    cmd.short_name_display(),
+                                    cmd.description()
+                                ))?;
+                            }
+                        }
                     }
                 }
             }
         }
 
+        if let Some(command) = executed {
+            let after = (self.sw, &self.laps, self.chess);
+            if let Some(event) = Self::log_change(
+                &mut self.events,
+                command,
+                before,
+                after,
+                (self.clock.now(), self.clock.sys_now()),
+            ) {
+                hook(&mut cb, &self.config.hooks, &event, &self.name)?;
+            }
+        }
+
+        if let (Some(usage), Some(command)) = (&mut self.usage, ran) {
+            let arg = match command {
+                // their answers are only kept in the input
+                Command::Change | Command::Offset if before.0 != self.sw => {
+                    Shell::input(&self.input)
+                }
+                Command::Change | Command::Offset => "",
+                _ => self.arg.as_str(),
+            };
+            usage.record(&self.typed, arg);
+        }
+
+        let (sw, _, chess) = before;
+        if sticky && (sw, chess) != (self.sw, self.chess) {
+            Self::display(
+                &mut cb,
+                &self.sw,
+                self.chess.as_ref(),
+                self.frozen,
+                &self.timer,
+                self.prec,
+                self.clock.now(),
+            )?;
+        }
+
+        if self.sw.is_running() != sw.is_running() {
+            let sys_now = self.clock.sys_now();
+            if self.sw.is_running() {
+                self.wall.started = Some(sys_now);
+            } else {
+                self.wall.stopped = Some(sys_now);
+            }
+        }
+
         // sw and since_stop have mutually exclusive state
         if self.sw.is_running() {
             self.since_stop.reset();
@@ -380,15 +3044,433 @@ impl<'shell> State<'shell> {
         }
         assert_ne!(self.sw.is_running(), self.since_stop.is_running());
 
+        self.store(false)?;
+        self.update_status();
+
         Ok(passback)
     }
 }
 
+/// Announces the alarms which the elapsed time has passed and the countdown
+/// if it expired, since they were last checked.
+#[allow(clippy::too_many_arguments)]
+fn alert_due(
+    cb: &mut CmdBuf<'_>,
+    events: &mut Events,
+    alarms: &mut Alarms,
+    timer: &mut Timer,
+    sw: &Sw,
+    name: &str,
+    config: &Config,
+    prec: Precision,
+    (now, sys_now): (Instant, SystemTime),
+) -> io::Result<()> {
+    let expired = timer.check(now);
+    if let Some(countdown) = expired {
+        let event = LogEvent::new("expiry", sw.elapsed_at(now), sw.is_running(), sys_now);
+        events.push(event);
+        cb.alert(format_args!(
+            "countdown of {} expired",
+            DurationFmt::scaled(countdown.target, prec, cb.visual_cues())
+        ))?;
+        play(cb, &config.sounds, Event::Expiry)?;
+        hook(cb, &config.hooks, &event, name)?;
+    }
+    let fired = alarms.check(sw, now);
+    for alarm in &fired {
+        let event = LogEvent::new("alarm", alarm.at, sw.is_running(), sys_now);
+        events.push(event);
+        cb.alert(format_args!(
+            "alarm: {} elapsed",
+            DurationFmt::scaled(alarm.at, prec, cb.visual_cues())
+        ))?;
+        play(cb, &config.sounds, Event::Alarm)?;
+        hook(cb, &config.hooks, &event, name)?;
+    }
+    if expired.is_some() || !fired.is_empty() {
+        cb.info_idle(format_args!(
+            r#"enter "{}" to acknowledge"#,
+            Command::Ack.short_name_display()
+        ))?;
+    }
+    Ok(())
+}
+
+/// Previews the duration which `input` parses as, for reading it live.
+pub fn preview_dur(input: &str, allow_neg: bool, prec: Precision, visual_cues: bool) -> Preview {
+    match ReadDur::parse(Shell::input(input), allow_neg) {
+        None => Preview::None,
+        Some(Ok(ReadDur { dur, is_neg })) => Preview::Valid(format!(
+            "= {}{}",
+            if is_neg { "-" } else { "" },
+            DurationFmt::scaled(dur, prec, visual_cues)
+        )), // @alloc
+        Some(Err(err)) => Preview::Invalid(err.to_string()), // @alloc
+    }
+}
+
+/// Previews the duration `since` or `until` the time of day `time`, as `word`
+/// says, given the offset of local time from UTC.
+fn preview_time_of_day(
+    time: &str,
+    word: &str,
+    offset: i64,
+    clock: &dyn Clock,
+    prec: Precision,
+    visual_cues: bool,
+) -> Preview {
+    match TimeOfDay::parse(time) {
+        Some(time) => {
+            let now = TimeOfDay::local_now(clock.sys_now(), offset);
+            let dur = if word == "until" {
+                time.until(now)
+            } else {
+                time.since(now)
+            };
+            Preview::Valid(format!(
+                "= {} {word} {time}",
+                DurationFmt::scaled(dur, prec, visual_cues)
+            )) // @alloc
+        }
+        None if time.is_empty() => Preview::None,
+        None => Preview::Invalid("expected a time such as 09:15".to_string()), // @alloc
+    }
+}
+
+/// Plays the sound of `event`, warning if the player can't be started.
+fn play(cb: &mut CmdBuf<'_>, sounds: &Sounds, event: Event) -> io::Result<()> {
+    if let Err(err) = sounds.play(event) {
+        cb.warn(format_args!("failed to play {} sound: {err}", event.name()))?;
+    }
+    Ok(())
+}
+
+/// Runs the hook of the event which was just recorded, if it has one,
+/// warning if the command can't be started.
+fn hook(cb: &mut CmdBuf<'_>, hooks: &Hooks, event: &LogEvent, name: &str) -> io::Result<()> {
+    let Some(kind) = Event::from_name(event.name) else {
+        return Ok(());
+    };
+    if let Err(err) = hooks.run(kind, name, event.elapsed) {
+        cb.warn(format_args!("failed to run {} hook: {err}", kind.name()))?;
+    }
+    Ok(())
+}
+
+/// Reports that the shell lost its terminal while the stopwatch was last in
+/// use, and clears the marker.
+fn report_disconnected(
+    cb: &mut CmdBuf<'_>,
+    saved: &mut Saved,
+    sys_now: SystemTime,
+    prec: Precision,
+) -> io::Result<()> {
+    if let Some(disconnected) = saved.disconnected.take() {
+        let ago = sys_now.duration_since(disconnected).unwrap_or_default();
+        cb.warn(format_args!(
+            "last session lost its terminal {} ago, so the stopwatch was stopped at {}",
+            DurationFmt::new(ago, 0, cb.visual_cues()),
+            DurationFmt::scaled(saved.sw.elapsed, prec, cb.visual_cues())
+        ))?;
+    }
+    Ok(())
+}
+
+/// Summarizes the changes made to a state file written by an older version.
+pub fn report_migrated(cb: &mut CmdBuf<'_>, migrated: &[String]) -> io::Result<()> {
+    if !migrated.is_empty() {
+        cb.warn(format_args!(
+            "migrated state file from an older version of {}:",
+            env!("CARGO_PKG_NAME")
+        ))?;
+        for note in migrated {
+            cb.warn(format_args!("  {note}"))?;
+        }
+    }
+    Ok(())
+}
+
+impl State<'_> {
+    /// Lists the commands by category, in columns of their short names, long
+    /// names, descriptions, and what may follow them. Without visual cues,
+    /// each is described in a sentence instead.
+    fn help(cb: &mut CmdBuf<'_>) -> io::Result<()> {
+        let width = |cmds: &mut dyn Iterator<Item = &str>| {
+            cmds.map(UnicodeWidthStr::width).max().unwrap_or_default()
+        };
+        let cmds = Command::iter();
+        let short_width = width(&mut cmds.iter().map(|cmd| cmd.short_name_display()));
+        let long_width = width(&mut cmds.iter().map(|cmd| cmd.long_name()));
+        let desc_width = width(&mut cmds.iter().map(|cmd| cmd.description()));
+        let arg_color = ColorSpec::new().set_dimmed(true).clone();
+        for (idx, category) in Category::ALL.into_iter().enumerate() {
+            if idx != 0 {
+                cb.writeln(format_args!(""))?;
+            }
+            cb.heading(format_args!("{}", category.name()))?;
+            for cmd in cmds.iter().filter(|cmd| cmd.category() == category) {
+                let (short, long, desc) =
+                    (cmd.short_name_display(), cmd.long_name(), cmd.description());
+                if !cb.visual_cues() {
+                    match cmd.argument() {
+                        Some(arg) => cb.writeln(format_args!(
+                            "{long} or {short}. {desc}. May be followed by {arg}."
+                        ))?,
+                        None => cb.writeln(format_args!("{long} or {short}. {desc}."))?,
+                    }
+                    continue;
+                }
+                let short_pad = short_width - UnicodeWidthStr::width(short);
+                let long_pad = long_width - UnicodeWidthStr::width(long);
+                let line =
+                    format_args!("  {short}{:short_pad$}  {long}{:long_pad$}  {desc}", "", "");
+                if let Some(arg) = cmd.argument() {
+                    let desc_pad = desc_width - UnicodeWidthStr::width(desc);
+                    cb.write(format_args!("{line}{:desc_pad$}  ", ""))?;
+                    cb.writeln_color(&arg_color, format_args!("{arg}"))?;
+                } else {
+                    cb.writeln(line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts down in the foreground, printing each second and sounding the
+    /// bell for the last three. Returns whether the countdown finished, rather
+    /// than being cancelled with a key.
+    fn run_sequence(
+        cb: &mut CmdBuf<'_>,
+        countdown: &Countdown,
+        clock: &dyn Clock,
+    ) -> io::Result<bool> {
+        const BEEPS: u64 = 3;
+
+        let mut shown = None;
+        cb.until_key(&mut |cb| {
+            let remaining = countdown.remaining_at(clock.now());
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+
+            // whole seconds, rounded up so that "1" is shown during the last second
+            let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() != 0);
+            if shown != Some(secs) {
+                shown = Some(secs);
+                if secs <= BEEPS {
+                    cb.alert(format_args!("{secs}"))?;
+                } else {
+                    cb.info_idle(format_args!("{secs}"))?;
+                }
+                cb.flush()?;
+            }
+
+            // sleep until the next whole second
+            clock.sleep(remaining.saturating_sub(Duration::from_secs(secs - 1)));
+            Ok(false)
+        })
+    }
+
+    /// Starts counting down from `dur`, which is described as `target`.
+    fn start_countdown(
+        cb: &mut CmdBuf<'_>,
+        timer: &mut Timer,
+        dur: Duration,
+        now: Instant,
+        target: fmt::Arguments,
+    ) -> io::Result<()> {
+        if timer.start_at(dur, now).is_some() {
+            cb.info_change(format_args!("replaced countdown with one from {target}"))
+        } else {
+            cb.info_change(format_args!("started countdown from {target}"))
+        }
+    }
+
+    /// Sums up the session, which lasted `session`, before quitting.
+    fn summarize(
+        cb: &mut CmdBuf<'_>,
+        sw: &Sw,
+        events: &Events,
+        session: Duration,
+        now: Instant,
+        prec: Precision,
+    ) -> io::Result<()> {
+        let tally = events.tally(0);
+        let count = |name| {
+            tally
+                .iter()
+                .find(|(tallied, _)| *tallied == name)
+                .map_or(0, |(_, count)| *count)
+        };
+        let plural = |count| if count == 1 { "" } else { "s" };
+        let visual_cues = cb.visual_cues();
+        cb.info_idle(format_args!(
+            "elapsed time {} over a session of {}",
+            DurationFmt::scaled(sw.elapsed_at(now), prec, visual_cues),
+            DurationFmt::scaled(session, prec, visual_cues)
+        ))?;
+        let (starts, stops, laps) = (count("start"), count("stop"), count("lap"));
+        cb.info_idle(format_args!(
+            "{starts} start{}, {stops} stop{}, {laps} lap{}",
+            plural(starts),
+            plural(stops),
+            plural(laps)
+        ))
+    }
+
+    fn quit(
+        cb: &mut CmdBuf<'_>,
+        sw: &Sw,
+        now: Instant,
+        prec: Precision,
+        command: Command,
+    ) -> io::Result<Option<Passback>> {
+        if cb.hung_up() {
+            // there's nobody left to say goodbye to
+            return Ok(Some(Passback::Disconnected));
+        }
+
+        /* quit message comes from foot terminal
+         * (https://codeberg.org/dnkl/foot) */
+        cb.info_change(format_args!("goodbye"))?;
+
+        if let Command::QuitAbrupt = command {
+            // print how much is elapsed in case of accidental C-d
+            cb.info_change(format_args!(
+                "(clock reads {})",
+                DurationFmt::scaled(sw.elapsed_at(now), prec, cb.visual_cues())
+            ))?;
+        }
+
+        Ok(Some(Passback::Quit))
+    }
+}
+
+/// A mark, along with how far it is from the one before it and from the
+/// elapsed time `now`.
+struct MarkFmt<'a> {
+    mark: &'a Mark,
+    previous: Option<&'a Mark>,
+    now: Duration,
+    prec: Precision,
+    visual_cues: bool,
+}
+
+impl fmt::Display for MarkFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fmt = |dur| DurationFmt::scaled(dur, self.prec, self.visual_cues);
+        write!(f, "{}: {}", Escaped(&self.mark.name), fmt(self.mark.at))?;
+        if let Some(previous) = self.previous {
+            write!(
+                f,
+                " (+{} after {})",
+                fmt(self.mark.at.saturating_sub(previous.at)),
+                Escaped(&previous.name)
+            )?;
+        }
+        // the elapsed time may have been taken back below the mark
+        match self.now.checked_sub(self.mark.at) {
+            Some(ago) => write!(f, ", {} ago", fmt(ago)),
+            None => write!(f, ", {} ahead", fmt(self.mark.at.saturating_sub(self.now))),
+        }
+    }
+}
+
+/// When the laps are predicted to finish.
+struct PredictionFmt {
+    prediction: Prediction,
+    prec: Precision,
+    visual_cues: bool,
+}
+
+impl PredictionFmt {
+    const fn new(prediction: Prediction, prec: Precision, visual_cues: bool) -> Self {
+        Self {
+            prediction,
+            prec,
+            visual_cues,
+        }
+    }
+}
+
+impl fmt::Display for PredictionFmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let finish = DurationFmt::scaled(self.prediction.finish, self.prec, self.visual_cues);
+        match self.prediction.target {
+            Target::Laps(count) => write!(f, "projected finish {finish} after {count} laps"),
+            Target::Total(_) => write!(f, "projected {:.1} laps by {finish}", self.prediction.laps),
+        }
+    }
+}
+
+/// A recorded lap, numbered from one, with its label padded to
+/// `label_width` columns.
+struct LapFmt<'a> {
+    number: usize,
+    lap: &'a Lap,
+    label_width: usize,
+    prec: Precision,
+    visual_cues: bool,
+}
+
+impl<'a> LapFmt<'a> {
+    const fn new(
+        number: usize,
+        lap: &'a Lap,
+        label_width: usize,
+        prec: Precision,
+        visual_cues: bool,
+    ) -> Self {
+        Self {
+            number,
+            lap,
+            label_width,
+            prec,
+            visual_cues,
+        }
+    }
+}
+
+impl fmt::Display for LapFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "lap {}: ", self.number)?;
+        if self.label_width != 0 {
+            let label = Escaped(self.lap.label.as_deref().unwrap_or_default());
+            let pad = self.label_width.saturating_sub(label.width());
+            write!(f, "{label}{:pad$}  ", "")?;
+        }
+        write!(
+            f,
+            "{} (split {})",
+            DurationFmt::scaled(self.lap.time, self.prec, self.visual_cues),
+            DurationFmt::scaled(self.lap.split, self.prec, self.visual_cues)
+        )?;
+        if let Some(value) = self.lap.value {
+            write!(f, ", value {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How the elapsed time is written where it's displayed, as configured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisplayStyle {
+    pub rounding: Rounding,
+    /// Whether the hours are left out while there are none, as in `04:05.6`.
+    pub hide_zero_hours: bool,
+    /// Separator between groups of three digits in the number of days, if
+    /// they're grouped.
+    pub separator: Option<char>,
+    /// Language of the units when the time is written out in words.
+    pub locale: Locale,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct DurationFmt {
+pub struct DurationFmt {
     dur: Duration,
     prec: u8, // <= crate::MAX_NANOS_CHARS
     visual_cues: bool,
+    style: DisplayStyle,
 }
 
 impl DurationFmt {
@@ -399,21 +3481,32 @@ impl DurationFmt {
             dur,
             prec,
             visual_cues,
+            style: DisplayStyle {
+                rounding: Rounding::Truncate,
+                hide_zero_hours: false,
+                separator: None,
+                locale: Locale::English,
+            },
         }
     }
+
+    /// Writes the duration as `style` says, rather than in full and
+    /// truncated.
+    #[must_use]
+    pub const fn styled(mut self, style: DisplayStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Formats `dur` with as many subsecond digits as `prec` calls for.
+    #[must_use]
+    pub const fn scaled(dur: Duration, prec: Precision, visual_cues: bool) -> Self {
+        Self::new(dur, prec.at(dur), visual_cues)
+    }
 }
 
 impl fmt::Display for DurationFmt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result where {
-        fn plural(len: impl Into<u64>) -> &'static str {
-            let len: u64 = len.into();
-            if len == 1 {
-                ""
-            } else {
-                "s"
-            }
-        }
-
         fn subsecs(f: &mut impl fmt::Write, fmt: &DurationFmt) -> fmt::Result {
             if fmt.prec != 0 {
                 let nanos = fmt.dur.subsec_nanos();
@@ -427,29 +3520,57 @@ impl fmt::Display for DurationFmt {
             Ok(())
         }
 
-        let total_secs = self.dur.as_secs();
+        /// Writes `n` with its digits grouped in threes by `separator`.
+        fn grouped(f: &mut impl fmt::Write, n: u64, separator: Option<char>) -> fmt::Result {
+            let digits = n.to_string(); // @alloc
+            let Some(separator) = separator else {
+                return f.write_str(&digits);
+            };
+            for (idx, digit) in digits.chars().enumerate() {
+                if idx != 0 && (digits.len() - idx) % 3 == 0 {
+                    f.write_char(separator)?;
+                }
+                f.write_char(digit)?;
+            }
+            Ok(())
+        }
+
+        let dur = self.style.rounding.apply(self.dur, self.prec);
+        let fmt = &Self { dur, ..*self };
+        let total_secs = dur.as_secs();
         let total_mins = total_secs / 60;
+        let total_hours = total_mins / 60;
         let secs = total_secs % 60;
         let mins = total_mins % 60;
-        let hours = total_mins / 60;
+        let hours = total_hours % 24;
+        let days = total_hours / 24;
         if self.visual_cues {
             let pad_zero = 2;
-            write!(f, "{hours:0pad_zero$}:{mins:0pad_zero$}:{secs:0pad_zero$}")?;
-            subsecs(f, self)?;
+            if days != 0 {
+                grouped(f, days, self.style.separator)?;
+                write!(f, ":")?;
+            }
+            if days != 0 || hours != 0 || !self.style.hide_zero_hours {
+                write!(f, "{hours:0pad_zero$}:")?;
+            }
+            write!(f, "{mins:0pad_zero$}:{secs:0pad_zero$}")?;
+            subsecs(f, fmt)?;
         } else {
+            let locale = self.style.locale;
+            if days != 0 {
+                grouped(f, days, self.style.separator)?;
+                write!(f, " {}, ", locale.unit(Unit::Day, days, false))?;
+            }
             if hours != 0 {
-                write!(f, "{hours} hour{}, ", plural(hours))?;
+                write!(f, "{hours} {}, ", locale.unit(Unit::Hour, hours, false))?;
             }
             if mins != 0 {
-                write!(f, "{mins} minute{}, ", plural(mins))?;
+                write!(f, "{mins} {}, ", locale.unit(Unit::Minute, mins, false))?;
             }
             write!(f, "{secs}")?;
-            subsecs(f, self)?;
-            write!(
-                f,
-                " second{}",
-                if self.prec == 0 { plural(secs) } else { "s" }
-            )?;
+            subsecs(f, fmt)?;
+            let unit = locale.unit(Unit::Second, secs, self.prec != 0);
+            write!(f, " {unit}")?;
         }
         Ok(())
     }
@@ -0,0 +1,139 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use libsw_core::Sw;
+
+use core::fmt::Write as _;
+use core::time::Duration;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Instant;
+
+use crate::state::DurationFmt;
+
+/// Something which is kept up to date with the elapsed time, to the second.
+pub enum Subscriber {
+    /// File or FIFO which is rewritten with each update, for status bars to
+    /// poll.
+    File(PathBuf),
+    /// Stream such as a socket, to which each update is written as a line. It
+    /// stops being fed once writing to it fails.
+    Stream(Box<dyn Write + Send>),
+}
+
+enum Message {
+    Update(Sw),
+    Subscribe(Subscriber),
+}
+
+/// Background thread which feeds the elapsed time to every subscriber, so
+/// that they share one wakeup a second however many there are. It can be
+/// cloned to subscribe from other threads.
+#[derive(Clone)]
+pub struct Broadcast {
+    messages: Sender<Message>,
+}
+
+impl Broadcast {
+    /// Starts broadcasting, with no subscribers. An update is sent whenever
+    /// the elapsed time changes, to the second.
+    ///
+    /// The thread isn't joined, and exits once every clone of `self` is
+    /// dropped.
+    pub fn spawn(sw: Sw) -> Self {
+        let (messages, pending) = mpsc::channel();
+        thread::spawn(move || {
+            let mut sw = sw;
+            let mut feeds: Vec<Sender<String>> = Vec::new(); // @alloc
+            let mut written = String::new(); // @alloc
+            let mut text = String::new(); // @alloc
+            loop {
+                let now = Instant::now();
+                let elapsed = sw.elapsed_at(now);
+                text.clear();
+                _ = writeln!(text, "{}", DurationFmt::new(elapsed, 0, true));
+                if text != written {
+                    written.replace_range(.., &text);
+                    feeds.retain(|feed| feed.send(written.clone()).is_ok()); // @alloc
+                }
+
+                let received = if sw.is_running() {
+                    // wake when the next second has elapsed
+                    let until_next = 1_000_000_000 - elapsed.subsec_nanos();
+                    pending.recv_timeout(Duration::from_nanos(until_next.into()))
+                } else {
+                    pending.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                };
+                match received {
+                    Ok(Message::Update(new)) => sw = new,
+                    Ok(Message::Subscribe(subscriber)) => {
+                        let feed = Self::feed(subscriber);
+                        if feed.send(written.clone()).is_ok() {
+                            feeds.push(feed); // @alloc
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        }); // @alloc
+        Self { messages }
+    }
+
+    /// Tells the subscribers about changes to the stopwatch.
+    pub fn update(&self, sw: Sw) {
+        _ = self.messages.send(Message::Update(sw));
+    }
+
+    /// Starts feeding `subscriber`, beginning with the current elapsed time.
+    pub fn subscribe(&self, subscriber: Subscriber) {
+        _ = self.messages.send(Message::Subscribe(subscriber));
+    }
+
+    /// Writes to `subscriber` on a thread of its own, since writing to a FIFO
+    /// blocks until someone reads it, and returns where to send updates.
+    /// Updates which pile up meanwhile are skipped, save for the latest.
+    fn feed(subscriber: Subscriber) -> Sender<String> {
+        let (feed, texts) = mpsc::channel::<String>();
+        thread::spawn(move || match subscriber {
+            Subscriber::File(path) => {
+                while let Ok(text) = texts.recv() {
+                    let text = texts.try_iter().last().unwrap_or(text);
+                    // errors are ignored, since there's nowhere to report them
+                    _ = Self::write(&path, &text);
+                }
+            }
+            Subscriber::Stream(mut stream) => {
+                while let Ok(text) = texts.recv() {
+                    let text = texts.try_iter().last().unwrap_or(text);
+                    if stream
+                        .write_all(text.as_bytes())
+                        .and_then(|()| stream.flush())
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        }); // @alloc
+        feed
+    }
+
+    fn write(path: &Path, text: &str) -> io::Result<()> {
+        let is_file = fs::metadata(path).map_or(true, |meta| meta.is_file());
+        if is_file {
+            // write then rename, so that a reader never sees a partial write
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp, text)?;
+            fs::rename(&tmp, path)
+        } else {
+            // FIFOs and such can't be renamed over, so write to them directly
+            let mut file = OpenOptions::new().write(true).open(path)?;
+            file.write_all(text.as_bytes())
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use core::time::Duration;
+
+/// Setup of a recurring session, such as a meeting, which the `new` command
+/// starts from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Template {
+    /// Name the template is started by.
+    pub key: String,
+    /// Name given to the stopwatch, if other than `key`.
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    /// Length of the session, which is counted down from once it starts.
+    pub target: Option<Duration>,
+    /// Elapsed times at which alarms go off.
+    pub alarms: Vec<Duration>,
+}
+
+impl Template {
+    /// Fields which can be set in the configuration file.
+    pub const FIELDS: [&'static str; 4] = ["name", "tags", "target", "alarms"];
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.key)
+    }
+}
+
+/// Templates defined in the configuration file, in the order they first
+/// appear.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Templates(Vec<Template>);
+
+impl Templates {
+    pub fn as_slice(&self) -> &[Template] {
+        &self.0
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Template> {
+        self.0.iter().find(|template| template.key == key)
+    }
+
+    /// Returns the template called `key`, adding an empty one if there isn't
+    /// one yet.
+    pub fn entry(&mut self, key: &str) -> &mut Template {
+        let idx = if let Some(idx) = self.0.iter().position(|template| template.key == key) {
+            idx
+        } else {
+            self.0.push(Template {
+                key: key.to_string(),
+                ..Template::default()
+            }); // @alloc
+            self.0.len() - 1
+        };
+        &mut self.0[idx]
+    }
+}
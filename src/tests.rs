@@ -2,6 +2,1755 @@
 // copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
 // licensed under GPL-3.0-or-later
 
+mod alarm {
+    use crate::alarm::{Ack, Alarms};
+    use crate::escalate::Escalator;
+    use core::time::Duration;
+    use libsw_core::Sw;
+    use std::time::Instant;
+
+    #[test]
+    fn check() {
+        let mut alarms = Alarms::new();
+        assert!(alarms.add(Duration::from_secs(20)));
+        assert!(alarms.add(Duration::from_secs(10)));
+        assert!(!alarms.add(Duration::from_secs(10)));
+
+        let fired = |alarms: &mut Alarms, secs| -> Vec<u64> {
+            alarms
+                .check(&Sw::with_elapsed(Duration::from_secs(secs)), Instant::now())
+                .iter()
+                .map(|alarm| alarm.at.as_secs())
+                .collect()
+        };
+        assert_eq!(fired(&mut alarms, 5), []);
+        assert_eq!(fired(&mut alarms, 15), [10]);
+        assert_eq!(fired(&mut alarms, 25), [20]);
+        assert_eq!(fired(&mut alarms, 25), []);
+        // rearmed after going back
+        assert_eq!(fired(&mut alarms, 0), []);
+        assert_eq!(fired(&mut alarms, 30), [10, 20]);
+    }
+
+    #[test]
+    fn acknowledge() {
+        let start = Instant::now();
+        let secs = |secs| start + Duration::from_secs(secs);
+        let mut alarms = Alarms::new();
+        alarms.add(Duration::from_secs(10));
+        alarms.add(Duration::from_secs(20));
+        let mut sw = Sw::new();
+        sw.start_at(start);
+        assert_eq!(alarms.due(&sw), [secs(10), secs(20)]);
+
+        // went off at 10 seconds, noticed at 14
+        assert_eq!(alarms.check(&sw, secs(14)).len(), 1);
+        assert_eq!(alarms.due(&sw), [secs(10), secs(20)]);
+        assert!(alarms.as_slice()[0].is_pending());
+        let acked = alarms.acknowledge(secs(16));
+        assert_eq!(
+            acked,
+            [Ack {
+                at: Duration::from_secs(10),
+                after: Duration::from_secs(6)
+            }]
+        );
+        assert_eq!(
+            alarms.as_slice()[0].acked_after,
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(alarms.due(&sw), [secs(20)]);
+        assert!(alarms.acknowledge(secs(17)).is_empty());
+        assert_eq!(alarms.acks().len(), 1);
+    }
+
+    #[test]
+    fn escalate() {
+        let first = Instant::now();
+        let ms = |ms| first + Duration::from_millis(ms);
+        assert_eq!(Escalator::next_ring(ms(1), first), ms(1));
+        assert_eq!(Escalator::next_ring(first, first), ms(30_000));
+        assert_eq!(Escalator::next_ring(first, ms(30_000)), ms(45_000));
+        assert_eq!(Escalator::next_ring(first, ms(50_000)), ms(52_500));
+        assert_eq!(Escalator::next_ring(first, ms(60_000)), ms(62_500));
+        assert_eq!(Escalator::next_ring(first, ms(62_500)), ms(67_500));
+    }
+}
+
+mod big {
+    use crate::big::{fit, render};
+
+    #[test]
+    fn scale() {
+        // 6 digits of 6 columns, 2 colons of 2, and 7 gaps of 1
+        assert_eq!(fit("00:00:00", 47, 7), Some(1));
+        assert_eq!(fit("00:00:00", 46, 7), None);
+        assert_eq!(fit("00:00:00", 200, 7), Some(1));
+        assert_eq!(fit("00:00:00", 200, 12), Some(2));
+        assert_eq!(fit("1h", 200, 50), None);
+        let rows = render("1:0", 1);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0], "  ██      ██████");
+        assert_eq!(rows[1], "████   ██ ██  ██");
+        assert_eq!(rows[4], "██████    ██████");
+    }
+}
+
+mod chess {
+    use crate::chess::Chess;
+    use core::time::Duration;
+    use libsw_core::Sw;
+    use std::time::Instant;
+
+    #[test]
+    fn pass() {
+        let start = Instant::now();
+        let secs = |secs| start + Duration::from_secs(secs);
+        let mut chess = Chess::new();
+        let mut sw = Sw::new();
+        sw.start_at(start);
+
+        chess.pass_at(&mut sw, secs(3));
+        assert_eq!(chess.side_to_move(), 2);
+        assert!(sw.is_running() && chess.waiting.is_stopped());
+        let [one, two] = chess.sides(&sw);
+        assert_eq!(one.elapsed_at(secs(10)), Duration::from_secs(3));
+        assert_eq!(two.elapsed_at(secs(10)), Duration::from_secs(7));
+
+        chess.pass_at(&mut sw, secs(10));
+        assert_eq!(chess.side_to_move(), 1);
+        let [one, two] = chess.sides(&sw);
+        assert_eq!(one.elapsed_at(secs(12)), Duration::from_secs(5));
+        assert_eq!(two.elapsed_at(secs(12)), Duration::from_secs(7));
+    }
+}
+
+mod changelog {
+    use crate::changelog::{compare_versions, parse};
+    use core::cmp::Ordering;
+
+    const CHANGELOG: &str = "# Changelog
+
+## [Unreleased]
+
+## [0.2.0] - 2024-01-02
+### Added
+* added a thing
+  * with details
+
+### Fixed
+* fixed a thing
+
+## [0.1.10] - 2023-05-06
+### Changed
+* changed a thing
+
+[0.2.0]: https://example.com/compare/v0.1.10...v0.2.0
+[0.1.10]: https://example.com/releases/v0.1.10
+";
+
+    #[test]
+    fn versions() {
+        assert_eq!(compare_versions("0.1.10", "0.1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("0.2", "0.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.16.0", "1.0.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn releases() {
+        let releases = parse(CHANGELOG);
+        assert_eq!(releases.len(), 3);
+        assert!(releases[0].is_unreleased());
+        assert_eq!(releases[0].notable_sections().count(), 0);
+
+        assert_eq!(releases[1].version, "0.2.0");
+        assert_eq!(releases[1].date, Some("2024-01-02"));
+        let notable: Vec<_> = releases[1].notable_sections().collect();
+        assert_eq!(notable.len(), 1);
+        assert_eq!(notable[0].heading, "Added");
+        assert_eq!(notable[0].lines, ["* added a thing", "  * with details"]);
+
+        assert_eq!(releases[2].sections[0].lines, ["* changed a thing"]);
+
+        assert!(releases[1].is_newer_than("0.1.10"));
+        assert!(!releases[2].is_newer_than("0.1.10"));
+        assert!(releases[0].is_newer_than("0.2.0"));
+    }
+}
+
+mod clock {
+    use crate::clock::{Jump, Source, WallAnchor};
+    use core::time::Duration;
+    use std::time::{Instant, SystemTime};
+
+    #[test]
+    fn anchor() {
+        let (now, sys_now) = (Instant::now(), SystemTime::now());
+        let secs = Duration::from_secs;
+        let mut anchor = WallAnchor::new(now, sys_now);
+        // clocks agree, to within a moment
+        let moment = Duration::from_millis(5);
+        assert_eq!(
+            anchor.check(now + secs(10), sys_now + secs(10) + moment),
+            None
+        );
+        // an hour suspended
+        assert_eq!(
+            anchor.check(now + secs(20), sys_now + secs(3620) + moment),
+            Some(Jump::Ahead(secs(3600)))
+        );
+        // wall clock set back
+        assert_eq!(
+            anchor.check(now + secs(30), sys_now + secs(3620) + moment),
+            Some(Jump::Back(secs(10)))
+        );
+        assert_eq!(
+            anchor.check(now + secs(40), sys_now + moment),
+            Some(Jump::Back(secs(3630)))
+        );
+
+        assert_eq!("wall".parse(), Ok(Source::Wall));
+        assert!("sundial".parse::<Source>().is_err());
+        assert_eq!(Source::Monotonic.resolve(), (Source::Monotonic, false));
+    }
+}
+
+mod config {
+    use crate::config::Config;
+    use crate::sound::Event;
+    use core::time::Duration;
+    use std::path::{Path, PathBuf};
+    use termcolor::Color;
+
+    #[test]
+    fn parse() {
+        let (config, warnings) = Config::parse(
+            "# comment\n\nsound.player = paplay\nsound.lap = /tmp/lap.wav\nsound.mute=true\nsound.bogus = x\nnonsense\n",
+            Path::new("config"),
+        );
+        assert_eq!(config.sounds.player.as_deref(), Some("paplay"));
+        assert!(config.sounds.muted);
+        assert!(config.sounds.any());
+        let mut expected = Config::default();
+        expected.sounds.player = Some("paplay".into());
+        expected.sounds.muted = true;
+        expected
+            .sounds
+            .set(Event::Lap, PathBuf::from("/tmp/lap.wav"));
+        assert_eq!(config, expected);
+        assert_eq!(
+            warnings,
+            [
+                "config:6: unknown key 'sound.bogus'",
+                "config:7: expected 'key = value', ignoring line"
+            ]
+        );
+    }
+
+    #[test]
+    fn flags() {
+        let (config, warnings) = Config::parse(
+            "prompt.defaults = true\nsound.mute = yes\nprompt.elapsed = true\ndisplay.sticky = true\nmotion.reduced = true\ninput.single_key = true\n",
+            Path::new("config"),
+        );
+        assert!(config.prompt_defaults);
+        assert!(config.prompt_elapsed);
+        assert!(config.sticky_display);
+        assert!(config.reduced_motion);
+        assert!(config.single_key);
+        assert!(!config.sounds.muted);
+        assert_eq!(
+            warnings,
+            ["config:2: expected 'true' or 'false', ignoring 'sound.mute'"]
+        );
+    }
+
+    #[test]
+    fn suggest_threshold() {
+        let (config, warnings) = Config::parse(
+            "suggest.threshold = 60\nsuggest.threshold = 0.5\nsuggest.threshold = 101\n",
+            Path::new("config"),
+        );
+        assert_eq!(config.suggest_threshold, Some(60));
+        assert_eq!(
+            warnings,
+            [
+                "config:2: expected a percentage, ignoring 'suggest.threshold'",
+                "config:3: expected a percentage, ignoring 'suggest.threshold'",
+            ]
+        );
+    }
+
+    #[test]
+    fn name_width() {
+        let (config, warnings) = Config::parse(
+            "name.max_width = 12
+name.max_width = 0
+",
+            Path::new("config"),
+        );
+        assert_eq!(config.name_width, Some(12));
+        assert_eq!(
+            warnings,
+            ["config:2: expected a number of columns, ignoring 'name.max_width'"]
+        );
+    }
+
+    #[test]
+    fn offset_floor() {
+        let (config, warnings) = Config::parse(
+            "offset.floor = 1h\noffset.floor = -5m\n",
+            Path::new("config"),
+        );
+        assert_eq!(config.offset_floor, Duration::from_secs(3600));
+        assert_eq!(
+            warnings,
+            ["config:2: invalid duration, ignoring 'offset.floor'"]
+        );
+    }
+
+    #[test]
+    fn theme() {
+        let (config, warnings) = Config::parse(
+            "theme = mono\n\
+             theme.warn = yellow\n\
+             theme.error = #ff8700\n\
+             theme.prompt = 33\n\
+             theme.info_idle = #ff87\n\
+             theme = bogus\n",
+            Path::new("config"),
+        );
+        assert_eq!(config.theme.info_change, None);
+        assert_eq!(config.theme.info_idle, None);
+        assert_eq!(config.theme.warn, Some(Color::Yellow));
+        assert_eq!(config.theme.error, Some(Color::Rgb(0xff, 0x87, 0)));
+        assert_eq!(config.theme.prompt, Some(Color::Ansi256(33)));
+        assert_eq!(
+            warnings,
+            [
+                "config:5: invalid color, ignoring 'theme.info_idle'",
+                "config:6: unknown theme 'bogus', ignoring 'theme'"
+            ]
+        );
+    }
+
+    #[test]
+    fn thresholds() {
+        let (config, warnings) = Config::parse(
+            "display.thresholds = green < 25m, yellow < 50m, red\n\
+             display.thresholds = red < 5m, green < 1m\n\
+             display.thresholds = red, green < 1m\n",
+            Path::new("config"),
+        );
+        let color_at = |mins: u64| config.thresholds.color_at(Duration::from_secs(mins * 60));
+        assert_eq!(color_at(0), Some(Color::Green));
+        assert_eq!(color_at(25), Some(Color::Yellow));
+        assert_eq!(color_at(90), Some(Color::Red));
+        assert_eq!(
+            warnings,
+            [
+                "config:2: invalid thresholds, ignoring 'display.thresholds'",
+                "config:3: invalid thresholds, ignoring 'display.thresholds'"
+            ]
+        );
+        assert_eq!(Config::default().thresholds.color_at(Duration::MAX), None);
+    }
+
+    #[test]
+    fn hooks() {
+        let (config, warnings) = Config::parse(
+            "hook.start = timew start sw
+hook.pass = x
+",
+            Path::new("config"),
+        );
+        let mut expected = Config::default();
+        expected
+            .hooks
+            .set(Event::Start, "timew start sw".to_string());
+        assert_eq!(config, expected);
+        assert_eq!(warnings, ["config:2: unknown key 'hook.pass'"]);
+    }
+
+    #[test]
+    fn templates() {
+        let (config, warnings) = Config::parse(
+            "template.standup.tags = meeting, daily\n\
+             template.standup.target = 15m\n\
+             template.standup.alarms = 5m, 10m\n\
+             template.standup.alarms = 5m, soon\n\
+             template.standup.colour = red\n\
+             template.1on1.name = one on one\n",
+            Path::new("config"),
+        );
+        let templates = config.templates.as_slice();
+        assert_eq!(templates.len(), 2);
+        let standup = config.templates.get("standup").unwrap();
+        assert_eq!(standup.name(), "standup");
+        assert_eq!(standup.tags, ["meeting", "daily"]);
+        assert_eq!(standup.target, Some(Duration::from_secs(15 * 60)));
+        assert_eq!(
+            standup.alarms,
+            [Duration::from_secs(5 * 60), Duration::from_secs(10 * 60)]
+        );
+        assert_eq!(config.templates.get("1on1").unwrap().name(), "one on one");
+        assert_eq!(
+            warnings,
+            [
+                "config:4: invalid duration, ignoring 'template.standup.alarms'",
+                "config:5: unknown key 'template.standup.colour'"
+            ]
+        );
+    }
+}
+
+mod laps {
+    use crate::laps::{self, Announce, Laps, Target};
+    use core::time::Duration;
+
+    #[test]
+    fn announce() {
+        let mut laps = Laps::new();
+        laps.announce = Announce { every: 2 };
+        laps.target = Some(Target::Laps(10));
+        laps.record(Duration::from_secs(30), None, None);
+        assert!(!laps.should_announce());
+        let lap = laps.record(Duration::from_secs(70), Some(156.0), None);
+        assert_eq!(lap.time, Duration::from_secs(40));
+        assert_eq!(lap.value, Some(156.0));
+        assert!(laps.should_announce());
+        assert_eq!(laps.average(), Some(Duration::from_secs(35)));
+        assert_eq!(laps.projected_finish(10), Some(Duration::from_secs(350)));
+    }
+
+    #[test]
+    fn edit() {
+        assert_eq!(
+            laps::parse_arg("156 uphill  "),
+            (Some(156.0), Some("uphill"))
+        );
+        assert_eq!(laps::parse_arg("first draft"), (None, Some("first draft")));
+        assert_eq!(laps::parse_arg("12"), (Some(12.0), None));
+        let mut laps = Laps::new();
+        laps.record(Duration::from_secs(30), None, Some("warm up"));
+        laps.record(Duration::from_secs(70), None, None);
+        laps.record(Duration::from_secs(90), None, None);
+        assert_eq!(laps::parse_number("3", 3), Ok(3));
+        assert!(laps::parse_number("4", 3).is_err());
+        assert!(laps::parse_number("0", 3).is_err());
+        assert_eq!(laps.relabel(1, Some("easy")).as_deref(), Some("warm up"));
+        let removed = laps.remove(2);
+        assert_eq!(removed.time, Duration::from_secs(40));
+        let laps = laps.as_slice();
+        assert_eq!(laps[0].label.as_deref(), Some("easy"));
+        assert_eq!(laps[1].split, Duration::from_secs(90));
+        assert_eq!(laps[1].time, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn stats() {
+        let mut laps = Laps::new();
+        assert_eq!(laps.stats(), None);
+        for secs in [40, 70, 90, 130] {
+            laps.record(Duration::from_secs(secs), None, None);
+        }
+        let stats = laps.stats().unwrap();
+        assert_eq!(stats.min, Duration::from_secs(20));
+        assert_eq!(stats.max, Duration::from_secs(40));
+        assert_eq!(stats.mean, Duration::from_millis(32_500));
+        assert_eq!(stats.median, Duration::from_secs(35));
+        // ties go to the first lap
+        assert_eq!((stats.fastest, stats.slowest), (2, 0));
+    }
+
+    #[test]
+    fn predict() {
+        assert_eq!("12".parse(), Ok(Target::Laps(12)));
+        assert_eq!("30m".parse(), Ok(Target::Total(Duration::from_secs(1800))));
+        assert!("0".parse::<Target>().is_err());
+        assert!("soon".parse::<Target>().is_err());
+        let mut laps = Laps::new();
+        assert_eq!(laps.predict(Target::Laps(4)), None);
+        laps.record(Duration::from_secs(100), None, None);
+        laps.record(Duration::from_secs(200), None, None);
+        let by_laps = laps.predict(Target::Laps(4)).unwrap();
+        assert_eq!(by_laps.finish, Duration::from_secs(400));
+        let by_time = laps
+            .predict(Target::Total(Duration::from_secs(250)))
+            .unwrap();
+        assert_eq!(by_time.finish, Duration::from_secs(250));
+        assert!((by_time.laps - 2.5).abs() < f64::EPSILON);
+    }
+}
+
+mod state {
+    use crate::clock::{Clock, Source};
+    use crate::config::Config;
+    use crate::persist::Saved;
+    use crate::shell::Shell;
+    use crate::state::{Passback, State};
+    use core::cell::{Cell, RefCell};
+    use core::time::Duration;
+    use libsw_core::SystemSw;
+    use std::env;
+    use std::fs;
+    use std::io::{self, Read, Write};
+    use std::process;
+    use std::rc::Rc;
+    use std::time::{Instant, SystemTime};
+    use termcolor::{ColorChoice, NoColor};
+
+    /// Clock which only moves when told to.
+    #[derive(Clone)]
+    struct ManualClock {
+        start: (Instant, SystemTime),
+        passed: Rc<Cell<Duration>>,
+    }
+
+    impl ManualClock {
+        fn new() -> Self {
+            Self {
+                start: (Instant::now(), SystemTime::now()),
+                passed: Rc::default(),
+            }
+        }
+
+        fn advance(&self, dur: Duration) {
+            self.passed.set(self.passed.get() + dur);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start.0 + self.passed.get()
+        }
+
+        fn sys_now(&self) -> SystemTime {
+            self.start.1 + self.passed.get()
+        }
+
+        fn sleep(&self, dur: Duration) {
+            self.advance(dur);
+        }
+    }
+
+    /// Shell which reads `commands` as if they were typed, discarding its
+    /// output.
+    fn attached(commands: &'static str) -> Shell {
+        let mut shell = Shell::new_batch(ColorChoice::Never, 1024, false, Box::new(io::empty()));
+        shell
+            .attach(
+                Box::new(commands.as_bytes()),
+                Box::new(NoColor::new(io::sink())),
+            )
+            .unwrap();
+        shell
+    }
+
+    /// Output which is kept to be checked afterwards.
+    #[derive(Clone, Default)]
+    struct Captured(Rc<RefCell<Vec<u8>>>);
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.borrow()).into_owned()
+        }
+    }
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Input which fails as if the terminal was hung up once `before` is read.
+    struct HungUp {
+        before: &'static [u8],
+    }
+
+    impl Read for HungUp {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.before.is_empty() {
+                Err(io::Error::from_raw_os_error(5)) // EIO
+            } else {
+                self.before.read(buf)
+            }
+        }
+    }
+
+    #[test]
+    fn manual_clock() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let mut shell = attached("s\no\n+5m\ns\nu\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start
+        clock.advance(secs(90));
+        assert_eq!(state.elapsed(), secs(90));
+        state.update().unwrap(); // offset by 5 minutes
+        assert_eq!(state.elapsed(), secs(390));
+        clock.advance(secs(30));
+        state.update().unwrap(); // stop
+        clock.advance(secs(3600));
+        assert_eq!(state.elapsed(), secs(420));
+        // undoing the stop counts the time since, as if it never happened
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(4020));
+    }
+
+    #[test]
+    fn toggle() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let started = clock.sys_now();
+        let mut shell = attached("s\ns\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start
+        clock.advance(secs(90));
+        state.update().unwrap(); // stop
+        clock.advance(secs(60));
+        assert_eq!(state.elapsed(), secs(90));
+        let events: Vec<_> = state
+            .events()
+            .as_slice()
+            .iter()
+            .map(|event| (event.name, event.wall, event.elapsed))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("start", started, secs(0)),
+                ("stop", started + secs(90), secs(90))
+            ]
+        );
+    }
+
+    #[test]
+    fn countdown() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let started = clock.sys_now();
+        let mut shell = attached("cd 5m\nk\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start countdown
+        clock.advance(secs(360));
+        // expires before acknowledging, a minute late
+        state.update().unwrap();
+        let events: Vec<_> = state
+            .events()
+            .as_slice()
+            .iter()
+            .map(|event| (event.name, event.wall, event.duration))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("expiry", started + secs(360), None),
+                ("overtime", started + secs(360), Some(secs(60)))
+            ]
+        );
+    }
+
+    #[test]
+    fn chained() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        // prompts are answered from the next line
+        let mut shell = attached("s;; o ;\n+5m\nr; u\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start
+        clock.advance(secs(30));
+        state.update().unwrap(); // offset by 5 minutes
+        assert_eq!(state.elapsed(), secs(330));
+        state.update().unwrap(); // reset
+        assert_eq!(state.elapsed(), secs(0));
+        state.update().unwrap(); // undo
+        assert_eq!(state.elapsed(), secs(330));
+    }
+
+    #[test]
+    fn eof_or_hangup() {
+        let clock = ManualClock::new();
+        let mut shell = attached("s\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+        state.update().unwrap(); // start
+
+        // ending input on purpose quits as usual
+        assert_eq!(state.update().unwrap(), Some(Passback::Quit));
+
+        let mut shell = attached("");
+        shell
+            .attach(
+                Box::new(io::BufReader::new(HungUp { before: b"s\n" })),
+                Box::new(NoColor::new(io::sink())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock),
+        );
+        state.update().unwrap(); // start
+        assert_eq!(state.update().unwrap(), Some(Passback::Disconnected));
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let output = Captured::default();
+        let mut shell = attached("");
+        shell
+            .attach(
+                Box::new(b"s\no\n+5s\n".as_slice()),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state
+            .set_elapsed(Duration::MAX.saturating_sub(secs(10)))
+            .unwrap();
+        state.update().unwrap(); // start
+        clock.advance(secs(8));
+        // only the time since starting takes it past the maximum
+        state.update().unwrap();
+        assert!(output
+            .text()
+            .contains("new elapsed time too large, clamped to maximum"));
+    }
+
+    #[test]
+    fn offset_floor() {
+        let secs = Duration::from_secs;
+        let output = Captured::default();
+        let mut shell = attached("");
+        shell
+            .attach(
+                Box::new(b"o\n-10s\no\n-60s\n".as_slice()),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let config = Config {
+            offset_floor: secs(60),
+            ..Config::default()
+        };
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            config,
+            Box::new(ManualClock::new()),
+        );
+
+        // already below the floor, so nothing is taken away
+        state.set_elapsed(secs(30)).unwrap();
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(30));
+        assert!(output.text().contains("nothing subtracted"));
+        assert!(!output.text().contains("clamped"));
+
+        state.set_elapsed(secs(90)).unwrap();
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(60));
+        assert!(output.text().contains("subtracted from elapsed time"));
+        assert!(output.text().contains("clamped to floor"));
+    }
+
+    #[test]
+    fn answers() {
+        let secs = Duration::from_secs;
+        let output = Captured::default();
+        let mut shell = attached("");
+        shell
+            .attach(
+                Box::new(
+                    b"o\n+1s\no\n+2s\no\n+3s\no\n+4s\no\n!1\no\n!4\no\n!3\no\n!\x1b[2J\n"
+                        .as_slice(),
+                ),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(ManualClock::new()),
+        );
+
+        for _ in 0..4 {
+            state.update().unwrap();
+        }
+        assert_eq!(state.elapsed(), secs(10));
+        state.update().unwrap(); // recalls the latest
+        assert_eq!(state.elapsed(), secs(14));
+        // only the last three are kept
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(14));
+        assert!(output.text().contains("no recent answer numbered '4'"));
+        // recalling an answer makes it the latest again
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(16));
+
+        // input is echoed escaped
+        state.update().unwrap();
+        let text = output.text();
+        assert!(text.contains(", !1 +2s, !2 +4s, !3 +3s"));
+        assert!(text.contains(r"no recent answer numbered '\u{1b}[2J'"));
+    }
+
+    #[test]
+    fn repeated_error() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let output = Captured::default();
+        let mut shell = attached("");
+        shell
+            .attach(
+                Box::new(b"s\n\n\n\n".as_slice()),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state
+            .set_elapsed(Duration::MAX.saturating_sub(secs(1)))
+            .unwrap();
+        state.update().unwrap(); // start
+        clock.advance(secs(2));
+        for _ in 0..3 {
+            state.update().unwrap(); // display
+        }
+        drop(state);
+        shell.finish().unwrap();
+
+        // still an error, but only written once and then counted
+        assert!(shell.errored());
+        let text = output.text();
+        assert_eq!(text.matches("error: elapsed time overflowing").count(), 2);
+        assert!(text.contains("(repeated 2 more times)"));
+    }
+
+    #[test]
+    fn sequence() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let mut shell = attached("sq 3s\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        // counting down sleeps on the clock, so it passes at once
+        state.update().unwrap();
+        assert_eq!(state.elapsed(), secs(0));
+        clock.advance(secs(10));
+        assert_eq!(state.elapsed(), secs(10));
+    }
+
+    #[test]
+    fn sequence_overflow() {
+        let clock = ManualClock::new();
+        let mut shell = attached("sq 5000000000000000h\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        // the deadline can't be represented, so nothing is started
+        state.update().unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(state.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn total() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let mut shell = attached(
+            "s
+r
+s
+r
+ur
+u
+u
+",
+        );
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start
+        clock.advance(secs(60));
+        state.update().unwrap(); // stop and reset
+        state.update().unwrap(); // start
+        clock.advance(secs(40));
+        state.update().unwrap(); // stop and reset
+        assert_eq!((state.elapsed(), state.total()), (secs(0), secs(100)));
+        state.update().unwrap(); // unreset
+        assert_eq!((state.elapsed(), state.total()), (secs(40), secs(100)));
+        // undoing either keeps the total
+        state.update().unwrap(); // undo unreset
+        assert_eq!((state.elapsed(), state.total()), (secs(0), secs(100)));
+        state.update().unwrap(); // undo reset
+        assert_eq!((state.elapsed(), state.total()), (secs(40), secs(100)));
+    }
+
+    #[test]
+    fn describe() {
+        let mut shell = attached("s\nr\n");
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(ManualClock::new()),
+        );
+
+        let mut details = String::new();
+        state.describe(&mut details);
+        assert!(details.ends_with("recent commands, oldest first:\n"));
+
+        state.update().unwrap(); // start
+        state.update().unwrap(); // reset
+        details.clear();
+        state.describe(&mut details);
+        assert!(details.ends_with("recent commands, oldest first: toggle, reset\n"));
+    }
+
+    #[test]
+    fn info_clock() {
+        let output = Captured::default();
+        let mut shell = Shell::new_batch(ColorChoice::Never, 1024, false, Box::new(io::empty()));
+        shell
+            .attach(
+                Box::new(b"i\n".as_slice()),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(ManualClock::new()),
+        );
+
+        state.set_clock(Some(Source::Wall)).unwrap();
+        state.update().unwrap();
+        assert!(output.text().ends_with("keeping time by the wall clock\n"));
+    }
+
+    #[test]
+    fn groups() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        // the other stopwatches are listed from a state directory of this
+        // test's own, which no other test lists
+        let dir = env::temp_dir().join(format!("sw-test-groups-{}", process::id()));
+        env::set_var("XDG_STATE_HOME", &dir);
+        let stored = |name: &str, group: &str, sw: SystemSw| {
+            let mut saved = Saved::new(name.to_owned());
+            saved.group = group.to_owned();
+            saved.sw = sw;
+            saved.store(&Saved::path(name).unwrap()).unwrap();
+        };
+        stored("stopped", "work", SystemSw::with_elapsed(secs(60)));
+        let started = clock.sys_now() - secs(30);
+        stored(
+            "running",
+            "work",
+            SystemSw::from_raw(secs(0), Some(started)),
+        );
+        stored("loose", "", SystemSw::with_elapsed(secs(10)));
+
+        let output = Captured::default();
+        let mut shell = Shell::new_batch(ColorChoice::Never, 1024, false, Box::new(io::empty()));
+        let commands = b"s\ng work\ngs\ng\n\ngs\n";
+        shell
+            .attach(
+                Box::new(commands.as_slice()),
+                Box::new(NoColor::new(output.clone())),
+            )
+            .unwrap();
+        let mut state = State::new(
+            &mut shell,
+            String::new(),
+            None,
+            Config::default(),
+            Box::new(clock.clone()),
+        );
+
+        state.update().unwrap(); // start
+        clock.advance(secs(20));
+        state.update().unwrap(); // group
+        assert!(output.text().ends_with("moved to group 'work'\n"));
+        // the running stopwatches count until now
+        state.update().unwrap(); // totals
+        assert!(output.text().ends_with(
+            "work:        2 minutes, 10.00 seconds over 3 stopwatches, 2 running
+(ungrouped): 10.00 seconds over 1 stopwatch, 0 running
+"
+        ));
+        state.update().unwrap(); // ungroup
+        assert!(output.text().ends_with("removed from group\n"));
+        state.update().unwrap(); // totals
+        assert!(output.text().ends_with(
+            "work:        1 minute, 50.00 seconds over 2 stopwatches, 1 running
+(ungrouped): 30.00 seconds over 2 stopwatches, 1 running
+"
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+mod shell {
+    use crate::precision::Precision;
+    use crate::shell::{LineEdit, Preview, Received};
+    use crate::state::preview_dur;
+
+    /// Types `keys` into a fresh line, returning the line and how it ended.
+    fn typed(keys: &[u8], limit: usize) -> (String, Option<Received>) {
+        let mut edit = LineEdit::default();
+        let mut input = String::new();
+        for &byte in keys {
+            if let Some(received) = edit.key(&mut input, byte, limit) {
+                return (input, Some(received));
+            }
+        }
+        (input, None)
+    }
+
+    #[test]
+    fn line_edit() {
+        let line = |text: &str| (text.to_string(), Some(Received::Line));
+        assert_eq!(typed(b"abc\r", 64), line("abc"));
+        assert_eq!(typed(b"abc\x7f\x08d\n", 64), line("ad"));
+        assert_eq!(typed(b"\x7f\n", 64), line(""));
+        // ctrl-u
+        assert_eq!(typed(b"abc\x15de\n", 64), line("de"));
+        // arrow keys and such are skipped whole
+        assert_eq!(typed(b"a\x1b[Db\x1b[1;5Cc\x1bOd\n", 64), line("abcd"));
+        // characters are assembled from their bytes, and dropped if invalid
+        assert_eq!(typed("é🪴\n".as_bytes(), 64), line("é🪴"));
+        assert_eq!(typed(b"a\xffb\n", 64), line("ab"));
+        assert_eq!(typed("ab🪴c\n".as_bytes(), 5), line("abc"));
+        // ctrl-c and ctrl-\\ abandon the line, and ctrl-d only ends an empty one
+        assert_eq!(
+            typed(b"abc\x03def", 64),
+            (String::new(), Some(Received::Cancel))
+        );
+        assert_eq!(
+            typed(b"abc\x1c", 64),
+            (String::new(), Some(Received::Cancel))
+        );
+        assert_eq!(typed(b"\x04", 64), (String::new(), Some(Received::Eof)));
+        assert_eq!(typed(b"a\x04\x01", 64), (String::from("a"), None));
+    }
+
+    #[test]
+    fn preview() {
+        let prec = Precision::Fixed(2);
+        assert_eq!(preview_dur("", false, prec, true), Preview::None);
+        assert_eq!(
+            preview_dur("1m30s", false, prec, true),
+            Preview::Valid(String::from("= 00:01:30.00"))
+        );
+        assert_eq!(
+            preview_dur("-5s", true, prec, false),
+            Preview::Valid(String::from("= -5.00 seconds"))
+        );
+        assert!(matches!(
+            preview_dur("-5s", false, prec, true),
+            Preview::Invalid(_)
+        ));
+        assert!(matches!(
+            preview_dur("5x", false, prec, true),
+            Preview::Invalid(_)
+        ));
+    }
+}
+
+mod locale {
+    use crate::locale::{Locale, Plural, Unit};
+    use crate::state::{DisplayStyle, DurationFmt};
+    use core::time::Duration;
+
+    #[test]
+    fn plurals() {
+        assert_eq!("pl_PL.UTF-8".parse(), Ok(Locale::Polish));
+        assert!("xx".parse::<Locale>().is_err());
+        assert_eq!(Locale::English.plural(1, false), Plural::One);
+        assert_eq!(Locale::English.plural(1, true), Plural::Other);
+        assert_eq!(Locale::French.plural(0, false), Plural::One);
+        assert_eq!(Locale::Russian.plural(21, false), Plural::One);
+        assert_eq!(Locale::Russian.plural(11, false), Plural::Many);
+        assert_eq!(Locale::Polish.plural(21, false), Plural::Many);
+        assert_eq!(Locale::Polish.plural(22, false), Plural::Few);
+        assert_eq!(Locale::Polish.plural(12, false), Plural::Many);
+        assert_eq!(Locale::Russian.unit(Unit::Minute, 3, false), "минуты");
+        assert_eq!(Locale::Polish.unit(Unit::Second, 5, false), "sekund");
+        assert_eq!(Locale::Polish.unit(Unit::Second, 1, true), "sekundy");
+    }
+
+    #[test]
+    fn styled() {
+        let fmt = |secs, locale| {
+            let style = DisplayStyle {
+                locale,
+                ..DisplayStyle::default()
+            };
+            DurationFmt::new(Duration::from_secs(secs), 0, false)
+                .styled(style)
+                .to_string()
+        };
+        assert_eq!(fmt(125, Locale::Polish), "2 minuty, 5 sekund");
+        assert_eq!(fmt(125, Locale::English), "2 minutes, 5 seconds");
+        // without a style, durations are written in English
+        assert_eq!(
+            DurationFmt::new(Duration::from_secs(61), 0, false).to_string(),
+            "1 minute, 1 second"
+        );
+    }
+}
+
+mod marks {
+    use crate::marks::Marks;
+    use core::time::Duration;
+
+    #[test]
+    fn set() {
+        let secs = Duration::from_secs;
+        let mut marks = Marks::new();
+        assert_eq!(marks.set("b", secs(20)), None);
+        assert_eq!(marks.set("a", secs(10)), None);
+        assert_eq!(marks.set("c", secs(30)), None);
+        // moving a mark keeps them in order
+        assert_eq!(marks.set("a", secs(40)), Some(secs(10)));
+        let names: Vec<_> = marks.as_slice().iter().map(|mark| &*mark.name).collect();
+        assert_eq!(names, ["b", "c", "a"]);
+    }
+}
+
+mod persist {
+    use crate::clock::Source;
+    use crate::persist::Saved;
+    use crate::precision::Spec;
+    use core::time::Duration;
+    use libsw_core::Sw;
+    use std::path::Path;
+    use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn round_trip() {
+        let mut saved = Saved::new(String::from("back\\slash\nnewline"));
+        saved.sw.elapsed = Duration::new(5, 6);
+        saved.scheduled = Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        saved.disconnected = Some(UNIX_EPOCH + Duration::new(1_700_000_001, 2));
+        saved.closed = Some(UNIX_EPOCH + Duration::new(1_700_000_002, 3));
+        saved.clock = Some(Source::Wall);
+        saved.precision = Some(Spec::Fixed(3));
+        saved.started = Some(UNIX_EPOCH + Duration::from_secs(1_699_999_999));
+        saved.group = String::from("project x");
+        let loaded = Saved::decode(&saved.encode(), "").unwrap();
+        assert_eq!(loaded.saved, saved);
+        assert!(loaded.migrated.is_empty());
+    }
+
+    #[test]
+    fn anchor() {
+        let now = Instant::now();
+        let sys_now = SystemTime::now();
+        let mut sw = Sw::with_elapsed(Duration::from_secs(3));
+        sw.start_at(now.checked_sub(Duration::from_secs(2)).unwrap());
+
+        let mut saved = Saved::new(String::new());
+        saved.set_sw(&sw, now, sys_now);
+        assert_eq!(saved.sw.elapsed_at(sys_now), Duration::from_secs(5));
+        let later = Duration::from_secs(10);
+        assert_eq!(
+            saved
+                .to_sw(now + later, sys_now + later)
+                .elapsed_at(now + later),
+            Duration::from_secs(15)
+        );
+    }
+
+    #[test]
+    fn dir_key() {
+        let short = Saved::path_for_dir(Path::new("/home/me/project")).unwrap();
+        let file = short.file_name().unwrap().to_str().unwrap();
+        assert_eq!(file, "cwd-%2Fhome%2Fme%2Fproject.state");
+
+        let deep = "/very-long-directory-name".repeat(20);
+        let a = Saved::path_for_dir(Path::new(&deep)).unwrap();
+        let b = Saved::path_for_dir(Path::new(&(deep + "2"))).unwrap();
+        assert_ne!(a, b);
+        assert!(a.file_name().unwrap().len() <= 210);
+    }
+
+    #[test]
+    fn migrate() {
+        let loaded = Saved::decode("version 1\nelapsed 5.000000000\nfoo bar\n", "work").unwrap();
+        assert_eq!(loaded.saved.name, "work");
+        assert_eq!(loaded.saved.sw.elapsed, Duration::from_secs(5));
+        assert_eq!(loaded.migrated.len(), 3);
+        assert_eq!(loaded.saved.scheduled, None);
+
+        assert!(Saved::decode("version 999\n", "").is_err());
+        assert!(Saved::decode("elapsed 1.0\n", "").is_err());
+    }
+
+    #[test]
+    fn fractions() {
+        let elapsed = |stamp: &str| {
+            Saved::decode(&format!("version 1\nelapsed {stamp}\n"), "")
+                .map(|loaded| loaded.saved.sw.elapsed)
+        };
+        assert_eq!(elapsed("1.5"), Ok(Duration::from_millis(1500)));
+        assert_eq!(elapsed("1.000000005"), Ok(Duration::new(1, 5)));
+        assert_eq!(elapsed("2"), Ok(Duration::from_secs(2)));
+        assert!(elapsed("1.").is_err());
+        assert!(elapsed("1.0000000001").is_err());
+        assert!(elapsed("1.+5").is_err());
+    }
+}
+
+mod completions {
+    use crate::completions::{Flag, Usage};
+
+    #[test]
+    fn usage() {
+        const HELP: &str = "Usage: sw [-v] [--color <color>] [<name>] [<command>] [<args>]
+
+Terminal stopwatch that runs as a shell.
+
+Options:
+  -v, --no-visual-cues
+                    disable text-based graphics and visual cues
+  --color           when to use colors in output: \"auto\", \"always\", or
+                    \"never\"
+  --help, help      display usage information
+
+Commands:
+  start             start the persisted stopwatch
+";
+        let usage = Usage::parse(HELP);
+        assert_eq!(
+            usage.flags,
+            [
+                Flag {
+                    short: Some('v'),
+                    long: "no-visual-cues",
+                    value: false,
+                    description: "disable text-based graphics and visual cues".to_string(),
+                },
+                Flag {
+                    short: None,
+                    long: "color",
+                    value: true,
+                    description: "when to use colors in output: \"auto\", \"always\", or \"never\""
+                        .to_string(),
+                },
+                Flag {
+                    short: None,
+                    long: "help",
+                    value: false,
+                    description: "display usage information".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            usage.commands,
+            [("start", "start the persisted stopwatch".to_string())]
+        );
+    }
+}
+
+mod countdown {
+    use crate::countdown::{Countdown, Timer};
+    use core::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    fn progress() {
+        let start = Instant::now();
+        let countdown = Countdown::new_started_at(Duration::from_secs(8), start);
+        assert_eq!(countdown.permille_at(start), 0);
+        assert_eq!(countdown.permille_at(start + Duration::from_secs(2)), 250);
+        assert_eq!(countdown.permille_at(start + Duration::from_secs(9)), 1000);
+        let zero = Countdown::new_started_at(Duration::ZERO, start);
+        assert_eq!(zero.permille_at(start), 1000);
+    }
+
+    #[test]
+    fn timer() {
+        let start = Instant::now();
+        let secs = |secs| start + Duration::from_secs(secs);
+        let mut timer = Timer::new();
+        assert!(timer.start_at(Duration::from_secs(10), start).is_none());
+        assert_eq!(timer.due(), Some(secs(10)));
+
+        // paused for 5 seconds, then given 3 more
+        let countdown = timer.countdown_mut().unwrap();
+        assert!(countdown.pause_at(secs(4)));
+        assert!(!countdown.pause_at(secs(5)));
+        assert!(countdown.resume_at(secs(9)));
+        assert!(countdown.extend(Duration::from_secs(3)));
+        assert_eq!(timer.due(), Some(secs(18)));
+
+        assert_eq!(timer.overtime_at(secs(17)), None);
+        assert!(timer.check(secs(17)).is_none());
+        assert!(timer.check(secs(19)).is_some());
+        assert!(timer.countdown().is_none());
+        assert_eq!(timer.due(), Some(secs(18)));
+        assert_eq!(timer.overtime_at(secs(19)), Some(Duration::from_secs(1)));
+        assert_eq!(timer.acknowledge(secs(20)), Some(Duration::from_secs(2)));
+        assert_eq!(timer.due(), None);
+    }
+}
+
+mod daytime {
+    use crate::daytime::{strip, TimeOfDay};
+    use core::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn since() {
+        let hm = |hours: u64, mins: u64| Duration::from_secs(hours * 3600 + mins * 60);
+        assert_eq!(strip("since 9:15", "since"), Some("9:15"));
+        assert_eq!(strip("@ 09:15", "since"), Some("09:15"));
+        assert_eq!(strip("9:15", "since"), None);
+        assert_eq!(strip("sincere", "since"), None);
+
+        let time = TimeOfDay::parse("9:15").unwrap();
+        assert_eq!(time.to_string(), "09:15");
+        assert_eq!(time.since(hm(10, 0)), hm(0, 45));
+        // later than now, so yesterday
+        assert_eq!(time.since(hm(8, 0)), hm(22, 45));
+        assert_eq!(
+            TimeOfDay::parse("17:30:05").unwrap().to_string(),
+            "17:30:05"
+        );
+        assert_eq!(time.until(hm(8, 0)), hm(1, 15));
+        // earlier than now, so tomorrow
+        assert_eq!(time.until(hm(10, 0)), hm(23, 15));
+        assert_eq!(time.until(hm(9, 15)), hm(24, 0));
+        for invalid in ["24:00", "9", "9:5", "9:15:", "-1:00", "9:15:00:00", "917"] {
+            assert_eq!(TimeOfDay::parse(invalid), None, "{invalid}");
+        }
+
+        // an hour ahead of UTC, 23:30 UTC is 00:30
+        let now = UNIX_EPOCH + Duration::from_secs(23 * 3600 + 30 * 60);
+        assert_eq!(TimeOfDay::local_now(now, 3600), hm(0, 30));
+        assert_eq!(TimeOfDay::local_now(now, -3600), hm(22, 30));
+    }
+}
+
+mod events {
+    use crate::events::{Event, Events};
+    use core::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn tally() {
+        let mut events = Events::new();
+        for name in ["start", "lap", "stop", "lap", "start"] {
+            events.push(Event::new(name, Duration::ZERO, false, UNIX_EPOCH));
+        }
+        assert_eq!(events.tally(1), [("lap", 2), ("stop", 1), ("start", 1)]);
+        assert_eq!(events.tally(5), []);
+    }
+}
+
+mod export {
+    use crate::events::Event;
+    use crate::export::{
+        civil_from_days, commands, crc32, csv, json, timeclock, timewarrior, Snapshot, Verification,
+    };
+    use crate::laps::Lap;
+    use core::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn rows() {
+        let wall = UNIX_EPOCH + Duration::new(1_704_164_645, 678_000_000);
+        let start = Event::new("start", Duration::ZERO, true, wall);
+        let lap = Event {
+            lap: Some(1),
+            duration: Some(Duration::from_millis(83_250)),
+            value: Some(156.0),
+            ..Event::new("lap", Duration::from_millis(83_250), true, wall)
+        };
+        assert_eq!(
+            csv(&[start, lap]),
+            "timestamp,event,elapsed,lap,duration,value\r\n\
+             2024-01-02T03:04:05.678Z,start,0.000000000,,,\r\n\
+             2024-01-02T03:04:05.678Z,lap,83.250000000,1,83.250000000,156\r\n"
+        );
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert!(Verification(b"123456789")
+            .to_string()
+            .ends_with(", format 1, crc32 cbf43926"));
+    }
+
+    #[test]
+    fn intervals() {
+        let at = |secs: u64, name, running| {
+            let wall = UNIX_EPOCH + Duration::from_secs(1_704_164_645 + secs);
+            Event::new(name, Duration::ZERO, running, wall)
+        };
+        let events = [
+            at(0, "stop", false),
+            at(60, "start", true),
+            at(90, "lap", true),
+            at(120, "stop", false),
+            at(180, "sequence", true),
+            at(240, "reset", false),
+            at(300, "start", true),
+        ];
+        assert_eq!(
+            timeclock(&events, " client  work ", &|_| 3600),
+            "i 2024/01/02 04:05:05 client work\n\
+             o 2024/01/02 04:06:05\n\
+             i 2024/01/02 04:07:05 client work\n\
+             o 2024/01/02 04:08:05\n\
+             i 2024/01/02 04:09:05 client work\n"
+        );
+        assert_eq!(
+            timeclock(&events[..2], "", &|_| -7200),
+            "i 2024/01/02 01:05:05 sw\n"
+        );
+        assert_eq!(
+            timewarrior(&events[..5], " client work ", &["billable".to_string()]),
+            "[{\"start\":\"20240102T030505Z\",\"end\":\"20240102T030605Z\",\
+             \"tags\":[\"client work\",\"billable\"]},\
+             {\"start\":\"20240102T030705Z\",\"tags\":[\"client work\",\"billable\"]}]\n"
+        );
+        assert_eq!(timewarrior(&events[..1], "", &[]), "[]\n");
+    }
+
+    #[test]
+    fn running_state() {
+        let at = |secs: u64, name, running| {
+            let wall = UNIX_EPOCH + Duration::from_secs(1_704_164_645 + secs);
+            Event::new(name, Duration::ZERO, running, wall)
+        };
+        // changing the elapsed time leaves it running, undoing a stop starts
+        // it again, and undoing that start stops it
+        let events = [
+            at(0, "start", true),
+            at(60, "change", true),
+            at(120, "offset", true),
+            at(180, "stop", false),
+            at(240, "undo", true),
+            at(300, "undo", false),
+            at(360, "unreset", false),
+        ];
+        assert_eq!(
+            timewarrior(&events, "", &[]),
+            "[{\"start\":\"20240102T030405Z\",\"end\":\"20240102T030705Z\",\"tags\":[]},\
+             {\"start\":\"20240102T030805Z\",\"end\":\"20240102T030905Z\",\"tags\":[]}]\n"
+        );
+        // a lap of a stopwatch already running when the session began
+        assert_eq!(timewarrior(&[at(0, "lap", true)], "", &[]), "[]\n");
+
+        // each time is offset as it was then, such as across a change to
+        // daylight saving time
+        let dst = |time| {
+            if time < UNIX_EPOCH + Duration::from_secs(1_704_164_645 + 100) {
+                3600
+            } else {
+                7200
+            }
+        };
+        assert_eq!(
+            timeclock(&events[..4], "", &dst),
+            "i 2024/01/02 04:04:05 sw\n\
+             o 2024/01/02 05:07:05\n"
+        );
+    }
+
+    #[test]
+    fn state() {
+        let laps = [
+            Lap {
+                split: Duration::from_millis(41_500),
+                time: Duration::from_millis(41_500),
+                value: None,
+                label: None,
+            },
+            Lap {
+                split: Duration::from_millis(83_250),
+                time: Duration::from_millis(41_750),
+                value: Some(156.0),
+                label: Some("second \"try\"".to_string()),
+            },
+        ];
+        let snapshot = Snapshot {
+            name: "say \"hi\"\n\u{1}",
+            tags: &["meeting".to_string()],
+            elapsed: Duration::from_millis(83_250),
+            running: true,
+            precision: 2,
+            laps: &laps,
+            overtime: Some(Duration::from_millis(1_500)),
+            clock: "wall",
+        };
+        let out = json(&snapshot);
+        let (object, checksum) = out.split_at(out.find(",\"checksum\":").unwrap());
+        assert_eq!(
+            checksum,
+            format!(",\"checksum\":\"{}\"}}", Verification(object.as_bytes()))
+        );
+        assert_eq!(
+            object,
+            "{\"name\":\"say \\\"hi\\\"\\n\\u0001\",\"tags\":[\"meeting\"],\"elapsed\":83.250000000,\
+             \"display\":\"00:01:23.25\",\"running\":true,\"precision\":2,\"laps\":[\
+             {\"split\":41.500000000,\"time\":41.500000000,\"value\":null,\"label\":null},\
+             {\"split\":83.250000000,\"time\":41.750000000,\"value\":156,\
+             \"label\":\"second \\\"try\\\"\"}],\
+             \"overtime\":1.500000000,\"clock\":\"wall\""
+        );
+    }
+
+    #[test]
+    fn command_list() {
+        let list = commands();
+        assert!(list.starts_with(
+            "[{\"name\":\"help\",\"short\":\"h\",\"argument\":null,\"description\":\"show help\"},\
+             {\"name\":\"display\",\"short\":\"\",\"argument\":null,"
+        ));
+        assert!(
+            list.contains("{\"name\":\"lap\",\"short\":\"a\",\"argument\":\"[value] [label]\",")
+        );
+        assert!(list.ends_with("\"description\":\"Abandon all Data\"}]"));
+    }
+}
+
+mod plumbing {
+    use crate::command::Command;
+    use crate::plumbing::run;
+    use core::time::Duration;
+    use libsw_core::Sw;
+    use std::time::Instant;
+
+    #[test]
+    fn get_and_set() {
+        let now = Instant::now();
+        let mut sw = Sw::with_elapsed(Duration::from_millis(1500));
+        assert_eq!(run(":version", &mut sw, now), Ok(("1".to_string(), None)));
+        assert_eq!(
+            run(":get elapsed-ns", &mut sw, now),
+            Ok(("1500000000".to_string(), None))
+        );
+        let (answer, change) = run(":set running true", &mut sw, now).unwrap();
+        assert_eq!(answer, "ok");
+        assert_eq!(change.map(|change| change.event), Some("start"));
+        let (_, change) = run(":set elapsed-ns 42", &mut sw, now).unwrap();
+        assert_eq!(change.map(|change| change.command), Some(Command::Change));
+        assert!(sw.is_running());
+        assert_eq!(sw.elapsed_at(now), Duration::from_nanos(42));
+        assert_eq!(
+            run(":set running true", &mut sw, now),
+            Ok(("ok".to_string(), None))
+        );
+        assert!(run(":set elapsed-ns -1", &mut sw, now).is_err());
+        assert!(run(":get elapsed", &mut sw, now).is_err());
+        assert!(run(":version 2", &mut sw, now).is_err());
+    }
+}
+
+mod command {
+    use crate::command::Command;
+
+    #[test]
+    fn from_key() {
+        assert_eq!(Command::from_key("s"), Some(Command::Toggle));
+        assert_eq!(Command::from_key("R"), Some(Command::Reset));
+        assert_eq!(Command::from_key("q"), Some(Command::Quit));
+        assert_eq!(Command::from_key(""), None);
+        assert_eq!(Command::from_key("la"), None);
+        assert_eq!(Command::from_key(" s"), None);
+    }
+}
+
+mod usage {
+    use crate::usage::Usage;
+
+    #[test]
+    fn insights() {
+        let mut usage = Usage::default();
+        for _ in 0..4 {
+            usage.record("Toggle", "");
+            usage.record("o", "+5m");
+        }
+        usage.record("s", "");
+        usage.record("", "");
+        assert_eq!(Usage::parse(&usage.serialize()), usage);
+        assert_eq!(
+            usage.insights(),
+            [
+                "most used: s (5), o (4), <Enter> (1)",
+                "you often answer 'o' with '+5m' (4 times); '!1' at its prompt repeats your \
+                 last answer, as does enter with prompt.defaults",
+                "you typed 'toggle' 4 times, and 's' does the same",
+            ]
+        );
+        assert!(Usage::default().insights().is_empty());
+    }
+}
+
+mod fmt {
+    use crate::config;
+    use crate::precision::{AutoPrecision, Precision, Rounding};
+    use crate::state::{DisplayStyle, DurationFmt};
+    use core::time::Duration;
+
+    #[test]
+    fn days() {
+        let dur = Duration::from_secs(93784);
+        assert_eq!(DurationFmt::new(dur, 0, true).to_string(), "1:02:03:04");
+        assert_eq!(
+            DurationFmt::new(dur, 0, false).to_string(),
+            "1 day, 2 hours, 3 minutes, 4 seconds"
+        );
+        let dur = Duration::from_secs(23 * 3600);
+        assert_eq!(DurationFmt::new(dur, 1, true).to_string(), "23:00:00.0");
+    }
+
+    #[test]
+    fn auto_precision() {
+        let auto = Precision::Auto(AutoPrecision::default());
+        let scaled = |millis| DurationFmt::scaled(Duration::from_millis(millis), auto, true);
+        assert_eq!(scaled(12_345).to_string(), "00:00:12.345");
+        assert_eq!(scaled(60_000).to_string(), "00:01:00.00");
+        assert_eq!(scaled(3_599_999).to_string(), "00:59:59.99");
+        assert_eq!(scaled(3_600_500).to_string(), "01:00:00");
+        assert_eq!(auto.to_string(), "auto");
+    }
+    #[test]
+    fn rounding() {
+        let fmt = |millis, prec, rounding| {
+            DurationFmt::new(Duration::from_millis(millis), prec, true)
+                .styled(DisplayStyle {
+                    rounding,
+                    ..DisplayStyle::default()
+                })
+                .to_string()
+        };
+        assert_eq!(fmt(4_990, 1, Rounding::Truncate), "00:00:04.9");
+        assert_eq!(fmt(4_990, 1, Rounding::HalfUp), "00:00:05.0");
+        assert_eq!(fmt(59_999, 2, Rounding::HalfUp), "00:01:00.00");
+        assert_eq!(fmt(250, 1, Rounding::HalfUp), "00:00:00.3");
+        assert_eq!(fmt(250, 1, Rounding::HalfEven), "00:00:00.2");
+        assert_eq!(fmt(350, 1, Rounding::HalfEven), "00:00:00.4");
+        assert_eq!(fmt(1_500, 0, Rounding::HalfEven), "00:00:02");
+        assert_eq!("half_up".parse(), Ok(Rounding::HalfUp));
+        assert!("nearest".parse::<Rounding>().is_err());
+    }
+
+    #[test]
+    fn style() {
+        let style = DisplayStyle {
+            hide_zero_hours: true,
+            separator: Some(','),
+            ..DisplayStyle::default()
+        };
+        let fmt = |secs, visual_cues| {
+            DurationFmt::new(Duration::from_secs(secs), 0, visual_cues)
+                .styled(style)
+                .to_string()
+        };
+        assert_eq!(fmt(245, true), "04:05");
+        assert_eq!(fmt(3845, true), "01:04:05");
+        assert_eq!(fmt(1_234 * 86_400 + 5, true), "1,234:00:00:05");
+        assert_eq!(fmt(1_234 * 86_400, false), "1,234 days, 0 seconds");
+        assert_eq!(fmt(999 * 86_400, true), "999:00:00:00");
+        assert_eq!(config::separator_for("de_DE.UTF-8"), '.');
+        assert_eq!(config::separator_for("fr_FR"), '\u{a0}');
+        assert_eq!(config::separator_for("C"), ',');
+    }
+}
+
 mod parse {
     mod frac {
         use crate::parse::{parse_frac, ParseFracErr};
@@ -31,6 +1780,125 @@ mod parse {
         }
     }
 
+    mod span {
+        use crate::parse::ByteSpan;
+
+        fn columns(s: &str, target: &str) -> (usize, usize) {
+            let start = s.find(target).unwrap();
+            ByteSpan::new(start, target.len(), s).caret_columns()
+        }
+
+        #[test]
+        fn ascii() {
+            assert_eq!(columns("12x:3", "x"), (2, 1));
+            assert_eq!(columns("12x:3", "12x"), (0, 3));
+        }
+
+        #[test]
+        fn wide() {
+            assert_eq!(columns("１２x", "x"), (4, 1));
+            assert_eq!(columns("1🪴s", "🪴"), (1, 2));
+        }
+
+        #[test]
+        fn combining() {
+            // 'e' followed by a combining acute accent occupies one column
+            assert_eq!(columns("e\u{301}1x", "x"), (2, 1));
+            assert_eq!(columns("1e\u{301}s", "e\u{301}"), (1, 1));
+            // span covering only the combining mark underlines its grapheme
+            assert_eq!(columns("1e\u{301}s", "\u{301}"), (1, 1));
+        }
+
+        #[test]
+        fn zwj() {
+            const FAMILY: &str = "👨\u{200d}👩\u{200d}👧";
+            let s = format!("{FAMILY}x");
+            assert_eq!(columns(&s, "x"), (2, 1));
+            assert_eq!(columns(&s, FAMILY), (0, 2));
+            // span starting within the sequence
+            assert_eq!(columns(&s, "👩"), (0, 2));
+
+            let s = "🇺🇸1x";
+            assert_eq!(columns(s, "x"), (3, 1));
+        }
+    }
+
+    #[test]
+    fn escaped() {
+        use crate::parse::ByteSpan;
+        use crate::shell::Escaped;
+
+        assert_eq!(Escaped("plain").to_string(), "plain");
+        assert_eq!(
+            Escaped("a\x1b[31mb\u{202e}").to_string(),
+            "a\\u{1b}[31mb\\u{202e}"
+        );
+        assert_eq!(Escaped("\x07").width(), 5);
+        assert_eq!(Escaped("stopwatch").clip(9).to_string(), "stopwatch");
+        assert_eq!(Escaped("stopwatch").clip(5).to_string(), "stop\u{2026}");
+        assert_eq!(Escaped("日本語").clip(4).to_string(), "日\u{2026}");
+        assert_eq!(
+            Escaped("e\u{301}e\u{301}e").clip(2).to_string(),
+            "e\u{301}\u{2026}"
+        );
+        // carets line up with the escaped input
+        let s = "\x1b1x";
+        assert_eq!(ByteSpan::new(2, 1, s).caret_columns(), (7, 1));
+    }
+
+    mod digits {
+        use crate::parse::{decimal_digit, normalize_digits, parse_frac, ReadDur};
+        use crate::precision::Spec;
+        use core::time::Duration;
+
+        #[test]
+        fn decimal() {
+            assert_eq!(decimal_digit('0'), Some(0));
+            assert_eq!(decimal_digit('9'), Some(9));
+            assert_eq!(decimal_digit('٣'), Some(3)); // arabic-indic
+            assert_eq!(decimal_digit('۷'), Some(7)); // extended arabic-indic
+            assert_eq!(decimal_digit('५'), Some(5)); // devanagari
+            assert_eq!(decimal_digit('９'), Some(9)); // fullwidth
+            assert_eq!(decimal_digit('a'), None);
+            assert_eq!(decimal_digit('/'), None);
+            assert_eq!(decimal_digit('Ⅳ'), None); // numeric, but not decimal
+        }
+
+        #[test]
+        fn normalize() {
+            assert_eq!(normalize_digits("12"), "12");
+            assert_eq!(normalize_digits("١٢"), "12");
+            assert_eq!(normalize_digits("x٣y"), "x3y");
+        }
+
+        #[test]
+        fn parse() {
+            assert_eq!(parse_frac("٥", 2), Ok(50));
+            assert_eq!(
+                ReadDur::parse("١٢s", false),
+                Some(Ok(ReadDur {
+                    dur: Duration::from_secs(12),
+                    is_neg: false,
+                }))
+            );
+            assert_eq!(
+                ReadDur::parse("١:٣٠.٥", false),
+                Some(Ok(ReadDur {
+                    dur: Duration::from_millis(90_500),
+                    is_neg: false,
+                }))
+            );
+            assert_eq!(
+                ReadDur::parse("١.٥::", false),
+                Some(Ok(ReadDur {
+                    dur: Duration::from_secs(5400),
+                    is_neg: false,
+                }))
+            );
+            assert_eq!("٣".parse::<Spec>(), Ok(Spec::Fixed(3)));
+        }
+    }
+
     mod short {
         // TODO: test short format
 
@@ -51,6 +1919,97 @@ mod parse {
             assert_eq!(ReadDur::parse_as_short("1 .s", true), expect);
         }
 
+        #[test]
+        fn days() {
+            assert_eq!(
+                ReadDur::parse_as_short("1d", false),
+                Ok(ReadDur {
+                    dur: Duration::from_secs(86400),
+                    is_neg: false,
+                })
+            );
+            assert_eq!(
+                ReadDur::parse_as_short("1.5 d", false),
+                Ok(ReadDur {
+                    dur: Duration::from_secs(129_600),
+                    is_neg: false,
+                })
+            );
+        }
+
+        #[test]
+        fn compound() {
+            let expect = |secs, nanos, is_neg| {
+                Some(Ok(ReadDur {
+                    dur: Duration::new(secs, nanos),
+                    is_neg,
+                }))
+            };
+            assert_eq!(
+                ReadDur::parse("1h 30m 15.5s", false),
+                expect(5415, 500_000_000, false)
+            );
+            assert_eq!(ReadDur::parse("1h30m", false), expect(5400, 0, false));
+            assert_eq!(ReadDur::parse(" 2 m  3 s ", false), expect(123, 0, false));
+            assert_eq!(ReadDur::parse("-1d 1h", true), expect(90000, 0, true));
+            assert_eq!(
+                ReadDur::parse("1 hour 30mins", false),
+                expect(5400, 0, false)
+            );
+            assert_eq!(ReadDur::parse("2 days", false), expect(172_800, 0, false));
+            assert_eq!(
+                ReadDur::parse("1hr 1min 1.5secs", false),
+                expect(3661, 500_000_000, false)
+            );
+        }
+
+        #[test]
+        fn unit_suggestions() {
+            assert_eq!(Unit::similar("secnds"), Some(Unit::Second));
+            assert_eq!(Unit::similar("Mins"), Some(Unit::Minute));
+            assert_eq!(Unit::similar("hous"), Some(Unit::Hour));
+            assert_eq!(Unit::similar("dy"), Some(Unit::Day));
+            assert_eq!(Unit::similar("x"), None);
+        }
+
+        #[test]
+        fn compound_errors() {
+            use crate::parse::short::ShortErrKind;
+
+            let input = "1h 30x";
+            assert_eq!(
+                ReadDur::parse(input, true),
+                Some(Err(ParseErr::new(
+                    ByteSpan::new(5, 1, input),
+                    ShortErrKind::UnitUnknown("x")
+                )))
+            );
+            let input = "1h -30m";
+            assert_eq!(
+                ReadDur::parse(input, true),
+                Some(Err(ParseErr::new(
+                    ByteSpan::new(3, 1, input),
+                    ShortErrKind::SignNotFirst
+                )))
+            );
+            let input = "1h 5sek";
+            assert_eq!(
+                ReadDur::parse(input, true),
+                Some(Err(ParseErr::new(
+                    ByteSpan::new(4, 3, input),
+                    ShortErrKind::UnitUnknown("sek")
+                )))
+            );
+            let input = "1h 2m 3q 4s";
+            assert_eq!(
+                ReadDur::parse(input, true),
+                Some(Err(ParseErr::new(
+                    ByteSpan::new(7, 1, input),
+                    ShortErrKind::UnitUnknown("q")
+                )))
+            );
+        }
+
         #[test]
         fn overflow_bug() {
             assert_eq!(
@@ -63,6 +2022,80 @@ mod parse {
         }
     }
 
+    mod iso {
+        use crate::parse::iso::IsoErrKind;
+        use crate::parse::*;
+        use core::time::Duration;
+
+        fn dur(secs: u64, nanos: u32, is_neg: bool) -> ReadDur {
+            ReadDur {
+                dur: Duration::new(secs, nanos),
+                is_neg,
+            }
+        }
+
+        #[test]
+        fn basic() {
+            assert_eq!(
+                ReadDur::parse("PT1H30M", false).unwrap(),
+                Ok(dur(5400, 0, false))
+            );
+            assert_eq!(
+                ReadDur::parse("PT0.5S", false).unwrap(),
+                Ok(dur(0, 500_000_000, false))
+            );
+            assert_eq!(
+                ReadDur::parse(" P1DT2S ", false).unwrap(),
+                Ok(dur(86402, 0, false))
+            );
+            assert_eq!(
+                ReadDur::parse("P1W", false).unwrap(),
+                Ok(dur(604_800, 0, false))
+            );
+            assert_eq!(
+                ReadDur::parse("pt1,5m", false).unwrap(),
+                Ok(dur(90, 0, false))
+            );
+            assert_eq!(ReadDur::parse("-PT1M", true).unwrap(), Ok(dur(60, 0, true)));
+        }
+
+        #[test]
+        fn errors() {
+            fn err(
+                input: &'static str,
+                start: usize,
+                len: usize,
+                kind: IsoErrKind<'static>,
+            ) -> Result<ReadDur, ParseErr<'static>> {
+                Err(ParseErr::new(ByteSpan::new(start, len, input), kind))
+            }
+            assert_eq!(
+                ReadDur::parse("P1M", false).unwrap(),
+                err("P1M", 2, 1, IsoErrKind::VariableLength("M"))
+            );
+            assert_eq!(
+                ReadDur::parse("PT1M1H", false).unwrap(),
+                err("PT1M1H", 5, 1, IsoErrKind::DesignatorOrder)
+            );
+            assert_eq!(
+                ReadDur::parse("PT1.5H30M", false).unwrap(),
+                err("PT1.5H30M", 3, 1, IsoErrKind::FracNotLast)
+            );
+            assert_eq!(
+                ReadDur::parse("P", false).unwrap(),
+                err("P", 0, 1, IsoErrKind::Empty)
+            );
+            assert_eq!(
+                ReadDur::parse("P1DT", false).unwrap(),
+                err("P1DT", 3, 1, IsoErrKind::TimeEmpty)
+            );
+            assert_eq!(
+                ReadDur::parse("PT5", false).unwrap(),
+                err("PT5", 2, 1, IsoErrKind::DesignatorMissing)
+            );
+        }
+    }
+
     mod long {
         // TODO: test subsecond parsing
 
@@ -116,6 +2149,71 @@ mod parse {
             test(runs.into_iter());
         }
 
+        #[test]
+        fn fractional_groups() {
+            let runs: [(&[&'static str], Result<ReadDur, ParseErr<'static>>); 3] = [
+                (
+                    &["1.5:", "1.5:0", "1:30", ".5:60"],
+                    Ok(ReadDur {
+                        dur: Duration::from_secs(90),
+                        is_neg: false,
+                    }),
+                ),
+                (
+                    &["1.5::", "1:30:", "1.25:15:"],
+                    Ok(ReadDur {
+                        dur: Duration::from_secs(5400),
+                        is_neg: false,
+                    }),
+                ),
+                (
+                    &["0.000000000001::"],
+                    Ok(ReadDur {
+                        dur: Duration::from_nanos(3),
+                        is_neg: false,
+                    }),
+                ),
+            ];
+            test(runs.into_iter());
+            let input = "1.2.3:";
+            assert_eq!(
+                ReadDur::parse_as_long(input, true),
+                Err(ParseErr::new(
+                    ByteSpan::new(1, 1, input),
+                    LongErrKind::UnexpectedDot(Group::Minutes)
+                ))
+            );
+        }
+
+        #[test]
+        fn days() {
+            let runs: [(&[&'static str], Result<ReadDur, ParseErr<'static>>); 2] = [
+                (
+                    &["1:02:03:04", "1:2:3:4", " 1 : 2 : 3 : 4 "],
+                    Ok(ReadDur {
+                        dur: Duration::from_secs(93784),
+                        is_neg: false,
+                    }),
+                ),
+                (
+                    &["1:::", "1:0:0:0", "0:24::"],
+                    Ok(ReadDur {
+                        dur: Duration::from_secs(86400),
+                        is_neg: false,
+                    }),
+                ),
+            ];
+            test(runs.into_iter());
+
+            assert_eq!(
+                ReadDur::parse_as_long("1::::", true),
+                Err(ParseErr::new(
+                    ByteSpan::new(1, 1, "1::::"),
+                    LongErrKind::UnexpectedColon
+                ))
+            );
+        }
+
         #[test]
         fn zero_dur_corner_cases() {
             let runs: [(&[&'static str], Result<ReadDur, ParseErr<'static>>); 2] = [
@@ -0,0 +1,175 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use termcolor::Color;
+
+use core::time::Duration;
+
+use crate::parse::ReadDur;
+
+/// Colors of the shell's messages. `None` leaves the terminal's own color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Messages about something which changed, such as "started stopwatch".
+    pub info_change: Option<Color>,
+    /// Messages which change nothing, such as "no alarms set".
+    pub info_idle: Option<Color>,
+    pub warn: Option<Color>,
+    pub error: Option<Color>,
+    /// Prompts asked partway through a command.
+    pub prompt: Option<Color>,
+    /// Fastest lap in the lap list.
+    pub fastest: Option<Color>,
+    /// Slowest lap in the lap list.
+    pub slowest: Option<Color>,
+    /// Running stopwatches in the summary.
+    pub running: Option<Color>,
+    /// Stopped stopwatches in the summary.
+    pub stopped: Option<Color>,
+}
+
+impl Theme {
+    pub const DEFAULT: Self = Self {
+        info_change: Some(Color::Magenta),
+        info_idle: Some(Color::Cyan),
+        warn: Some(Color::Yellow),
+        error: Some(Color::Red),
+        prompt: Some(Color::Blue),
+        fastest: Some(Color::Green),
+        slowest: Some(Color::Red),
+        running: Some(Color::Green),
+        stopped: Some(Color::Yellow),
+    };
+
+    /// Themes which can be chosen by name.
+    pub const PRESETS: [(&'static str, Self); 3] = [
+        ("default", Self::DEFAULT),
+        (
+            "mono",
+            Self {
+                info_change: None,
+                info_idle: None,
+                warn: None,
+                error: None,
+                prompt: None,
+                fastest: None,
+                slowest: None,
+                running: None,
+                stopped: None,
+            },
+        ),
+        (
+            "pastel",
+            Self {
+                info_change: Some(Color::Rgb(0xd7, 0xaf, 0xff)),
+                info_idle: Some(Color::Rgb(0xaf, 0xd7, 0xff)),
+                warn: Some(Color::Rgb(0xff, 0xd7, 0x87)),
+                error: Some(Color::Rgb(0xff, 0x87, 0x87)),
+                prompt: Some(Color::Rgb(0x87, 0xaf, 0xd7)),
+                fastest: Some(Color::Rgb(0xaf, 0xff, 0xaf)),
+                slowest: Some(Color::Rgb(0xff, 0x87, 0x87)),
+                running: Some(Color::Rgb(0xaf, 0xff, 0xaf)),
+                stopped: Some(Color::Rgb(0xff, 0xd7, 0x87)),
+            },
+        ),
+    ];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, theme)| *theme)
+    }
+
+    /// Color of the messages called `role` in the configuration file.
+    pub fn role_mut(&mut self, role: &str) -> Option<&mut Option<Color>> {
+        match role {
+            "info_change" => Some(&mut self.info_change),
+            "info_idle" => Some(&mut self.info_idle),
+            "warn" => Some(&mut self.warn),
+            "error" => Some(&mut self.error),
+            "prompt" => Some(&mut self.prompt),
+            "fastest" => Some(&mut self.fastest),
+            "slowest" => Some(&mut self.slowest),
+            "running" => Some(&mut self.running),
+            "stopped" => Some(&mut self.stopped),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Colors of the elapsed time by how long it has been running, such as green
+/// under 25 minutes, yellow under 50, and red after.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Colors used below each duration, which increase.
+    below: Vec<(Duration, Option<Color>)>,
+    /// Color used once every duration has passed.
+    beyond: Option<Color>,
+}
+
+impl Thresholds {
+    /// Parses thresholds such as `green < 25m, yellow < 50m, red`, where the
+    /// last color, which has no duration, is optional. Returns `None` if a
+    /// color or duration is invalid, or the durations don't increase.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut thresholds = Self::default();
+        let mut items = s.split(',').map(str::trim).peekable();
+        while let Some(item) = items.next() {
+            let Some((color, below)) = item.split_once('<') else {
+                // only the last color may go without a duration
+                if items.peek().is_some() {
+                    return None;
+                }
+                thresholds.beyond = parse_color(item)?;
+                break;
+            };
+            let Some(Ok(ReadDur { dur: below, .. })) = ReadDur::parse(below.trim(), false) else {
+                return None;
+            };
+            if thresholds
+                .below
+                .last()
+                .is_some_and(|(last, _)| *last >= below)
+            {
+                return None;
+            }
+            thresholds.below.push((below, parse_color(color.trim())?)); // @alloc
+        }
+        Some(thresholds)
+    }
+
+    /// Color of the elapsed time once `elapsed` has passed, or `None` for the
+    /// terminal's own color.
+    pub fn color_at(&self, elapsed: Duration) -> Option<Color> {
+        self.below
+            .iter()
+            .find(|(below, _)| elapsed < *below)
+            .map_or(self.beyond, |(_, color)| *color)
+    }
+}
+
+/// Parses a color, which is either the name of an ANSI color such as `red`,
+/// an ANSI 256-color number, an RGB triple such as `255,135,0` or `#ff8700`,
+/// or `none` for the terminal's own color.
+#[allow(clippy::option_option)] // since no color is a valid choice
+pub fn parse_color(s: &str) -> Option<Option<Color>> {
+    if s == "none" {
+        return Some(None);
+    }
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let byte = |idx| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+        return Some(Some(Color::Rgb(byte(0)?, byte(2)?, byte(4)?)));
+    }
+    s.parse().ok().map(Some)
+}
@@ -0,0 +1,155 @@
+// sw: terminal stopwatch
+// copyright (C) 2022-2023 Ula Shipman <ula.hello@mailbox.org>
+// licensed under GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::command::Command;
+use crate::persist;
+use crate::shell::Escaped;
+
+/// How often commands were run, counted by the name they were typed as and
+/// their argument or answer, such as `o +5m`. This is only kept if enabled,
+/// and never leaves the state directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    counts: BTreeMap<String, u64>,
+}
+
+impl Usage {
+    /// Times something is run before it's worth suggesting a shortcut for.
+    const OFTEN: u64 = 3;
+    /// Number of the most used commands which are listed.
+    const TOP: usize = 5;
+
+    /// Counts a run of the command typed as `word`, with `arg` if it isn't
+    /// empty.
+    pub fn record(&mut self, word: &str, arg: &str) {
+        let mut entry = word.to_lowercase(); // @alloc
+        if !arg.is_empty() {
+            entry.push(' ');
+            entry.push_str(arg);
+        }
+        *self.counts.entry(entry).or_default() += 1;
+    }
+
+    /// Parses counts written by [`Self::serialize`]. Invalid lines are
+    /// skipped.
+    pub fn parse(s: &str) -> Self {
+        let mut usage = Self::default();
+        for line in s.lines() {
+            let Some((count, entry)) = line.split_once('\t') else {
+                continue;
+            };
+            if let Ok(count) = count.parse::<u64>() {
+                let total = usage.counts.entry(entry.to_string()).or_default(); // @alloc
+                *total = total.saturating_add(count);
+            }
+        }
+        usage
+    }
+
+    /// Writes each count and what was run on its own line, separated by a
+    /// tab.
+    pub fn serialize(&self) -> String {
+        let mut out = String::new(); // @alloc
+        for (entry, count) in &self.counts {
+            out.push_str(&count.to_string()); // @alloc
+            out.push('\t');
+            out.push_str(entry);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (entry, count) in &other.counts {
+            let total = self.counts.entry(entry.clone()).or_default(); // @alloc
+            *total = total.saturating_add(*count);
+        }
+    }
+
+    fn path() -> io::Result<PathBuf> {
+        Ok(persist::state_dir()?.join("usage"))
+    }
+
+    /// Reads the counts saved so far. A missing file is the same as an empty
+    /// one.
+    pub fn load() -> io::Result<Self> {
+        match fs::read_to_string(Self::path()?) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Adds these counts to those saved so far, so that other sessions'
+    /// counts are kept.
+    pub fn save(&self) -> io::Result<()> {
+        if self.counts.is_empty() {
+            return Ok(());
+        }
+        let mut total = Self::load()?;
+        total.merge(self);
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, total.serialize())
+    }
+
+    /// Lists the most used commands, followed by suggestions of shortcuts
+    /// for what's run often.
+    pub fn insights(&self) -> Vec<String> {
+        let mut by_count = self.counts.iter().collect::<Vec<_>>(); // @alloc
+        by_count.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut per_command = BTreeMap::<&str, u64>::new();
+        for (entry, count) in &by_count {
+            let word = entry.split(' ').next().unwrap_or_default();
+            if let Ok(cmd) = word.parse::<Command>() {
+                *per_command.entry(cmd.short_name_display()).or_default() += **count;
+            }
+        }
+        let mut top = per_command.into_iter().collect::<Vec<_>>(); // @alloc
+        top.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let mut lines = Vec::new(); // @alloc
+        if top.is_empty() {
+            return lines;
+        }
+        let top = top
+            .iter()
+            .take(Self::TOP)
+            .map(|(name, count)| format!("{name} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", "); // @alloc
+        lines.push(format!("most used: {top}")); // @alloc
+
+        for (entry, count) in by_count {
+            if *count < Self::OFTEN {
+                break;
+            }
+            let (word, arg) = entry.split_once(' ').unwrap_or((entry, ""));
+            let Ok(cmd) = word.parse::<Command>() else {
+                continue;
+            };
+            let short = cmd.short_name_display();
+            if arg.is_empty() && word != cmd.short_name_literal() {
+                lines.push(format!(
+                    "you typed '{}' {count} times, and '{short}' does the same",
+                    Escaped(word)
+                )); // @alloc
+            } else if matches!(cmd, Command::Change | Command::Offset) && !arg.is_empty() {
+                lines.push(format!(
+                    "you often answer '{short}' with '{}' ({count} times); '!1' at its \
+                     prompt repeats your last answer, as does enter with prompt.defaults",
+                    Escaped(arg)
+                )); // @alloc
+            }
+        }
+        lines
+    }
+}